@@ -0,0 +1,114 @@
+use std::ops::Range;
+
+//first fit sub-allocator for handing out slices of the gpu buffers
+//freed ranges are merged with their neighbours and the tail is given back so `end` stays tight
+#[derive(Debug, Default)]
+pub(crate) struct RangeAllocator {
+    free: Vec<Range<u32>>,
+    end: u32,
+    used: u32,
+}
+
+impl RangeAllocator {
+    pub(crate) fn allocate(&mut self, len: u32) -> Range<u32> {
+        if len == 0 {
+            return 0..0;
+        }
+
+        self.used += len;
+
+        for i in 0..self.free.len() {
+            let range = self.free[i].clone();
+
+            if range.len() as u32 >= len {
+                if range.len() as u32 == len {
+                    self.free.remove(i);
+                } else {
+                    self.free[i].start += len;
+                }
+
+                return range.start..range.start + len;
+            }
+        }
+
+        self.end += len;
+        self.end - len..self.end
+    }
+
+    pub(crate) fn free(&mut self, range: Range<u32>) {
+        if range.is_empty() {
+            return;
+        }
+
+        self.used -= range.len() as u32;
+
+        let idx = self.free.partition_point(|free| free.start < range.start);
+        self.free.insert(idx, range);
+
+        if idx + 1 < self.free.len() && self.free[idx].end == self.free[idx + 1].start {
+            self.free[idx].end = self.free[idx + 1].end;
+            self.free.remove(idx + 1);
+        }
+
+        if idx > 0 && self.free[idx - 1].end == self.free[idx].start {
+            self.free[idx - 1].end = self.free[idx].end;
+            self.free.remove(idx);
+        }
+
+        if let Some(last) = self.free.last() {
+            if last.end == self.end {
+                self.end = last.start;
+                self.free.pop();
+            }
+        }
+    }
+
+    //one past the highest element in use, what the gpu buffer has to hold
+    pub(crate) fn end(&self) -> u32 {
+        self.end
+    }
+
+    pub(crate) fn used(&self) -> u32 {
+        self.used
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RangeAllocator;
+
+    #[test]
+    fn test_range_allocator() {
+        let mut allocator = RangeAllocator::default();
+
+        let a = allocator.allocate(4);
+        let b = allocator.allocate(8);
+        let c = allocator.allocate(2);
+
+        assert_eq!(a, 0..4);
+        assert_eq!(b, 4..12);
+        assert_eq!(c, 12..14);
+        assert_eq!(allocator.allocate(0), 0..0);
+
+        allocator.free(b);
+        assert_eq!(allocator.end(), 14);
+        assert_eq!(allocator.used(), 6);
+
+        assert_eq!(allocator.allocate(3), 4..7);
+        assert_eq!(allocator.allocate(5), 7..12);
+        assert_eq!(allocator.allocate(1), 14..15);
+
+        allocator.free(4..7);
+        allocator.free(7..12);
+        allocator.free(0..4);
+        assert_eq!(allocator.allocate(12), 0..12);
+
+        allocator.free(12..14);
+        allocator.free(14..15);
+        assert_eq!(allocator.end(), 12);
+
+        allocator.free(0..12);
+        assert_eq!(allocator.end(), 0);
+        assert_eq!(allocator.used(), 0);
+    }
+}
@@ -0,0 +1,369 @@
+use std::{f32::consts::PI, time::Duration};
+
+use cgmath::Vector2;
+
+use crate::{color::RDColor, scene::RDNode};
+
+//maps the linear progress of a tween onto the progress of its value, both 0 at the start and 1 at the end
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RDEasing {
+    Linear,
+    //the css timing function, the curve runs from (0, 0) through the two control points to (1, 1)
+    CubicBezier(f32, f32, f32, f32),
+    //a unit mass let go of one unit away from rest, time is stretched so it has settled at the end
+    Spring { stiffness: f32, damping: f32 },
+    //overshoots and wobbles into place, period is the length of a wobble in tween time
+    Elastic { period: f32 },
+    //jumps at the end of each of the steps
+    Steps(u32),
+}
+
+impl Default for RDEasing {
+    fn default() -> Self {
+        RDEasing::EASE
+    }
+}
+
+impl RDEasing {
+    pub const EASE: RDEasing = RDEasing::CubicBezier(0.25, 0.1, 0.25, 1.0);
+    pub const EASE_IN: RDEasing = RDEasing::CubicBezier(0.42, 0.0, 1.0, 1.0);
+    pub const EASE_OUT: RDEasing = RDEasing::CubicBezier(0.0, 0.0, 0.58, 1.0);
+    pub const EASE_IN_OUT: RDEasing = RDEasing::CubicBezier(0.42, 0.0, 0.58, 1.0);
+    pub const SPRING: RDEasing = RDEasing::Spring { stiffness: 100.0, damping: 10.0 };
+    pub const ELASTIC: RDEasing = RDEasing::Elastic { period: 0.3 };
+
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+
+        //every curve lands exactly on its ends, springs and elastics only get close on their own
+        if t == 0.0 || t == 1.0 {
+            return t;
+        }
+
+        match *self {
+            RDEasing::Linear => t,
+            RDEasing::CubicBezier(x1, y1, x2, y2) => {
+                let s = solve_bezier(x1, x2, t);
+                bezier(y1, y2, s)
+            }
+            RDEasing::Spring { stiffness, damping } => {
+                let omega = stiffness.max(1e-3).sqrt();
+                //an undamped spring never settles
+                let zeta = (damping / (2.0 * omega)).max(0.01);
+
+                if zeta < 1.0 {
+                    //the envelope has shrunk to a thousandth at the end
+                    let time = t * 1000f32.ln() / (zeta * omega);
+                    let damped = omega * (1.0 - zeta * zeta).sqrt();
+
+                    1.0 - (-zeta * omega * time).exp() * ((damped * time).cos() + zeta * omega / damped * (damped * time).sin())
+                } else {
+                    //anything damped harder is drawn as critically damped
+                    let time = t * 10.0 / omega;
+                    1.0 - (-omega * time).exp() * (1.0 + omega * time)
+                }
+            }
+            RDEasing::Elastic { period } => {
+                let period = period.max(1e-3);
+                2f32.powf(-10.0 * t) * ((t - period / 4.0) * 2.0 * PI / period).sin() + 1.0
+            }
+            RDEasing::Steps(steps) => {
+                let steps = steps.max(1) as f32;
+                (t * steps).floor() / steps
+            }
+        }
+    }
+}
+
+//one coordinate of a bezier whose end points are 0 and 1
+fn bezier(p1: f32, p2: f32, s: f32) -> f32 {
+    let inverse = 1.0 - s;
+    3.0 * inverse * inverse * s * p1 + 3.0 * inverse * s * s * p2 + s * s * s
+}
+
+//the curve parameter where x reaches the given value, newton first and bisection when it stalls
+fn solve_bezier(x1: f32, x2: f32, x: f32) -> f32 {
+    let mut s = x;
+
+    for _ in 0..8 {
+        let error = bezier(x1, x2, s) - x;
+        if error.abs() < 1e-6 {
+            return s;
+        }
+
+        let inverse = 1.0 - s;
+        let slope = 3.0 * inverse * inverse * x1 + 6.0 * inverse * s * (x2 - x1) + 3.0 * s * s * (1.0 - x2);
+        if slope.abs() < 1e-6 {
+            break;
+        }
+
+        s -= error / slope;
+    }
+
+    let (mut low, mut high) = (0.0, 1.0);
+    s = x;
+
+    for _ in 0..32 {
+        if bezier(x1, x2, s) < x {
+            low = s;
+        } else {
+            high = s;
+        }
+        s = (low + high) * 0.5;
+    }
+
+    s
+}
+
+//a node property together with a value for it
+#[derive(Clone, Debug, PartialEq)]
+pub enum RDProperty {
+    Position(Vector2<f32>),
+    Rotation(f32),
+    Scale(Vector2<f32>),
+    Tint(RDColor),
+    Opacity(f32),
+}
+
+impl RDProperty {
+    //the same property holding the node's current value
+    fn read(&self, node: &RDNode) -> RDProperty {
+        match self {
+            RDProperty::Position(_) => RDProperty::Position(node.transform.position),
+            RDProperty::Rotation(_) => RDProperty::Rotation(node.transform.rotation),
+            RDProperty::Scale(_) => RDProperty::Scale(node.transform.scale),
+            RDProperty::Tint(_) => RDProperty::Tint(node.tint.clone()),
+            RDProperty::Opacity(_) => RDProperty::Opacity(node.opacity),
+        }
+    }
+
+    fn write(&self, node: &mut RDNode) {
+        match self {
+            RDProperty::Position(position) => node.transform_mut().position = *position,
+            RDProperty::Rotation(rotation) => node.transform_mut().rotation = *rotation,
+            RDProperty::Scale(scale) => node.transform_mut().scale = *scale,
+            RDProperty::Tint(tint) => node.set_tint(tint.clone()),
+            RDProperty::Opacity(opacity) => node.set_opacity(*opacity),
+        }
+    }
+
+    //t goes past 0..1 for easings that overshoot, colors are clamped
+    //None when the two are different properties
+    fn lerp(&self, to: &RDProperty, t: f32) -> Option<RDProperty> {
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+
+        match (self, to) {
+            (RDProperty::Position(a), RDProperty::Position(b)) => Some(RDProperty::Position(a + (b - a) * t)),
+            (RDProperty::Rotation(a), RDProperty::Rotation(b)) => Some(RDProperty::Rotation(mix(*a, *b))),
+            (RDProperty::Scale(a), RDProperty::Scale(b)) => Some(RDProperty::Scale(a + (b - a) * t)),
+            (RDProperty::Tint(a), RDProperty::Tint(b)) => Some(RDProperty::Tint(a.lerp(b, t.clamp(0.0, 1.0)))),
+            (RDProperty::Opacity(a), RDProperty::Opacity(b)) => Some(RDProperty::Opacity(mix(*a, *b).clamp(0.0, 1.0))),
+            _ => None,
+        }
+    }
+}
+
+//animates one property of a node towards a value
+#[derive(Clone, Debug)]
+pub struct RDTween {
+    pub to: RDProperty,
+    //None starts from whatever the node holds once the delay is over, set through `from` so it matches `to`
+    from: Option<RDProperty>,
+    pub duration: Duration,
+    pub delay: Duration,
+    pub easing: RDEasing,
+    //how often it plays after the first time, None repeats forever
+    pub repeat: Option<u32>,
+    //every other play runs backwards
+    pub yoyo: bool,
+}
+
+impl RDTween {
+    pub fn new(to: RDProperty, duration: Duration) -> Self {
+        Self {
+            to,
+            from: None,
+            duration,
+            delay: Duration::ZERO,
+            easing: RDEasing::default(),
+            repeat: Some(0),
+            yoyo: false,
+        }
+    }
+
+    pub fn from(mut self, from: RDProperty) -> Self {
+        assert!(std::mem::discriminant(&from) == std::mem::discriminant(&self.to), "tween between different properties");
+        self.from = Some(from);
+        self
+    }
+
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    pub fn easing(mut self, easing: RDEasing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    pub fn repeat(mut self, count: u32) -> Self {
+        self.repeat = Some(count);
+        self
+    }
+
+    pub fn repeat_forever(mut self) -> Self {
+        self.repeat = None;
+        self
+    }
+
+    pub fn yoyo(mut self) -> Self {
+        self.yoyo = true;
+        self
+    }
+
+    //the eased progress from `from` to `to` this far past the delay, true once the last play is over
+    fn progress(&self, time: Duration) -> (f32, bool) {
+        let plays = self.repeat.map(|repeat| repeat as u64 + 1);
+
+        let (play, t, finished) = if self.duration.is_zero() {
+            (plays.unwrap_or(1) - 1, 1.0, plays.is_some())
+        } else {
+            let nanos = self.duration.as_nanos();
+            let play = (time.as_nanos() / nanos) as u64;
+
+            match plays {
+                Some(plays) if play >= plays => (plays - 1, 1.0, true),
+                _ => (play, (time.as_nanos() % nanos) as f32 / nanos as f32, false),
+            }
+        };
+
+        if self.yoyo && play % 2 == 1 {
+            (self.easing.apply(1.0 - t), finished)
+        } else {
+            (self.easing.apply(t), finished)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RDAnimationId(pub(crate) u64);
+
+//a tween running on a scene node
+pub(crate) struct Animation {
+    pub(crate) id: RDAnimationId,
+    pub(crate) tween: RDTween,
+    pub(crate) elapsed: Duration,
+}
+
+impl Animation {
+    //moves the node's property along, true once the tween is done with it
+    pub(crate) fn advance(&mut self, node: &mut RDNode, elapsed: Duration) -> bool {
+        self.elapsed += elapsed;
+
+        let Some(time) = self.elapsed.checked_sub(self.tween.delay) else { return false };
+
+        let from = self.tween.from.get_or_insert_with(|| self.tween.to.read(node)).clone();
+        let (t, finished) = self.tween.progress(time);
+
+        //`to` was swapped for another property after `from` was set, there is nothing to tween between
+        let Some(value) = from.lerp(&self.tween.to, t) else { return true };
+
+        value.write(node);
+        finished
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use cgmath::Vector2;
+
+    use crate::{color::RDColor, scene::RDNode};
+
+    use super::{Animation, RDAnimationId, RDEasing, RDProperty, RDTween};
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-3
+    }
+
+    #[test]
+    fn test_easing() {
+        let curves = [RDEasing::Linear, RDEasing::EASE, RDEasing::EASE_IN_OUT, RDEasing::SPRING, RDEasing::ELASTIC, RDEasing::Steps(4)];
+
+        for curve in curves {
+            assert_eq!((curve.apply(0.0), curve.apply(1.0)), (0.0, 1.0), "{:?}", curve);
+        }
+
+        assert!(close(RDEasing::Linear.apply(0.3), 0.3));
+        //symmetric around the middle
+        assert!(close(RDEasing::EASE_IN_OUT.apply(0.5), 0.5));
+        assert!(close(RDEasing::EASE_IN_OUT.apply(0.2) + RDEasing::EASE_IN_OUT.apply(0.8), 1.0));
+        assert!(RDEasing::EASE_IN.apply(0.25) < 0.25 && RDEasing::EASE_OUT.apply(0.25) > 0.25);
+
+        assert_eq!(RDEasing::Steps(4).apply(0.2), 0.0);
+        assert_eq!(RDEasing::Steps(4).apply(0.6), 0.5);
+
+        //an underdamped spring and an elastic overshoot before settling
+        let peak = |curve: RDEasing| (1..100).map(|i| curve.apply(i as f32 / 100.0)).fold(0.0, f32::max);
+        assert!(peak(RDEasing::SPRING) > 1.05);
+        assert!(peak(RDEasing::ELASTIC) > 1.05);
+        assert!(close(RDEasing::SPRING.apply(0.999), 1.0));
+
+        //critically damped only approaches
+        assert!(peak(RDEasing::Spring { stiffness: 100.0, damping: 20.0 }) <= 1.0);
+    }
+
+    #[test]
+    fn test_tween() {
+        let mut node = RDNode::new();
+        node.transform.position = Vector2::new(10.0, 0.0);
+
+        let tween = RDTween::new(RDProperty::Position(Vector2::new(20.0, 0.0)), Duration::from_secs(1))
+            .delay(Duration::from_millis(500))
+            .easing(RDEasing::Linear)
+            .repeat(1)
+            .yoyo();
+
+        let mut animation = Animation { id: RDAnimationId(0), tween, elapsed: Duration::ZERO };
+        let mut step = |millis: u64, node: &mut RDNode| (animation.advance(node, Duration::from_millis(millis)), node.transform.position.x);
+
+        //the start is taken once the delay is over, the node may still move until then
+        assert_eq!(step(250, &mut node), (false, 10.0));
+        node.transform.position.x = 12.0;
+        assert_eq!(step(250, &mut node), (false, 12.0));
+
+        let (finished, x) = step(500, &mut node);
+        assert!(!finished && close(x, 16.0));
+
+        //back again on the second play
+        let (finished, x) = step(750, &mut node);
+        assert!(!finished && close(x, 18.0));
+
+        let (finished, x) = step(1000, &mut node);
+        assert!(finished && close(x, 12.0));
+    }
+
+    #[test]
+    fn test_tween_properties() {
+        let mut node = RDNode::new();
+
+        let tint = RDTween::new(RDProperty::Tint(RDColor::BLACK), Duration::from_secs(2)).easing(RDEasing::Linear);
+        let mut animation = Animation { id: RDAnimationId(0), tween: tint, elapsed: Duration::ZERO };
+        assert!(!animation.advance(&mut node, Duration::from_secs(1)));
+        assert_eq!(node.tint, RDColor::new(128, 128, 128, 255));
+
+        let fade = RDTween::new(RDProperty::Opacity(0.0), Duration::ZERO).from(RDProperty::Opacity(0.5)).repeat_forever();
+        let mut animation = Animation { id: RDAnimationId(1), tween: fade, elapsed: Duration::ZERO };
+        assert!(!animation.advance(&mut node, Duration::from_secs(1)));
+        assert_eq!(node.opacity, 0.0);
+
+        //a `to` changed to another property after `from` finishes the tween instead of panicking
+        let mut grow = RDTween::new(RDProperty::Scale(Vector2::new(2.0, 2.0)), Duration::from_secs(1)).from(RDProperty::Scale(Vector2::new(1.0, 1.0)));
+        grow.to = RDProperty::Rotation(1.0);
+        let mut animation = Animation { id: RDAnimationId(2), tween: grow, elapsed: Duration::ZERO };
+        assert!(animation.advance(&mut node, Duration::from_millis(500)));
+        assert_eq!((node.transform.scale, node.transform.rotation), (Vector2::new(1.0, 1.0), 0.0));
+    }
+}
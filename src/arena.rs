@@ -0,0 +1,133 @@
+//generational arena, slots are reused after removal but a stale id never resolves to the new occupant
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) struct ArenaId {
+    index: u32,
+    generation: u32,
+}
+
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+pub(crate) struct Arena<T> {
+    slots: Vec<Slot<T>>,
+    free: Vec<u32>,
+    len: usize,
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Self {
+            slots: vec![],
+            free: vec![],
+            len: 0,
+        }
+    }
+}
+
+impl<T> Arena<T> {
+    pub(crate) fn insert(&mut self, value: T) -> ArenaId {
+        self.len += 1;
+
+        if let Some(index) = self.free.pop() {
+            let slot = &mut self.slots[index as usize];
+            slot.value = Some(value);
+
+            return ArenaId { index, generation: slot.generation };
+        }
+
+        self.slots.push(Slot { generation: 0, value: Some(value) });
+
+        ArenaId { index: self.slots.len() as u32 - 1, generation: 0 }
+    }
+
+    pub(crate) fn remove(&mut self, id: ArenaId) -> Option<T> {
+        let slot = self.slots.get_mut(id.index as usize)?;
+
+        if slot.generation != id.generation {
+            return None;
+        }
+
+        let value = slot.value.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+
+        self.free.push(id.index);
+        self.len -= 1;
+
+        Some(value)
+    }
+
+    pub(crate) fn get(&self, id: ArenaId) -> Option<&T> {
+        let slot = self.slots.get(id.index as usize)?;
+
+        if slot.generation != id.generation {
+            return None;
+        }
+
+        slot.value.as_ref()
+    }
+
+    pub(crate) fn get_mut(&mut self, id: ArenaId) -> Option<&mut T> {
+        let slot = self.slots.get_mut(id.index as usize)?;
+
+        if slot.generation != id.generation {
+            return None;
+        }
+
+        slot.value.as_mut()
+    }
+
+    pub(crate) fn contains(&self, id: ArenaId) -> bool {
+        self.get(id).is_some()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.slots.iter_mut().filter_map(|slot| slot.value.as_mut())
+    }
+}
+
+impl<T> std::ops::Index<ArenaId> for Arena<T> {
+    type Output = T;
+
+    fn index(&self, id: ArenaId) -> &T {
+        self.get(id).expect("stale arena id")
+    }
+}
+
+impl<T> std::ops::IndexMut<ArenaId> for Arena<T> {
+    fn index_mut(&mut self, id: ArenaId) -> &mut T {
+        self.get_mut(id).expect("stale arena id")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Arena;
+
+    #[test]
+    fn test_arena() {
+        let mut arena = Arena::default();
+
+        let a = arena.insert("a");
+        let b = arena.insert("b");
+
+        assert_eq!(arena.get(a), Some(&"a"));
+        assert_eq!(arena[b], "b");
+        assert_eq!(arena.len(), 2);
+
+        assert_eq!(arena.remove(a), Some("a"));
+        assert_eq!(arena.remove(a), None);
+        assert_eq!(arena.get(a), None);
+
+        let c = arena.insert("c");
+
+        assert_eq!(c.index, a.index);
+        assert_eq!(arena.get(a), None);
+        assert_eq!(arena.get(c), Some(&"c"));
+    }
+}
@@ -0,0 +1,271 @@
+use std::{collections::HashMap, ops::Range};
+
+use cgmath::{InnerSpace, Vector2};
+
+use crate::{font::RDFont, math::RDRect, path_builder::RDPath};
+
+//RDMesh::texture for meshes whose texture positions are pixels of the scene's glyph atlas
+pub const GLYPH_ATLAS_TEXTURE: u32 = 1;
+
+//pixels per em glyphs are rasterized at, the distance field keeps edges sharp at other sizes
+const GLYPH_SIZE: f32 = 32.0;
+//how far in atlas pixels the field reaches to either side of an outline, shader.wgsl has its own copy
+pub(crate) const SDF_SPREAD: f32 = 4.0;
+//the atlas only grows in height, so rows keep their layout and uploads stay whole rows
+const ATLAS_WIDTH: u32 = 1024;
+const INITIAL_HEIGHT: u32 = 128;
+//wgpu's default limit for 2d textures
+const MAX_HEIGHT: u32 = 8192;
+//empty pixels between glyphs so filtering never reaches into a neighbour
+const GLYPH_GAP: u32 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RDAtlasGlyph {
+    //where the glyph sits in the atlas, in pixels from the top left
+    pub texels: RDRect,
+    //the area those pixels cover in ems, from the glyph origin with y up
+    pub bounds: RDRect,
+}
+
+//atlas rows that changed since the last frame
+#[derive(Debug)]
+pub struct RDAtlasUpdate {
+    pub width: u32,
+    pub height: u32,
+    //whenever the atlas grows this covers all of it, so a texture of the wrong size can simply be replaced
+    pub rows: Range<u32>,
+    pub pixels: Vec<u8>,
+}
+
+//glyphs rasterized once as signed distance fields and packed into one texture shared by all text of a scene
+//a pixel is 0.5 on the outline and rises towards 1 inside, SDF_SPREAD pixels per half
+pub struct RDGlyphAtlas {
+    height: u32,
+    pixels: Vec<u8>,
+    //keyed by font and glyph, None for glyphs without an outline
+    glyphs: HashMap<(u64, u16), Option<RDAtlasGlyph>>,
+    //glyphs are packed left to right into shelves as tall as their tallest glyph
+    cursor: (u32, u32),
+    shelf_height: u32,
+    dirty: Option<Range<u32>>,
+}
+
+impl Default for RDGlyphAtlas {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RDGlyphAtlas {
+    pub fn new() -> Self {
+        Self {
+            height: INITIAL_HEIGHT,
+            pixels: vec![0; (ATLAS_WIDTH * INITIAL_HEIGHT) as usize],
+            glyphs: HashMap::new(),
+            cursor: (0, 0),
+            shelf_height: 0,
+            dirty: None,
+        }
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (ATLAS_WIDTH, self.height)
+    }
+
+    //one byte per pixel, row by row
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    //rasterizes the glyph the first time it is asked for
+    //None for glyphs without an outline, like spaces, and once the atlas can't grow any further
+    pub fn glyph(&mut self, font: &RDFont, glyph: u16) -> Option<RDAtlasGlyph> {
+        if let Some(entry) = self.glyphs.get(&(font.id(), glyph)) {
+            return *entry;
+        }
+
+        let Some((field, width, height, bounds)) = distance_field(font, glyph) else {
+            self.glyphs.insert((font.id(), glyph), None);
+            return None;
+        };
+
+        let (x, y) = self.allocate(width, height)?;
+
+        for (row, field_row) in field.chunks_exact(width as usize).enumerate() {
+            let start = (y as usize + row) * ATLAS_WIDTH as usize + x as usize;
+            self.pixels[start..start + width as usize].copy_from_slice(field_row);
+        }
+        self.mark_dirty(y..y + height);
+
+        let entry = RDAtlasGlyph {
+            texels: RDRect::new(Vector2::new(x as f32, y as f32), Vector2::new((x + width) as f32, (y + height) as f32)),
+            bounds: RDRect::new(bounds.min / GLYPH_SIZE, bounds.max / GLYPH_SIZE),
+        };
+        self.glyphs.insert((font.id(), glyph), Some(entry));
+
+        Some(entry)
+    }
+
+    fn mark_dirty(&mut self, rows: Range<u32>) {
+        self.dirty = Some(match self.dirty.take() {
+            Some(dirty) => dirty.start.min(rows.start)..dirty.end.max(rows.end),
+            None => rows,
+        });
+    }
+
+    //top left corner of a free width x height area, doubling the height when the shelves run out
+    fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        if width + GLYPH_GAP > ATLAS_WIDTH {
+            return None;
+        }
+
+        if self.cursor.0 + width + GLYPH_GAP > ATLAS_WIDTH {
+            self.cursor = (0, self.cursor.1 + self.shelf_height);
+            self.shelf_height = 0;
+        }
+
+        while self.cursor.1 + height + GLYPH_GAP > self.height {
+            if self.height >= MAX_HEIGHT {
+                return None;
+            }
+
+            self.height *= 2;
+            self.pixels.resize((ATLAS_WIDTH * self.height) as usize, 0);
+            self.mark_dirty(0..self.height);
+        }
+
+        let corner = (self.cursor.0 + GLYPH_GAP, self.cursor.1 + GLYPH_GAP);
+        self.cursor.0 += width + GLYPH_GAP;
+        self.shelf_height = self.shelf_height.max(height + GLYPH_GAP);
+
+        Some(corner)
+    }
+
+    //the whole atlas goes up again with the next update, unless nothing was ever drawn into it
+    pub(crate) fn invalidate(&mut self) {
+        if !self.glyphs.is_empty() {
+            self.mark_dirty(0..self.height);
+        }
+    }
+
+    pub(crate) fn take_update(&mut self) -> Option<RDAtlasUpdate> {
+        let rows = self.dirty.take()?;
+
+        Some(RDAtlasUpdate {
+            width: ATLAS_WIDTH,
+            height: self.height,
+            pixels: self.pixels[(rows.start * ATLAS_WIDTH) as usize..(rows.end * ATLAS_WIDTH) as usize].to_vec(),
+            rows,
+        })
+    }
+}
+
+//how much of a pixel a distance field sample covers, texels_per_pixel being how far the sample moves per screen pixel
+//shader.wgsl does the same for the gpu
+#[cfg(any(test, feature = "software"))]
+pub(crate) fn sdf_coverage(value: f32, texels_per_pixel: f32) -> f32 {
+    let pixels = (value - 0.5) * 2.0 * SDF_SPREAD / texels_per_pixel.max(1e-6);
+    (pixels + 0.5).clamp(0.0, 1.0)
+}
+
+fn segment_distance(point: Vector2<f32>, start: Vector2<f32>, end: Vector2<f32>) -> f32 {
+    let edge = end - start;
+    let t = if edge.magnitude2() == 0.0 { 0.0 } else { ((point - start).dot(edge) / edge.magnitude2()).clamp(0.0, 1.0) };
+
+    (point - (start + edge * t)).magnitude()
+}
+
+//the glyph's field rows from the top, its width and height, and the area it covers in pixels with the glyph origin at 0
+//inside is decided by the nonzero rule like font rasterizers do, so overlapping contours merge
+fn distance_field(font: &RDFont, glyph: u16) -> Option<(Vec<u8>, u32, u32, RDRect)> {
+    let path = font.append_glyph(RDPath::new(), glyph, GLYPH_SIZE, Vector2::new(0.0, 0.0));
+
+    let edges: Vec<_> = path.subpaths().iter().flat_map(|subpath| {
+        let points = &subpath.points;
+        (0..points.len()).map(move |i| (points[i], points[(i + 1) % points.len()]))
+    }).collect();
+
+    let outline = RDRect::from_points(edges.iter().map(|(start, _)| *start))?;
+
+    let min = Vector2::new(outline.min.x.floor(), outline.min.y.floor()) - Vector2::new(SDF_SPREAD, SDF_SPREAD);
+    let max = Vector2::new(outline.max.x.ceil(), outline.max.y.ceil()) + Vector2::new(SDF_SPREAD, SDF_SPREAD);
+    let (width, height) = ((max.x - min.x) as u32, (max.y - min.y) as u32);
+
+    let mut field = Vec::with_capacity((width * height) as usize);
+
+    for row in 0..height {
+        for column in 0..width {
+            let point = Vector2::new(min.x + column as f32 + 0.5, max.y - row as f32 - 0.5);
+
+            let mut distance = f32::MAX;
+            let mut winding = 0;
+
+            for (start, end) in edges.iter() {
+                distance = distance.min(segment_distance(point, *start, *end));
+
+                let side = (end - start).perp_dot(point - start);
+                if start.y <= point.y && end.y > point.y && side > 0.0 {
+                    winding += 1;
+                } else if end.y <= point.y && start.y > point.y && side < 0.0 {
+                    winding -= 1;
+                }
+            }
+
+            let signed = if winding != 0 { distance } else { -distance };
+            field.push(((0.5 + signed / (2.0 * SDF_SPREAD)).clamp(0.0, 1.0) * 255.0).round() as u8);
+        }
+    }
+
+    Some((field, width, height, RDRect::new(min, max)))
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Vector2;
+
+    use crate::{font::tests::truetype_font, math::RDRect};
+
+    use super::{sdf_coverage, RDGlyphAtlas, ATLAS_WIDTH, INITIAL_HEIGHT};
+
+    #[test]
+    fn test_glyph_atlas() {
+        let font = truetype_font();
+        let mut atlas = RDGlyphAtlas::new();
+
+        //the square with a hole is 0.6 em wide, 19.2 pixels rounded out to 20 and padded by the spread
+        let square = atlas.glyph(&font, 1).unwrap();
+        assert_eq!(square.texels, RDRect::new(Vector2::new(1.0, 1.0), Vector2::new(29.0, 29.0)));
+        assert_eq!(square.bounds, RDRect::new(Vector2::new(-0.125, -0.125), Vector2::new(0.75, 0.75)));
+
+        let value = |x: f32, y: f32| atlas.pixels()[y as usize * ATLAS_WIDTH as usize + x as usize];
+        //the middle of the ring, the middle of the hole and the far corner of the padding
+        assert!(value(6.0, 15.0) > 160);
+        assert!(value(15.0, 15.0) < 96);
+        assert_eq!(value(1.0, 1.0), 0);
+
+        //only the rows the glyph went into are sent
+        let update = atlas.take_update().unwrap();
+        assert_eq!((update.width, update.height, update.rows.clone()), (ATLAS_WIDTH, INITIAL_HEIGHT, 1..29));
+        assert_eq!(update.pixels.len(), 28 * ATLAS_WIDTH as usize);
+
+        //cached glyphs and outlines without any contours add nothing
+        assert_eq!(atlas.glyph(&font, 1), Some(square));
+        assert_eq!(atlas.glyph(&font, 0), None);
+        assert!(atlas.take_update().is_none());
+
+        //35 glyphs fit on a shelf and 4 shelves in the first 128 rows, the next one doubles the atlas and sends all of it
+        for _ in 1..35 * 4 {
+            atlas.allocate(28, 28).unwrap();
+        }
+        assert!(atlas.take_update().is_none());
+        assert_eq!(atlas.allocate(28, 28), Some((1, 117)));
+        assert_eq!(atlas.size(), (ATLAS_WIDTH, INITIAL_HEIGHT * 2));
+        assert_eq!(atlas.take_update().unwrap().rows, 0..INITIAL_HEIGHT * 2);
+
+        //a sample on the outline half covers a pixel, further than half a pixel either way is all or nothing
+        assert_eq!(sdf_coverage(0.5, 1.0), 0.5);
+        assert_eq!(sdf_coverage(0.5 + 0.75 / 8.0, 1.0), 1.0);
+        assert_eq!(sdf_coverage(0.5 + 0.75 / 8.0, 4.0), 0.6875);
+        assert_eq!(sdf_coverage(0.0, 1.0), 0.0);
+    }
+}
@@ -0,0 +1,171 @@
+use std::sync::Arc;
+
+use winit::window::Window;
+
+use crate::{image::RDImage, scene::{RDGfxUpdate, RDScene, RDView}, RDOptions, RDRenderStats};
+
+//what the scene's output is fed into, a frame is begin_frame, the uploads, draw and end_frame in that order
+//the scene remembers what it already uploaded, so keep feeding a scene to the same backend
+pub trait RDBackend {
+    type Error;
+
+    //the window backend acquires its surface texture here
+    fn begin_frame(&mut self) -> Result<(), Self::Error>;
+    //pixels of the frame being drawn, the scene lays its camera viewports out on it
+    fn size(&self) -> (u32, u32);
+    //changed vertex and index ranges, the buffers have to grow to the update's capacities first
+    //a failed upload ends the frame there, the scene then sends everything again with the next one
+    fn upload_geometry(&mut self, update: &RDGfxUpdate, stats: &mut RDRenderStats) -> Result<(), Self::Error>;
+    //changed per object transforms and textures
+    fn upload_objects(&mut self, update: &RDGfxUpdate, stats: &mut RDRenderStats) -> Result<(), Self::Error>;
+    //changed rows of the glyph atlas, a texture of a different size is replaced first
+    fn upload_textures(&mut self, update: &RDGfxUpdate, stats: &mut RDRenderStats) -> Result<(), Self::Error>;
+    //one view per camera, each in draw order
+    fn draw(&mut self, views: &[RDView]) -> Result<(), Self::Error>;
+    //presents the frame, backends drawing offscreen hand back the image, window backends only when asked to capture it
+    fn end_frame(&mut self) -> Option<RDImage>;
+}
+
+//what Raindeer needs on top to draw into its window, it is created once the window exists
+pub trait RDWindowBackend: RDBackend + Sized {
+    fn create(window: Arc<Window>, options: &RDOptions) -> Self;
+    //the window's new inner size in pixels, never zero
+    fn resize(&mut self, width: u32, height: u32);
+    //called with a failed frame, errors handed back stop the event loop
+    fn recover(&mut self, error: Self::Error) -> Result<(), Self::Error>;
+    //the next end_frame hands back what it presents
+    fn capture_next_frame(&mut self);
+}
+
+pub fn render_frame<B: RDBackend + ?Sized>(scene: &mut RDScene, backend: &mut B, stats: &mut RDRenderStats) -> Result<Option<RDImage>, B::Error> {
+    backend.begin_frame()?;
+
+    let (width, height) = backend.size();
+    let update = scene.output_gfx(height as f32, width as f32);
+
+    stats.uploaded_bytes = 0;
+    let uploaded = backend.upload_geometry(&update, stats)
+        .and_then(|_| backend.upload_objects(&update, stats))
+        .and_then(|_| backend.upload_textures(&update, stats));
+
+    //the update is gone from the scene, drawing now would read ranges the backend never got
+    if let Err(error) = uploaded {
+        scene.invalidate_uploads();
+        return Err(error);
+    }
+
+    let (vertex_count, index_count, object_count) = scene.allocated_counts();
+    stats.vertex_count = vertex_count;
+    stats.index_count = index_count;
+    stats.object_count = object_count;
+    stats.draw_calls = update.views.iter().map(|view| view.draws.len() as u32).sum();
+    stats.culled_draws = update.culled_draws;
+
+    backend.draw(&update.views)?;
+
+    Ok(backend.end_frame())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{color::RDColor, image::RDImage, path_builder::RDPath, scene::{RDGfxUpdate, RDScene, RDView}, RDRenderStats};
+
+    use super::{render_frame, RDBackend};
+
+    //keeps a log instead of drawing
+    #[derive(Default)]
+    struct Recorder {
+        log: Vec<String>,
+        //the next object upload fails
+        fail_objects: bool,
+    }
+
+    impl RDBackend for Recorder {
+        type Error = &'static str;
+
+        fn begin_frame(&mut self) -> Result<(), &'static str> {
+            self.log.push("begin".into());
+            Ok(())
+        }
+
+        fn size(&self) -> (u32, u32) {
+            (200, 100)
+        }
+
+        fn upload_geometry(&mut self, update: &RDGfxUpdate, _stats: &mut RDRenderStats) -> Result<(), &'static str> {
+            self.log.push(format!("geometry {} {}", update.vertices.len(), update.indicies.len()));
+
+            if let Some((_, vertices)) = update.vertices.first() {
+                let vertex = &vertices[0];
+                self.log.push(format!("vertex {:?} {} {:?}", vertex.position(), vertex.object(), vertex.color() == RDColor::RED));
+            }
+            Ok(())
+        }
+
+        fn upload_objects(&mut self, update: &RDGfxUpdate, _stats: &mut RDRenderStats) -> Result<(), &'static str> {
+            self.log.push(format!("objects {}", update.storage.len()));
+
+            if std::mem::take(&mut self.fail_objects) {
+                return Err("objects");
+            }
+
+            if let Some((_, storage)) = update.storage.first() {
+                let object = &storage[0];
+                self.log.push(format!("object {:?} {} {:?}", object.transform().w, object.texture(), object.tint() == RDColor::WHITE));
+            }
+            Ok(())
+        }
+
+        fn upload_textures(&mut self, update: &RDGfxUpdate, _stats: &mut RDRenderStats) -> Result<(), &'static str> {
+            self.log.push(format!("atlas {:?}", update.atlas.as_ref().map(|atlas| atlas.rows.clone())));
+            Ok(())
+        }
+
+        fn draw(&mut self, views: &[RDView]) -> Result<(), &'static str> {
+            self.log.push(format!("draw {} {:?}", views[0].draws.len(), views[0].viewport.max));
+            Ok(())
+        }
+
+        fn end_frame(&mut self) -> Option<RDImage> {
+            self.log.push("end".into());
+            Some(RDImage::new(1, 1))
+        }
+    }
+
+    #[test]
+    fn test_render_frame() {
+        let mut scene = RDScene::new(10);
+        scene.add_root(RDPath::new().to(0.0, 0.0).line(1.0, 0.0).line(0.0, 1.0).close().fill(RDColor::RED).to_node());
+
+        let mut recorder = Recorder::default();
+        let mut stats = RDRenderStats::default();
+
+        assert!(render_frame(&mut scene, &mut recorder, &mut stats).unwrap().is_some());
+        assert!(render_frame(&mut scene, &mut recorder, &mut stats).unwrap().is_some());
+
+        //fill and stroke go up once as a run each, read through the public accessors, the second frame only draws
+        assert_eq!(recorder.log, vec![
+            "begin", "geometry 2 2", "vertex Vector2 [0.0, 0.0] 0 true", "objects 1", "object Vector2 [0.0, 0.0] 0 true", "atlas None", "draw 2 Vector2 [200.0, 100.0]", "end",
+            "begin", "geometry 0 0", "objects 0", "atlas None", "draw 2 Vector2 [200.0, 100.0]", "end",
+        ]);
+        assert_eq!((stats.draw_calls, stats.object_count), (2, 2));
+    }
+
+    #[test]
+    fn test_failed_upload() {
+        let mut scene = RDScene::new(10);
+        scene.add_root(RDPath::new().to(0.0, 0.0).line(1.0, 0.0).line(0.0, 1.0).close().fill(RDColor::RED).to_node());
+
+        let mut recorder = Recorder { fail_objects: true, ..Default::default() };
+        let mut stats = RDRenderStats::default();
+
+        assert_eq!(render_frame(&mut scene, &mut recorder, &mut stats), Err("objects"));
+        assert!(render_frame(&mut scene, &mut recorder, &mut stats).unwrap().is_some());
+
+        //the failed frame is neither drawn nor ended, the next one uploads everything again
+        assert_eq!(recorder.log.iter().filter(|line| line.starts_with("geometry") || line.starts_with("objects") || line.starts_with("draw")).collect::<Vec<_>>(), [
+            "geometry 2 2", "objects 1",
+            "geometry 2 2", "objects 1", "draw 2 Vector2 [200.0, 100.0]",
+        ]);
+    }
+}
@@ -1,5 +1,6 @@
-use std::process::ExitCode;
-use raindeer::{color::RDColor, path_builder::{RDPath, RDStroke}, Raindeer};
+use std::{process::ExitCode, time::Duration};
+use cgmath::Vector2;
+use raindeer::{animation::{RDEasing, RDProperty, RDTween}, color::RDColor, input::RDEvent, path_builder::{RDPath, RDStroke}, Raindeer};
 
 fn main() -> ExitCode {
     let mut renderer = Raindeer::new();
@@ -35,11 +36,33 @@ fn main() -> ExitCode {
     square.transform.scale.y = 2.0;
 
     renderer.scene.add_root(square);
-    renderer.scene.add_root(square2);
+    let square2 = renderer.scene.add_root(square2);
+
+    renderer.scene.animate(square2, RDTween::new(RDProperty::Position(Vector2::new(20.0, 0.0)), Duration::from_secs(2))
+        .easing(RDEasing::EASE_IN_OUT)
+        .repeat_forever()
+        .yoyo());
+    renderer.scene.animate(square2, RDTween::new(RDProperty::Rotation(std::f32::consts::TAU), Duration::from_secs(4))
+        .easing(RDEasing::Linear)
+        .repeat_forever());
+    renderer.scene.animate(square2, RDTween::new(RDProperty::Opacity(0.3), Duration::from_millis(800))
+        .delay(Duration::from_secs(1))
+        .easing(RDEasing::SPRING)
+        .repeat_forever()
+        .yoyo());
 
     loop {
         if let Err(exitcode) = renderer.run() {
+            if let Some(error) = renderer.take_error() {
+                eprintln!("{error}");
+            }
             break exitcode;
         }
+
+        for event in renderer.poll_events() {
+            if let RDEvent::PointerPressed { pointer, button, .. } = event {
+                println!("{:?} pressed at {:?}", button, pointer.world);
+            }
+        }
     }
 }
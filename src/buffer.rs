@@ -0,0 +1,87 @@
+//the device can't hold a buffer of the requested size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct BufferTooLarge {
+    pub(crate) requested: u64,
+    pub(crate) limit: u64,
+}
+
+//powers of two keep reallocations rare, close to the limit the exact size still fits
+fn grown_size(size: u64, limit: u64) -> Result<u64, BufferTooLarge> {
+    if size > limit {
+        return Err(BufferTooLarge { requested: size, limit });
+    }
+
+    Ok(size.checked_next_power_of_two().filter(|grown| *grown <= limit).unwrap_or(size))
+}
+
+pub(crate) struct GfxBuffer {
+    pub(crate) buffer: wgpu::Buffer,
+    label: &'static str,
+    usage: wgpu::BufferUsages,
+}
+
+impl GfxBuffer {
+    pub(crate) fn new(device: &wgpu::Device, label: &'static str, usage: wgpu::BufferUsages, size: u64) -> Self {
+        //copy src so the old contents can be carried over when growing
+        let usage = usage | wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::COPY_SRC;
+
+        Self {
+            buffer: GfxBuffer::create(device, label, usage, size),
+            label,
+            usage,
+        }
+    }
+
+    fn create(device: &wgpu::Device, label: &'static str, usage: wgpu::BufferUsages, size: u64) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            mapped_at_creation: false,
+            usage,
+            size: size.next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT),
+        })
+    }
+
+    pub(crate) fn size(&self) -> u64 {
+        self.buffer.size()
+    }
+
+    //grows the buffer to fit at least `size` bytes, returns true if the buffer was replaced
+    //the copy is submitted right away so it lands before any write_buffer queued after this call
+    //the buffer is left alone when the device can't hold `size` bytes
+    pub(crate) fn reserve(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, size: u64) -> Result<bool, BufferTooLarge> {
+        if size <= self.buffer.size() {
+            return Ok(false);
+        }
+
+        let new_size = grown_size(size, device.limits().max_buffer_size)?;
+        let new_buffer = GfxBuffer::create(device, self.label, self.usage, new_size);
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Buffer Growth Encoder"),
+        });
+        encoder.copy_buffer_to_buffer(&self.buffer, 0, &new_buffer, 0, self.buffer.size());
+        queue.submit(std::iter::once(encoder.finish()));
+
+        self.buffer = new_buffer;
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{grown_size, BufferTooLarge};
+
+    #[test]
+    fn test_grown_size() {
+        assert_eq!(grown_size(600, 4096), Ok(1024));
+        assert_eq!(grown_size(1024, 4096), Ok(1024));
+
+        //just under the limit the next power of two doesn't fit but the size itself does
+        assert_eq!(grown_size(3000, 4096), Ok(4096));
+        assert_eq!(grown_size(3000, 3500), Ok(3000));
+        assert_eq!(grown_size(u64::MAX - 1, u64::MAX), Ok(u64::MAX - 1));
+
+        assert_eq!(grown_size(5000, 4096), Err(BufferTooLarge { requested: 5000, limit: 4096 }));
+    }
+}
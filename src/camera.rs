@@ -0,0 +1,165 @@
+use cgmath::Vector2;
+
+use crate::math::{RDAffine, RDRect};
+
+pub struct RDCamera {
+    //world position shown at the center of the viewport
+    pub position: Vector2<f32>,
+    //multiplies the scene's ppu, 2.0 shows everything twice as large
+    pub zoom: f32,
+    pub rotation: f32,
+    //region of the surface this camera draws into, in 0..1 with the origin at the top left
+    pub viewport: RDRect,
+}
+
+impl Default for RDCamera {
+    fn default() -> Self {
+        Self {
+            position: Vector2::new(0.0, 0.0),
+            zoom: 1.0,
+            rotation: 0.0,
+            viewport: RDRect::new(Vector2::new(0.0, 0.0), Vector2::new(1.0, 1.0)),
+        }
+    }
+}
+
+impl RDCamera {
+    pub fn with_viewport(viewport: RDRect) -> Self {
+        Self {
+            viewport,
+            ..Self::default()
+        }
+    }
+
+    //the viewport in pixels for a surface of the given size
+    pub fn pixel_viewport(&self, surface_size: Vector2<f32>) -> RDRect {
+        RDRect::new(
+            Vector2::new(self.viewport.min.x * surface_size.x, self.viewport.min.y * surface_size.y),
+            Vector2::new(self.viewport.max.x * surface_size.x, self.viewport.max.y * surface_size.y),
+        )
+    }
+
+    //world space to clip space inside the camera's viewport
+    pub fn view_matrix(&self, ppu: f32, surface_size: Vector2<f32>) -> RDAffine {
+        let viewport = self.pixel_viewport(surface_size);
+        let scale = ppu * self.zoom;
+
+        RDAffine::from_scale(Vector2::new(scale / viewport.width(), scale / viewport.height())) *
+        RDAffine::from_rotation(-self.rotation) *
+        RDAffine::from_translation(-self.position)
+    }
+
+    //the pixel viewport cut down to the surface and a view matrix drawing the same picture into it,
+    //wgpu rejects viewports reaching past the surface, None when nothing of it is on the surface
+    pub fn surface_view(&self, ppu: f32, surface_size: Vector2<f32>) -> Option<(RDAffine, RDRect)> {
+        let full = self.pixel_viewport(surface_size);
+        let visible = full.intersection(&RDRect::new(Vector2::new(0.0, 0.0), surface_size))?;
+
+        if visible.width() <= 0.0 || visible.height() <= 0.0 {
+            return None;
+        }
+
+        //maps clip space of the whole viewport onto clip space of the visible part, pixel y points down
+        let crop = RDAffine::from_translation(Vector2::new(
+            (2.0 * (full.min.x - visible.min.x) + full.width()) / visible.width() - 1.0,
+            1.0 - (2.0 * (full.min.y - visible.min.y) + full.height()) / visible.height(),
+        )) * RDAffine::from_scale(Vector2::new(full.width() / visible.width(), full.height() / visible.height()));
+
+        Some((crop * self.view_matrix(ppu, surface_size), visible))
+    }
+
+    //pixel positions have their origin at the top left of the surface
+    pub fn screen_to_world(&self, screen_point: Vector2<f32>, ppu: f32, surface_size: Vector2<f32>) -> Option<Vector2<f32>> {
+        let viewport = self.pixel_viewport(surface_size);
+
+        if viewport.width() <= 0.0 || viewport.height() <= 0.0 {
+            return None;
+        }
+
+        let clip = Vector2::new(
+            (screen_point.x - viewport.min.x) / viewport.width() * 2.0 - 1.0,
+            1.0 - (screen_point.y - viewport.min.y) / viewport.height() * 2.0,
+        );
+
+        Some(self.view_matrix(ppu, surface_size).inverse()?.transform_point(clip))
+    }
+
+    pub fn world_to_screen(&self, world_point: Vector2<f32>, ppu: f32, surface_size: Vector2<f32>) -> Vector2<f32> {
+        self.screen_matrix(ppu, surface_size).transform_point(world_point)
+    }
+
+    //world space to pixels with the origin at the top left of the surface
+    pub fn screen_matrix(&self, ppu: f32, surface_size: Vector2<f32>) -> RDAffine {
+        let viewport = self.pixel_viewport(surface_size);
+
+        RDAffine::from_translation(viewport.min + Vector2::new(viewport.width(), viewport.height()) * 0.5) *
+        RDAffine::from_scale(Vector2::new(viewport.width() * 0.5, -viewport.height() * 0.5)) *
+        self.view_matrix(ppu, surface_size)
+    }
+
+    //the part of the world this camera can see
+    pub fn view_bounds(&self, ppu: f32, surface_size: Vector2<f32>) -> Option<RDRect> {
+        let inverse = self.view_matrix(ppu, surface_size).inverse()?;
+
+        Some(RDRect::new(Vector2::new(-1.0, -1.0), Vector2::new(1.0, 1.0)).transform(&inverse))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{InnerSpace, Vector2};
+
+    use crate::math::RDRect;
+
+    use super::RDCamera;
+
+    #[test]
+    fn test_camera_conversions() {
+        let surface = Vector2::new(800.0, 400.0);
+
+        let mut camera = RDCamera::with_viewport(RDRect::new(Vector2::new(0.5, 0.0), Vector2::new(1.0, 1.0)));
+        camera.position = Vector2::new(10.0, 5.0);
+        camera.zoom = 2.0;
+
+        //center of the right half
+        let center = camera.screen_to_world(Vector2::new(600.0, 200.0), 10.0, surface).unwrap();
+        assert!((center - Vector2::new(10.0, 5.0)).magnitude() < 1e-4);
+
+        //ppu 10 at zoom 2 maps 400 pixels of viewport onto 40 units
+        let corner = camera.screen_to_world(Vector2::new(800.0, 0.0), 10.0, surface).unwrap();
+        assert!((corner - Vector2::new(30.0, 25.0)).magnitude() < 1e-4);
+
+        camera.rotation = 1.0;
+        let world = Vector2::new(12.0, -3.0);
+        let screen = camera.world_to_screen(world, 10.0, surface);
+        let back = camera.screen_to_world(screen, 10.0, surface).unwrap();
+        assert!((back - world).magnitude() < 1e-3);
+
+        camera.rotation = 0.0;
+        let bounds = camera.view_bounds(10.0, surface).unwrap();
+        assert!((bounds.min - Vector2::new(-10.0, -15.0)).magnitude() < 1e-4);
+    }
+
+    #[test]
+    fn test_surface_view() {
+        let surface = Vector2::new(800.0, 400.0);
+
+        //sliding in from the left, only the right half is on the surface
+        let mut camera = RDCamera::with_viewport(RDRect::new(Vector2::new(-0.25, 0.5), Vector2::new(0.25, 1.5)));
+        let (matrix, viewport) = camera.surface_view(10.0, surface).unwrap();
+        assert_eq!(viewport, RDRect::new(Vector2::new(0.0, 200.0), Vector2::new(200.0, 400.0)));
+
+        //a world point lands on the same pixel either way
+        let world = Vector2::new(7.0, 3.0);
+        let clip = matrix.transform_point(world);
+        let pixel = Vector2::new(viewport.min.x + (clip.x + 1.0) * 0.5 * viewport.width(), viewport.min.y + (1.0 - clip.y) * 0.5 * viewport.height());
+        assert!((pixel - camera.world_to_screen(world, 10.0, surface)).magnitude() < 1e-3);
+
+        camera.viewport = RDRect::new(Vector2::new(1.0, 0.0), Vector2::new(2.0, 1.0));
+        assert!(camera.surface_view(10.0, surface).is_none());
+
+        camera.viewport = RDRect::new(Vector2::new(0.0, 0.0), Vector2::new(1.0, 1.0));
+        let (matrix, viewport) = camera.surface_view(10.0, surface).unwrap();
+        assert_eq!((matrix, viewport), (camera.view_matrix(10.0, surface), camera.pixel_viewport(surface)));
+    }
+}
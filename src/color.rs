@@ -1,4 +1,21 @@
-#[derive(Clone)]
+//vertex colors are blended as linear values, the surface stores them as srgb
+pub(crate) fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+pub(crate) fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct RDColor {
     pub r: u8,
     pub g: u8,
@@ -23,6 +40,35 @@ impl RDColor {
         (self.r as u32) | ((self.g as u32) << 8) | ((self.b as u32) << 16) | ((self.a as u32) << 24)
     }
 
+    pub fn from_u32(color: u32) -> Self {
+        let [r, g, b, a] = color.to_le_bytes();
+        Self { r, g, b, a }
+    }
+
+    //the color as it ends up on screen, for output that isn't drawn through an srgb surface
+    pub(crate) fn to_srgb(&self) -> RDColor {
+        let convert = |channel: u8| (linear_to_srgb(channel as f32 / 255.0) * 255.0).round() as u8;
+        RDColor::new(convert(self.r), convert(self.g), convert(self.b), self.a)
+    }
+
+    //the inverse of to_srgb, for colors given the way they should look, like the ones in svg files
+    pub(crate) fn to_linear(&self) -> RDColor {
+        let convert = |channel: u8| (srgb_to_linear(channel as f32 / 255.0) * 255.0).round() as u8;
+        RDColor::new(convert(self.r), convert(self.g), convert(self.b), self.a)
+    }
+
+    //straight per channel mix, t in 0..1
+    pub fn lerp(&self, other: &RDColor, t: f32) -> RDColor {
+        let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+        RDColor::new(mix(self.r, other.r), mix(self.g, other.g), mix(self.b, other.b), mix(self.a, other.a))
+    }
+
+    //per channel product, white leaves the other color as it is
+    pub fn multiply(&self, other: &RDColor) -> RDColor {
+        let multiply = |a: u8, b: u8| ((a as u32 * b as u32 + 127) / 255) as u8;
+        RDColor::new(multiply(self.r, other.r), multiply(self.g, other.g), multiply(self.b, other.b), multiply(self.a, other.a))
+    }
+
     pub const fn new(
         r: u8,
         g: u8,
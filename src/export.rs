@@ -0,0 +1,92 @@
+use cgmath::Vector2;
+
+use crate::{color::RDColor, math::RDAffine, path_builder::{ContinousPath, RDFillRule, RDStroke}, scene::{inherit_tint, RDLayer, RDMesh, RDMeshSource, RDNodeId, RDScene}};
+
+//what a vector format has to be able to write, the scene walk is shared between them
+pub(crate) trait VectorWriter {
+    fn begin_group(&mut self, transform: &RDAffine);
+    fn end_group(&mut self);
+    fn fill(&mut self, subpaths: &[&ContinousPath], color: &RDColor, rule: RDFillRule);
+    fn stroke(&mut self, subpaths: &[&ContinousPath], stroke: &RDStroke);
+}
+
+//the page size in pixels and the world to page transform, y pointing down
+//the first camera and the last rendered surface decide it, before the first render the scene's bounds are used instead
+pub(crate) fn page_frame(scene: &RDScene) -> (Vector2<f32>, RDAffine) {
+    let ppu = scene.ppu() as f32;
+    //the same size things have on screen at zoom 1, where a unit spans half of ppu in pixels
+    let scale = ppu * 0.5;
+
+    match (scene.surface_size(), scene.cameras().first()) {
+        (Some(size), Some(camera)) => (size, camera.screen_matrix(ppu, size)),
+        _ => match scene.local_bounds(scene.root()) {
+            //y points up in the world
+            Some(bounds) => (
+                Vector2::new(bounds.width(), bounds.height()) * scale,
+                RDAffine::from_scale(Vector2::new(scale, -scale)) * RDAffine::from_translation(Vector2::new(-bounds.min.x, -bounds.max.y)),
+            ),
+            None => (Vector2::new(0.0, 0.0), RDAffine::identity()),
+        },
+    }
+}
+
+//vector formats paint in document order, so layers and z indices only sort nodes that share a parent
+pub(crate) fn write_scene(scene: &RDScene, writer: &mut impl VectorWriter) {
+    for child in sorted_children(scene, scene.root(), RDLayer::default()) {
+        write_node(scene, child, RDLayer::default(), &RDColor::WHITE, writer);
+    }
+}
+
+//stable, so equal siblings keep their tree order
+fn sorted_children(scene: &RDScene, id: RDNodeId, layer: RDLayer) -> Vec<RDNodeId> {
+    let mut children = scene.children(id).to_vec();
+    children.sort_by_key(|child| scene.get(*child).map(|child| (child.layer.unwrap_or(layer), child.z_index)));
+    children
+}
+
+fn write_node(scene: &RDScene, id: RDNodeId, layer: RDLayer, parent_tint: &RDColor, writer: &mut impl VectorWriter) {
+    let Some(node) = scene.get(id).filter(|node| node.visible) else { return };
+    let layer = node.layer.unwrap_or(layer);
+    let tint = inherit_tint(parent_tint, node);
+
+    writer.begin_group(&node.transform.to_matrix());
+
+    if let Some(mesh) = &node.mesh {
+        write_mesh(mesh, &tint, writer);
+    }
+
+    for child in sorted_children(scene, id, layer) {
+        write_node(scene, child, layer, &tint, writer);
+    }
+
+    writer.end_group();
+}
+
+fn write_mesh(mesh: &RDMesh, tint: &RDColor, writer: &mut impl VectorWriter) {
+    match &mesh.source {
+        Some(RDMeshSource::Fill(path)) => {
+            let subpaths: Vec<_> = path.subpaths().iter().collect();
+
+            if !subpaths.is_empty() {
+                writer.fill(&subpaths, &path.color.multiply(tint), path.fill_rule);
+            }
+        }
+        //only the subpaths that get a stroke on screen
+        Some(RDMeshSource::Stroke(path)) => {
+            let subpaths: Vec<_> = path.subpaths().iter().filter(|subpath| path.is_stroked(subpath)).collect();
+
+            if !subpaths.is_empty() {
+                writer.stroke(&subpaths, &RDStroke { color: path.stroke.color.multiply(tint), ..path.stroke.clone() });
+            }
+        }
+        //nothing to go on but the triangles, each takes the color of its first corner
+        None => {
+            for triangle in mesh.vao.indicies.chunks_exact(3) {
+                let corners: Vec<_> = triangle.iter().map(|index| Vector2::from(mesh.vao.vertices[*index as usize].position)).collect();
+                let color = RDColor::from_u32(mesh.vao.vertices[triangle[0] as usize].color).multiply(tint);
+
+                writer.fill(&[&ContinousPath::polygon(&corners)], &color, RDFillRule::Union);
+            }
+        }
+    }
+}
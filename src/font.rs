@@ -0,0 +1,1456 @@
+use std::{collections::HashMap, fmt, io, ops::Range, path::Path, sync::atomic::{AtomicU64, Ordering}};
+
+use cgmath::Vector2;
+
+use crate::{math::RDAffine, path_builder::{PathSegment, RDPath}};
+
+#[derive(Debug)]
+pub enum RDFontError {
+    Io(io::Error),
+    //a table or record points past the end of the file
+    Truncated,
+    MissingTable(&'static str),
+    //cff2, bitmap only fonts and cmaps without a unicode subtable
+    Unsupported(&'static str),
+}
+
+impl fmt::Display for RDFontError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RDFontError::Io(error) => write!(f, "failed to read the font: {error}"),
+            RDFontError::Truncated => write!(f, "the font file is truncated"),
+            RDFontError::MissingTable(tag) => write!(f, "the font has no {tag} table"),
+            RDFontError::Unsupported(what) => write!(f, "unsupported font: {what}"),
+        }
+    }
+}
+
+impl std::error::Error for RDFontError {}
+
+impl From<io::Error> for RDFontError {
+    fn from(error: io::Error) -> Self {
+        RDFontError::Io(error)
+    }
+}
+
+fn truncated<T>(value: Option<T>) -> Result<T, RDFontError> {
+    value.ok_or(RDFontError::Truncated)
+}
+
+//all font data is big endian, reads past the end give None
+fn read_u8(data: &[u8], offset: usize) -> Option<u8> {
+    data.get(offset).copied()
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn read_i16(data: &[u8], offset: usize) -> Option<i16> {
+    read_u16(data, offset).map(|value| value as i16)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+//one closed contour of a glyph in font units, quadratic curves are already raised to cubics
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Contour {
+    pub(crate) start: Vector2<f32>,
+    pub(crate) segments: Vec<PathSegment>,
+}
+
+impl Contour {
+    fn end(&self) -> Vector2<f32> {
+        match self.segments.last() {
+            Some(PathSegment::Line(point) | PathSegment::Cubic(_, _, point)) => *point,
+            None => self.start,
+        }
+    }
+
+    fn quad(&mut self, control: Vector2<f32>, end: Vector2<f32>) {
+        let start = self.end();
+        self.segments.push(PathSegment::Cubic(start + (control - start) * (2.0 / 3.0), end + (control - end) * (2.0 / 3.0), end));
+    }
+}
+
+//a truetype contour, on curve points are joined by lines, off curve ones are quadratic controls
+//with an on curve point implied halfway between two controls in a row
+fn quadratic_contour(points: &[(Vector2<f32>, bool)]) -> Option<Contour> {
+    let (last, _) = *points.last()?;
+
+    //start on a point that is on the curve, or the implied one between the first and last control
+    let (start, rest) = match points.iter().position(|(_, on_curve)| *on_curve) {
+        Some(i) => (points[i].0, [&points[i + 1..], &points[..i]].concat()),
+        None => ((points[0].0 + last) * 0.5, points.to_vec()),
+    };
+
+    let mut contour = Contour { start, segments: vec![] };
+    let mut control = None;
+
+    for (point, on_curve) in rest.into_iter().chain([(start, true)]) {
+        match (control, on_curve) {
+            (None, true) => contour.segments.push(PathSegment::Line(point)),
+            (None, false) => control = Some(point),
+            (Some(previous), true) => {
+                contour.quad(previous, point);
+                control = None;
+            }
+            (Some(previous), false) => {
+                contour.quad(previous, (previous + point) * 0.5);
+                control = Some(point);
+            }
+        }
+    }
+
+    Some(contour)
+}
+
+//sorted glyph ranges mapping to a value, both coverage tables and class definitions read into these
+#[derive(Default)]
+struct GlyphRanges {
+    //first glyph, last glyph, value of the first glyph
+    ranges: Vec<(u16, u16, u16)>,
+}
+
+impl GlyphRanges {
+    //format 1 lists glyphs one by one, format 2 as ranges, the same for coverage tables and class definitions
+    fn parse(data: &[u8], offset: usize, coverage: bool) -> Option<Self> {
+        let mut ranges = vec![];
+
+        match (read_u16(data, offset)?, coverage) {
+            (1, true) => {
+                for i in 0..read_u16(data, offset + 2)? as usize {
+                    let glyph = read_u16(data, offset + 4 + i * 2)?;
+                    ranges.push((glyph, glyph, i as u16));
+                }
+            }
+            (1, false) => {
+                let first = read_u16(data, offset + 2)?;
+                for i in 0..read_u16(data, offset + 4)? as usize {
+                    let glyph = first.checked_add(i as u16)?;
+                    ranges.push((glyph, glyph, read_u16(data, offset + 6 + i * 2)?));
+                }
+            }
+            (2, _) => {
+                for i in 0..read_u16(data, offset + 2)? as usize {
+                    let record = offset + 4 + i * 6;
+                    ranges.push((read_u16(data, record)?, read_u16(data, record + 2)?, read_u16(data, record + 4)?));
+                }
+            }
+            _ => return None,
+        }
+
+        ranges.sort_by_key(|range| range.0);
+        Some(Self { ranges })
+    }
+
+    fn find(&self, glyph: u16) -> Option<&(u16, u16, u16)> {
+        let index = self.ranges.partition_point(|range| range.0 <= glyph).checked_sub(1)?;
+        self.ranges.get(index).filter(|range| glyph <= range.1)
+    }
+
+    //position in a coverage table, ranges count up from their first value
+    fn coverage_index(&self, glyph: u16) -> Option<u16> {
+        self.find(glyph).map(|(start, _, index)| index + (glyph - start))
+    }
+
+    //glyphs a class definition leaves out are in class 0
+    fn class(&self, glyph: u16) -> u16 {
+        self.find(glyph).map_or(0, |(_, _, class)| *class)
+    }
+
+    fn glyphs(&self) -> impl Iterator<Item = (u16, u16)> + '_ {
+        self.ranges.iter().flat_map(|(start, end, value)| (*start..=*end).map(move |glyph| (glyph, value + (glyph - start))))
+    }
+}
+
+enum KerningTable {
+    //glyph pairs from the kern table or a gpos pair adjustment listing them one by one
+    Pairs(HashMap<(u16, u16), i16>),
+    //gpos pair adjustment by class, with a value for every first class and second class combination
+    Classes { coverage: GlyphRanges, first: GlyphRanges, second: GlyphRanges, second_count: u16, values: Vec<i16> },
+}
+
+impl KerningTable {
+    //None when the table doesn't cover the pair, so the next one gets to decide
+    fn get(&self, left: u16, right: u16) -> Option<i16> {
+        match self {
+            KerningTable::Pairs(pairs) => pairs.get(&(left, right)).copied(),
+            KerningTable::Classes { coverage, first, second, second_count, values } => {
+                coverage.coverage_index(left)?;
+                let index = first.class(left) as usize * *second_count as usize + second.class(right) as usize;
+                Some(values.get(index).copied().unwrap_or(0))
+            }
+        }
+    }
+}
+
+//the classic kern table, only horizontal format 0 subtables
+fn parse_kern(data: &[u8], table: usize) -> Option<Vec<KerningTable>> {
+    //apple's newer layout starts with a 32 bit version and isn't read
+    if read_u16(data, table)? != 0 {
+        return None;
+    }
+
+    let mut pairs = HashMap::new();
+    let mut offset = table + 4;
+
+    for _ in 0..read_u16(data, table + 2)? {
+        let coverage = read_u16(data, offset + 4)?;
+        let length = read_u16(data, offset + 2)? as usize;
+
+        //horizontal, not minimum values and not cross stream
+        if coverage >> 8 != 0 || coverage & 0x7 != 0x1 {
+            offset += length;
+            continue;
+        }
+
+        let count = read_u16(data, offset + 6)? as usize;
+        for i in 0..count {
+            let record = offset + 14 + i * 6;
+            pairs.insert((read_u16(data, record)?, read_u16(data, record + 2)?), read_i16(data, record + 4)?);
+        }
+
+        //the stored length overflows for big subtables, the pair count doesn't
+        offset += 14 + count * 6;
+    }
+
+    Some(vec![KerningTable::Pairs(pairs)])
+}
+
+//a value record has a field for every bit set in its format, the horizontal advance is the third
+fn value_record_size(format: u16) -> usize {
+    (format & 0xff).count_ones() as usize * 2
+}
+
+fn x_advance(data: &[u8], record: usize, format: u16) -> Option<i16> {
+    if format & 0x4 == 0 {
+        return Some(0);
+    }
+
+    read_i16(data, record + (format & 0x3).count_ones() as usize * 2)
+}
+
+fn parse_pair_adjustment(data: &[u8], subtable: usize) -> Option<KerningTable> {
+    let coverage = GlyphRanges::parse(data, subtable + read_u16(data, subtable + 2)? as usize, true)?;
+    let (first_format, second_format) = (read_u16(data, subtable + 4)?, read_u16(data, subtable + 6)?);
+    let record_size = value_record_size(first_format) + value_record_size(second_format);
+
+    match read_u16(data, subtable)? {
+        1 => {
+            let mut pairs = HashMap::new();
+
+            for (left, index) in coverage.glyphs() {
+                let set = subtable + read_u16(data, subtable + 10 + index as usize * 2)? as usize;
+
+                for i in 0..read_u16(data, set)? as usize {
+                    let record = set + 2 + i * (2 + record_size);
+                    pairs.insert((left, read_u16(data, record)?), x_advance(data, record + 2, first_format)?);
+                }
+            }
+
+            Some(KerningTable::Pairs(pairs))
+        }
+        2 => {
+            let first = GlyphRanges::parse(data, subtable + read_u16(data, subtable + 8)? as usize, false)?;
+            let second = GlyphRanges::parse(data, subtable + read_u16(data, subtable + 10)? as usize, false)?;
+            let (first_count, second_count) = (read_u16(data, subtable + 12)?, read_u16(data, subtable + 14)?);
+
+            let values = (0..first_count as usize * second_count as usize)
+                .map(|i| x_advance(data, subtable + 16 + i * record_size, first_format))
+                .collect::<Option<_>>()?;
+
+            Some(KerningTable::Classes { coverage, first, second, second_count, values })
+        }
+        _ => None,
+    }
+}
+
+//the pair adjustment lookups of the kern feature
+fn parse_gpos(data: &[u8], table: usize) -> Option<Vec<KerningTable>> {
+    let features = table + read_u16(data, table + 6)? as usize;
+    let lookups = table + read_u16(data, table + 8)? as usize;
+
+    let mut indices = vec![];
+    for i in 0..read_u16(data, features)? as usize {
+        let record = features + 2 + i * 6;
+        if data.get(record..record + 4)? != b"kern" {
+            continue;
+        }
+
+        let feature = features + read_u16(data, record + 4)? as usize;
+        for j in 0..read_u16(data, feature + 2)? as usize {
+            let index = read_u16(data, feature + 4 + j * 2)?;
+            if !indices.contains(&index) {
+                indices.push(index);
+            }
+        }
+    }
+
+    let mut tables = vec![];
+    for index in indices {
+        let lookup = lookups + read_u16(data, lookups + 2 + index as usize * 2)? as usize;
+        let lookup_type = read_u16(data, lookup)?;
+
+        for i in 0..read_u16(data, lookup + 4)? as usize {
+            let mut subtable = lookup + read_u16(data, lookup + 6 + i * 2)? as usize;
+            let mut subtable_type = lookup_type;
+
+            //extension subtables only hold a 32 bit offset to the real one
+            if subtable_type == 9 {
+                subtable_type = read_u16(data, subtable + 2)?;
+                subtable += read_u32(data, subtable + 4)? as usize;
+            }
+
+            if subtable_type == 2 {
+                tables.extend(parse_pair_adjustment(data, subtable));
+            }
+        }
+    }
+
+    Some(tables)
+}
+
+//a cff INDEX, the ranges are offsets into the whole file
+#[derive(Default)]
+struct CffIndex {
+    ranges: Vec<Range<usize>>,
+}
+
+impl CffIndex {
+    //the index and the offset right after it
+    fn parse(data: &[u8], offset: usize) -> Option<(Self, usize)> {
+        let count = read_u16(data, offset)? as usize;
+        if count == 0 {
+            return Some((Self::default(), offset + 2));
+        }
+
+        let size = read_u8(data, offset + 2)? as usize;
+        if !(1..=4).contains(&size) {
+            return None;
+        }
+
+        //offsets count from one, relative to the byte before the data
+        let data_start = offset + 3 + (count + 1) * size - 1;
+        let offsets: Vec<usize> = (0..=count)
+            .map(|i| (0..size).try_fold(0, |value, byte| Some(value << 8 | read_u8(data, offset + 3 + i * size + byte)? as usize)).map(|value| data_start + value))
+            .collect::<Option<_>>()?;
+
+        let end = *offsets.last()?;
+        if end > data.len() || offsets.windows(2).any(|pair| pair[1] < pair[0]) {
+            return None;
+        }
+
+        Some((Self { ranges: offsets.windows(2).map(|pair| pair[0]..pair[1]).collect() }, end))
+    }
+
+    //subroutine numbers are stored minus a bias that depends on how many there are
+    fn subroutine(&self, number: f32) -> Option<Range<usize>> {
+        let bias = match self.ranges.len() {
+            0..1240 => 107,
+            1240..33900 => 1131,
+            _ => 32768,
+        };
+
+        self.ranges.get(usize::try_from(number as i32 + bias).ok()?).cloned()
+    }
+}
+
+//operands by operator, two byte operators are 1200 plus their second byte
+fn parse_dict(data: &[u8], range: Range<usize>) -> Option<HashMap<u16, Vec<f64>>> {
+    let mut dict = HashMap::new();
+    let mut operands = vec![];
+    let mut position = range.start;
+
+    while position < range.end {
+        let b0 = read_u8(data, position)?;
+        position += 1;
+
+        match b0 {
+            0..=11 | 13..=21 => {
+                dict.insert(b0 as u16, std::mem::take(&mut operands));
+            }
+            12 => {
+                dict.insert(1200 + read_u8(data, position)? as u16, std::mem::take(&mut operands));
+                position += 1;
+            }
+            28 => {
+                operands.push(read_i16(data, position)? as f64);
+                position += 2;
+            }
+            29 => {
+                operands.push(read_u32(data, position)? as i32 as f64);
+                position += 4;
+            }
+            //a real, written as decimal nibbles
+            30 => {
+                let mut text = String::new();
+
+                'real: loop {
+                    let byte = read_u8(data, position)?;
+                    position += 1;
+
+                    for nibble in [byte >> 4, byte & 0xf] {
+                        match nibble {
+                            0..=9 => text.push((b'0' + nibble) as char),
+                            0xa => text.push('.'),
+                            0xb => text.push('E'),
+                            0xc => text.push_str("E-"),
+                            0xe => text.push('-'),
+                            0xf => break 'real,
+                            _ => (),
+                        }
+                    }
+                }
+
+                operands.push(text.parse().unwrap_or(0.0));
+            }
+            32..=246 => operands.push(b0 as f64 - 139.0),
+            247..=250 => {
+                operands.push((b0 as f64 - 247.0) * 256.0 + read_u8(data, position)? as f64 + 108.0);
+                position += 1;
+            }
+            251..=254 => {
+                operands.push(-(b0 as f64 - 251.0) * 256.0 - read_u8(data, position)? as f64 - 108.0);
+                position += 1;
+            }
+            _ => return None,
+        }
+    }
+
+    Some(dict)
+}
+
+const CHAR_STRINGS: u16 = 17;
+const PRIVATE: u16 = 18;
+const SUBRS: u16 = 19;
+const FD_ARRAY: u16 = 1236;
+const FD_SELECT: u16 = 1237;
+
+struct CffFont {
+    char_strings: CffIndex,
+    global_subrs: CffIndex,
+    //the private subroutines, one set per font dict in a cid font
+    local_subrs: Vec<CffIndex>,
+    //which set of local_subrs each glyph uses, empty when there is only one
+    fd_select: Vec<u8>,
+}
+
+impl CffFont {
+    fn parse(data: &[u8], table: Range<usize>, glyph_count: u16) -> Option<Self> {
+        let start = table.start;
+
+        //the header, then the name, top dict, string and global subroutine indices back to back
+        let (_, after_names) = CffIndex::parse(data, start + read_u8(data, start + 2)? as usize)?;
+        let (top_dicts, after_top_dicts) = CffIndex::parse(data, after_names)?;
+        let (_, after_strings) = CffIndex::parse(data, after_top_dicts)?;
+        let (global_subrs, _) = CffIndex::parse(data, after_strings)?;
+
+        let top = parse_dict(data, top_dicts.ranges.first()?.clone())?;
+        let offset = |dict: &HashMap<u16, Vec<f64>>, operator: u16| dict.get(&operator).and_then(|operands| operands.first()).map(|offset| start + *offset as usize);
+
+        let char_strings = CffIndex::parse(data, offset(&top, CHAR_STRINGS)?)?.0;
+
+        //the private dict's subroutine offset is relative to the private dict itself
+        let private_subrs = |dict: &HashMap<u16, Vec<f64>>| -> Option<CffIndex> {
+            let [size, private_offset] = dict.get(&PRIVATE)?[..] else { return None };
+            let private_start = start + private_offset as usize;
+            let private = parse_dict(data, private_start..private_start + size as usize)?;
+
+            Some(CffIndex::parse(data, private_start + *private.get(&SUBRS)?.first()? as usize)?.0)
+        };
+
+        let (local_subrs, fd_select) = match offset(&top, FD_ARRAY) {
+            Some(fd_array) => {
+                let local_subrs = CffIndex::parse(data, fd_array)?.0.ranges.into_iter()
+                    .map(|range| parse_dict(data, range).and_then(|dict| private_subrs(&dict)).unwrap_or_default())
+                    .collect();
+
+                (local_subrs, Self::parse_fd_select(data, offset(&top, FD_SELECT)?, glyph_count)?)
+            }
+            None => (vec![private_subrs(&top).unwrap_or_default()], vec![]),
+        };
+
+        Some(Self { char_strings, global_subrs, local_subrs, fd_select })
+    }
+
+    fn parse_fd_select(data: &[u8], offset: usize, glyph_count: u16) -> Option<Vec<u8>> {
+        let glyph_count = glyph_count as usize;
+
+        match read_u8(data, offset)? {
+            0 => data.get(offset + 1..offset + 1 + glyph_count).map(|select| select.to_vec()),
+            3 => {
+                let mut select = vec![0; glyph_count];
+
+                //each range runs up to the first glyph of the next, the last one to a sentinel
+                for i in 0..read_u16(data, offset + 1)? as usize {
+                    let record = offset + 3 + i * 3;
+                    let (first, font, next) = (read_u16(data, record)? as usize, read_u8(data, record + 2)?, read_u16(data, record + 3)? as usize);
+
+                    for glyph in select.iter_mut().take(next).skip(first) {
+                        *glyph = font;
+                    }
+                }
+
+                Some(select)
+            }
+            _ => None,
+        }
+    }
+
+    fn outline(&self, data: &[u8], glyph: u16) -> Option<Vec<Contour>> {
+        let local = self.local_subrs.get(self.fd_select.get(glyph as usize).copied().unwrap_or(0) as usize)?;
+
+        let mut charstring = Charstring {
+            data,
+            global: &self.global_subrs,
+            local,
+            stack: vec![],
+            stems: 0,
+            width_parsed: false,
+            point: Vector2::new(0.0, 0.0),
+            contours: vec![],
+            finished: false,
+        };
+
+        charstring.run(self.char_strings.ranges.get(glyph as usize)?.clone(), 0)?;
+        Some(charstring.contours)
+    }
+}
+
+//a type 2 charstring interpreter, hints are skipped and only the outline is kept
+struct Charstring<'a> {
+    data: &'a [u8],
+    global: &'a CffIndex,
+    local: &'a CffIndex,
+    stack: Vec<f32>,
+    stems: usize,
+    //the first stack clearing operator can have the glyph's width as an extra first operand
+    width_parsed: bool,
+    point: Vector2<f32>,
+    contours: Vec<Contour>,
+    finished: bool,
+}
+
+impl Charstring<'_> {
+    const MAX_STACK: usize = 48;
+    const MAX_DEPTH: usize = 10;
+
+    fn take_width(&mut self, has_width: bool) {
+        if !self.width_parsed && has_width && !self.stack.is_empty() {
+            self.stack.remove(0);
+        }
+        self.width_parsed = true;
+    }
+
+    fn move_to(&mut self, delta: Vector2<f32>) {
+        self.point += delta;
+        self.contours.push(Contour { start: self.point, segments: vec![] });
+    }
+
+    fn contour(&mut self) -> &mut Contour {
+        //drawing before the first move starts at the origin
+        if self.contours.is_empty() {
+            self.contours.push(Contour { start: self.point, segments: vec![] });
+        }
+        self.contours.last_mut().unwrap()
+    }
+
+    fn line(&mut self, dx: f32, dy: f32) {
+        self.point += Vector2::new(dx, dy);
+        let point = self.point;
+        self.contour().segments.push(PathSegment::Line(point));
+    }
+
+    //each control point and the end are relative to the point before them
+    fn curve(&mut self, a: (f32, f32), b: (f32, f32), c: (f32, f32)) {
+        let control_a = self.point + Vector2::new(a.0, a.1);
+        let control_b = control_a + Vector2::new(b.0, b.1);
+        self.point = control_b + Vector2::new(c.0, c.1);
+
+        let end = self.point;
+        self.contour().segments.push(PathSegment::Cubic(control_a, control_b, end));
+    }
+
+    //hvcurveto and vhcurveto, the curves alternate between starting horizontal and vertical
+    //and an odd operand at the very end bends the last curve's end
+    fn alternating_curves(&mut self, mut horizontal: bool) {
+        let operands = std::mem::take(&mut self.stack);
+        let mut i = 0;
+
+        while i + 4 <= operands.len() {
+            let [a, b, c, d] = [operands[i], operands[i + 1], operands[i + 2], operands[i + 3]];
+            let last = if operands.len() - i == 5 { operands[i + 4] } else { 0.0 };
+
+            if horizontal {
+                self.curve((a, 0.0), (b, c), (last, d));
+            } else {
+                self.curve((0.0, a), (b, c), (d, last));
+            }
+
+            horizontal = !horizontal;
+            i += 4;
+        }
+    }
+
+    fn run(&mut self, range: Range<usize>, depth: usize) -> Option<()> {
+        if depth > Self::MAX_DEPTH {
+            return None;
+        }
+
+        let data = self.data;
+        let mut position = range.start;
+
+        while position < range.end && !self.finished {
+            let b0 = read_u8(data, position)?;
+            position += 1;
+
+            if self.stack.len() > Self::MAX_STACK {
+                return None;
+            }
+
+            match b0 {
+                32..=246 => self.stack.push(b0 as f32 - 139.0),
+                247..=250 => {
+                    self.stack.push((b0 as f32 - 247.0) * 256.0 + read_u8(data, position)? as f32 + 108.0);
+                    position += 1;
+                }
+                251..=254 => {
+                    self.stack.push(-(b0 as f32 - 251.0) * 256.0 - read_u8(data, position)? as f32 - 108.0);
+                    position += 1;
+                }
+                28 => {
+                    self.stack.push(read_i16(data, position)? as f32);
+                    position += 2;
+                }
+                //16.16 fixed point
+                255 => {
+                    self.stack.push(read_u32(data, position)? as i32 as f32 / 65536.0);
+                    position += 4;
+                }
+                //hstem, vstem, hstemhm, vstemhm
+                1 | 3 | 18 | 23 => {
+                    self.take_width(self.stack.len() % 2 == 1);
+                    self.stems += self.stack.len() / 2;
+                    self.stack.clear();
+                }
+                //hintmask and cntrmask, operands left on the stack are an implied vstem
+                19 | 20 => {
+                    self.take_width(self.stack.len() % 2 == 1);
+                    self.stems += self.stack.len() / 2;
+                    self.stack.clear();
+                    position += self.stems.div_ceil(8);
+                }
+                //rmoveto, hmoveto, vmoveto
+                21 => {
+                    self.take_width(self.stack.len() > 2);
+                    let [dx, dy] = self.stack[..] else { return None };
+                    self.move_to(Vector2::new(dx, dy));
+                    self.stack.clear();
+                }
+                22 | 4 => {
+                    self.take_width(self.stack.len() > 1);
+                    let [delta] = self.stack[..] else { return None };
+                    self.move_to(if b0 == 22 { Vector2::new(delta, 0.0) } else { Vector2::new(0.0, delta) });
+                    self.stack.clear();
+                }
+                //rlineto
+                5 => {
+                    for pair in std::mem::take(&mut self.stack).chunks_exact(2) {
+                        self.line(pair[0], pair[1]);
+                    }
+                }
+                //hlineto and vlineto alternate between the two directions
+                6 | 7 => {
+                    for (i, delta) in std::mem::take(&mut self.stack).into_iter().enumerate() {
+                        if (i % 2 == 0) == (b0 == 6) {
+                            self.line(delta, 0.0);
+                        } else {
+                            self.line(0.0, delta);
+                        }
+                    }
+                }
+                //rrcurveto
+                8 => {
+                    for curve in std::mem::take(&mut self.stack).chunks_exact(6) {
+                        self.curve((curve[0], curve[1]), (curve[2], curve[3]), (curve[4], curve[5]));
+                    }
+                }
+                //rcurveline, curves then a line
+                24 => {
+                    let operands = std::mem::take(&mut self.stack);
+                    let split = operands.len().checked_sub(2)?;
+
+                    for curve in operands[..split].chunks_exact(6) {
+                        self.curve((curve[0], curve[1]), (curve[2], curve[3]), (curve[4], curve[5]));
+                    }
+                    self.line(operands[split], operands[split + 1]);
+                }
+                //rlinecurve, lines then a curve
+                25 => {
+                    let operands = std::mem::take(&mut self.stack);
+                    let split = operands.len().checked_sub(6)?;
+
+                    for pair in operands[..split].chunks_exact(2) {
+                        self.line(pair[0], pair[1]);
+                    }
+                    let curve = &operands[split..];
+                    self.curve((curve[0], curve[1]), (curve[2], curve[3]), (curve[4], curve[5]));
+                }
+                //vvcurveto and hhcurveto, an odd operand count starts with a sideways offset for the first curve
+                26 | 27 => {
+                    let mut operands = std::mem::take(&mut self.stack);
+                    let mut offset = if operands.len() % 2 == 1 { operands.remove(0) } else { 0.0 };
+
+                    for curve in operands.chunks_exact(4) {
+                        if b0 == 26 {
+                            self.curve((offset, curve[0]), (curve[1], curve[2]), (0.0, curve[3]));
+                        } else {
+                            self.curve((curve[0], offset), (curve[1], curve[2]), (curve[3], 0.0));
+                        }
+                        offset = 0.0;
+                    }
+                }
+                30 => self.alternating_curves(false),
+                31 => self.alternating_curves(true),
+                //callsubr and callgsubr, the subroutine works on the same stack
+                10 | 29 => {
+                    let number = self.stack.pop()?;
+                    let subroutine = if b0 == 10 { self.local } else { self.global }.subroutine(number)?;
+                    self.run(subroutine, depth + 1)?;
+                }
+                //return
+                11 => return Some(()),
+                //endchar, four operands left over would be an accented character which isn't supported
+                14 => {
+                    self.take_width(self.stack.len() == 1 || self.stack.len() == 5);
+                    self.stack.clear();
+                    self.finished = true;
+                }
+                12 => {
+                    let b1 = read_u8(data, position)?;
+                    position += 1;
+                    let operands = std::mem::take(&mut self.stack);
+
+                    match (b1, operands.as_slice()) {
+                        //flex, the depth operand only matters to hinting
+                        (35, [a0, a1, a2, a3, a4, a5, b0, b1, b2, b3, b4, b5, _]) => {
+                            self.curve((*a0, *a1), (*a2, *a3), (*a4, *a5));
+                            self.curve((*b0, *b1), (*b2, *b3), (*b4, *b5));
+                        }
+                        //hflex
+                        (34, [dx1, dx2, dy2, dx3, dx4, dx5, dx6]) => {
+                            self.curve((*dx1, 0.0), (*dx2, *dy2), (*dx3, 0.0));
+                            self.curve((*dx4, 0.0), (*dx5, -dy2), (*dx6, 0.0));
+                        }
+                        //hflex1
+                        (36, [dx1, dy1, dx2, dy2, dx3, dx4, dx5, dy5, dx6]) => {
+                            self.curve((*dx1, *dy1), (*dx2, *dy2), (*dx3, 0.0));
+                            self.curve((*dx4, 0.0), (*dx5, *dy5), (*dx6, -(dy1 + dy2 + dy5)));
+                        }
+                        //flex1, the last point goes back to the start on whichever axis moved less
+                        (37, [a0, a1, a2, a3, a4, a5, b0, b1, b2, b3, last]) => {
+                            let dx = a0 + a2 + a4 + b0 + b2;
+                            let dy = a1 + a3 + a5 + b1 + b3;
+
+                            self.curve((*a0, *a1), (*a2, *a3), (*a4, *a5));
+                            if dx.abs() > dy.abs() {
+                                self.curve((*b0, *b1), (*b2, *b3), (*last, -dy));
+                            } else {
+                                self.curve((*b0, *b1), (*b2, *b3), (-dx, *last));
+                            }
+                        }
+                        //the arithmetic and storage operators don't turn up in real fonts
+                        _ => (),
+                    }
+                }
+                _ => self.stack.clear(),
+            }
+        }
+
+        Some(())
+    }
+}
+
+enum Outlines {
+    //where each glyph starts in the file, with one more entry for where the last one ends
+    Glyf(Vec<usize>),
+    Cff(CffFont),
+}
+
+//the same glyph positioned along a line of text, in the units of the size it was shaped at
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RDGlyph {
+    pub id: u16,
+    //byte offset of the character in the text
+    pub cluster: usize,
+    //on the baseline, from the start of the line
+    pub position: Vector2<f32>,
+    //kerning with the next glyph included
+    pub advance: f32,
+}
+
+//every loaded font gets its own, glyph atlases tell fonts apart by it
+static NEXT_FONT_ID: AtomicU64 = AtomicU64::new(0);
+
+//a truetype or opentype font, outlines are read from the file data when a glyph is asked for
+pub struct RDFont {
+    id: u64,
+    data: Vec<u8>,
+    units_per_em: f32,
+    ascent: f32,
+    descent: f32,
+    line_gap: f32,
+    glyph_count: u16,
+    //offset of the cmap subtable in use and its format, 4 or 12
+    cmap: (usize, u16),
+    advances: Vec<u16>,
+    kerning: Vec<KerningTable>,
+    outlines: Outlines,
+}
+
+impl RDFont {
+    //collections use their first font
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, RDFontError> {
+        let face = if data.starts_with(b"ttcf") { read_u32(&data, 12).ok_or(RDFontError::Truncated)? as usize } else { 0 };
+
+        let mut tables = HashMap::new();
+        for i in 0..read_u16(&data, face + 4).ok_or(RDFontError::Truncated)? as usize {
+            let record = face + 12 + i * 16;
+            let tag: [u8; 4] = data.get(record..record + 4).and_then(|tag| tag.try_into().ok()).ok_or(RDFontError::Truncated)?;
+            let offset = read_u32(&data, record + 8).ok_or(RDFontError::Truncated)? as usize;
+            let length = read_u32(&data, record + 12).ok_or(RDFontError::Truncated)? as usize;
+
+            if offset + length > data.len() {
+                return Err(RDFontError::Truncated);
+            }
+            tables.insert(tag, offset..offset + length);
+        }
+
+        let table = |tag: &'static str| tables.get(tag.as_bytes()).cloned().ok_or(RDFontError::MissingTable(tag));
+
+        let head = table("head")?.start;
+        let units_per_em = truncated(read_u16(&data, head + 18))?.max(1) as f32;
+        let long_offsets = truncated(read_i16(&data, head + 50))? != 0;
+
+        let glyph_count = truncated(read_u16(&data, table("maxp")?.start + 4))?;
+
+        let hhea = table("hhea")?.start;
+        let ascent = truncated(read_i16(&data, hhea + 4))? as f32;
+        let descent = truncated(read_i16(&data, hhea + 6))? as f32;
+        let line_gap = truncated(read_i16(&data, hhea + 8))? as f32;
+        let metrics_count = truncated(read_u16(&data, hhea + 34))?.clamp(1, glyph_count.max(1)) as usize;
+
+        //glyphs past the last metric share its advance
+        let hmtx = table("hmtx")?.start;
+        let mut advances = truncated((0..metrics_count).map(|i| read_u16(&data, hmtx + i * 4)).collect::<Option<Vec<_>>>())?;
+        advances.resize(glyph_count as usize, *advances.last().unwrap_or(&0));
+
+        let cmap = Self::choose_cmap(&data, table("cmap")?.start).ok_or(RDFontError::Unsupported("no unicode cmap subtable"))?;
+
+        //gpos kerning replaces the kern table when a font has both
+        let gpos = tables.get(b"GPOS").and_then(|gpos| parse_gpos(&data, gpos.start)).filter(|kerning| !kerning.is_empty());
+        let kerning = gpos.or_else(|| tables.get(b"kern").and_then(|kern| parse_kern(&data, kern.start))).unwrap_or_default();
+
+        let outlines = match (tables.get(b"glyf"), tables.get(b"loca"), tables.get(b"CFF ")) {
+            (Some(glyf), Some(loca), _) => {
+                let offsets = (0..=glyph_count as usize).map(|i| match long_offsets {
+                    true => read_u32(&data, loca.start + i * 4).map(|offset| offset as usize),
+                    false => read_u16(&data, loca.start + i * 2).map(|offset| offset as usize * 2),
+                });
+
+                let offsets = truncated(offsets.map(|offset| offset.map(|offset| glyf.start + offset)).collect::<Option<Vec<_>>>())?;
+                if offsets.iter().any(|offset| *offset > glyf.end) {
+                    return Err(RDFontError::Truncated);
+                }
+                Outlines::Glyf(offsets)
+            }
+            (_, _, Some(cff)) => Outlines::Cff(truncated(CffFont::parse(&data, cff.clone(), glyph_count))?),
+            _ if tables.contains_key(b"CFF2") => return Err(RDFontError::Unsupported("cff2 outlines")),
+            _ => return Err(RDFontError::MissingTable("glyf")),
+        };
+
+        let id = NEXT_FONT_ID.fetch_add(1, Ordering::Relaxed);
+
+        Ok(Self { id, data, units_per_em, ascent, descent, line_gap, glyph_count, cmap, advances, kerning, outlines })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, RDFontError> {
+        Self::from_bytes(std::fs::read(path)?)
+    }
+
+    //format 12 reaches past the basic multilingual plane, so it wins over format 4
+    fn choose_cmap(data: &[u8], cmap: usize) -> Option<(usize, u16)> {
+        let mut best = None;
+
+        for i in 0..read_u16(data, cmap + 2)? as usize {
+            let record = cmap + 4 + i * 8;
+            let (platform, encoding) = (read_u16(data, record)?, read_u16(data, record + 2)?);
+            let subtable = cmap + read_u32(data, record + 4)? as usize;
+            let format = read_u16(data, subtable)?;
+
+            let unicode = platform == 0 || (platform == 3 && matches!(encoding, 1 | 10));
+            let rank = match (unicode, format) {
+                (true, 12) => 2,
+                (true, 4) => 1,
+                _ => continue,
+            };
+
+            if best.is_none_or(|(best_rank, _)| rank > best_rank) {
+                best = Some((rank, (subtable, format)));
+            }
+        }
+
+        best.map(|(_, cmap)| cmap)
+    }
+
+    fn lookup(&self, code: u32) -> Option<u16> {
+        let (data, (table, format)) = (self.data.as_slice(), self.cmap);
+
+        match format {
+            4 => {
+                let code = u16::try_from(code).ok()?;
+                let segments = read_u16(data, table + 6)? as usize / 2;
+                let ends = table + 14;
+                let starts = ends + segments * 2 + 2;
+                let deltas = starts + segments * 2;
+                let range_offsets = deltas + segments * 2;
+
+                //the first segment ending at or after the code
+                let (mut low, mut high) = (0, segments);
+                while low < high {
+                    let middle = (low + high) / 2;
+                    if read_u16(data, ends + middle * 2)? < code { low = middle + 1 } else { high = middle }
+                }
+
+                let start = read_u16(data, starts + low * 2)?;
+                if low == segments || code < start {
+                    return None;
+                }
+
+                let delta = read_u16(data, deltas + low * 2)?;
+                let range_offset = read_u16(data, range_offsets + low * 2)? as usize;
+
+                //a range offset points into the glyph array, counted from where the offset itself is stored
+                if range_offset == 0 {
+                    Some(code.wrapping_add(delta))
+                } else {
+                    let glyph = read_u16(data, range_offsets + low * 2 + range_offset + (code - start) as usize * 2)?;
+                    (glyph != 0).then(|| glyph.wrapping_add(delta))
+                }
+            }
+            12 => {
+                let groups = read_u32(data, table + 12)? as usize;
+
+                let (mut low, mut high) = (0, groups);
+                while low < high {
+                    let middle = (low + high) / 2;
+                    let group = table + 16 + middle * 12;
+
+                    if read_u32(data, group + 4)? < code {
+                        low = middle + 1;
+                    } else if read_u32(data, group)? > code {
+                        high = middle;
+                    } else {
+                        return u16::try_from(read_u32(data, group + 8)? + code - read_u32(data, group)?).ok();
+                    }
+                }
+
+                None
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn id(&self) -> u64 {
+        self.id
+    }
+
+    //0 is the font's missing glyph
+    pub fn glyph_index(&self, character: char) -> u16 {
+        self.lookup(character as u32).filter(|glyph| *glyph < self.glyph_count).unwrap_or(0)
+    }
+
+    pub fn glyph_count(&self) -> u16 {
+        self.glyph_count
+    }
+
+    pub fn units_per_em(&self) -> f32 {
+        self.units_per_em
+    }
+
+    //the metrics below are scaled so an em is size units
+    fn scale(&self, size: f32) -> f32 {
+        size / self.units_per_em
+    }
+
+    pub fn ascent(&self, size: f32) -> f32 {
+        self.ascent * self.scale(size)
+    }
+
+    //below the baseline, so usually negative
+    pub fn descent(&self, size: f32) -> f32 {
+        self.descent * self.scale(size)
+    }
+
+    //baseline to baseline
+    pub fn line_height(&self, size: f32) -> f32 {
+        (self.ascent - self.descent + self.line_gap) * self.scale(size)
+    }
+
+    pub fn advance(&self, glyph: u16, size: f32) -> f32 {
+        self.advances.get(glyph as usize).copied().unwrap_or(0) as f32 * self.scale(size)
+    }
+
+    //added to the advance of left when right follows it
+    pub fn kerning(&self, left: u16, right: u16, size: f32) -> f32 {
+        self.kerning.iter().find_map(|table| table.get(left, right)).unwrap_or(0) as f32 * self.scale(size)
+    }
+
+    //the glyph's contours in font units, broken glyphs come back empty
+    pub(crate) fn outline(&self, glyph: u16) -> Vec<Contour> {
+        let contours = match &self.outlines {
+            Outlines::Glyf(offsets) => {
+                let mut contours = vec![];
+                self.glyf_outline(offsets, glyph, RDAffine::identity(), 0, &mut contours).map(|_| contours)
+            }
+            Outlines::Cff(cff) => cff.outline(&self.data, glyph),
+        };
+
+        contours.unwrap_or_default()
+    }
+
+    fn glyf_outline(&self, offsets: &[usize], glyph: u16, transform: RDAffine, depth: usize, contours: &mut Vec<Contour>) -> Option<()> {
+        //composites can nest but a loop would never end
+        const MAX_DEPTH: usize = 8;
+
+        let (start, end) = (*offsets.get(glyph as usize)?, *offsets.get(glyph as usize + 1)?);
+        if depth > MAX_DEPTH || end < start {
+            return None;
+        }
+
+        //empty glyphs like the space have no data at all
+        let data = &self.data[start..end];
+        if data.is_empty() {
+            return Some(());
+        }
+
+        let contour_count = read_i16(data, 0)?;
+
+        //composites place other glyphs with a transform each
+        if contour_count < 0 {
+            let mut position = 10;
+
+            loop {
+                let flags = read_u16(data, position)?;
+                let component = read_u16(data, position + 2)?;
+                position += 4;
+
+                let (a, b) = if flags & 0x1 != 0 {
+                    position += 4;
+                    (read_i16(data, position - 4)? as f32, read_i16(data, position - 2)? as f32)
+                } else {
+                    position += 2;
+                    (read_u8(data, position - 2)? as i8 as f32, read_u8(data, position - 1)? as i8 as f32)
+                };
+
+                let f2dot14 = |offset: usize| read_i16(data, offset).map(|value| value as f32 / 16384.0);
+                let (x, y) = if flags & 0x8 != 0 {
+                    position += 2;
+                    let scale = f2dot14(position - 2)?;
+                    (Vector2::new(scale, 0.0), Vector2::new(0.0, scale))
+                } else if flags & 0x40 != 0 {
+                    position += 4;
+                    (Vector2::new(f2dot14(position - 4)?, 0.0), Vector2::new(0.0, f2dot14(position - 2)?))
+                } else if flags & 0x80 != 0 {
+                    position += 8;
+                    (Vector2::new(f2dot14(position - 8)?, f2dot14(position - 6)?), Vector2::new(f2dot14(position - 4)?, f2dot14(position - 2)?))
+                } else {
+                    (Vector2::new(1.0, 0.0), Vector2::new(0.0, 1.0))
+                };
+
+                //the arguments are either an offset or two points to line up, which are placed without one
+                let offset = if flags & 0x2 != 0 { Vector2::new(a, b) } else { Vector2::new(0.0, 0.0) };
+                self.glyf_outline(offsets, component, transform * RDAffine::new(x, y, offset), depth + 1, contours)?;
+
+                if flags & 0x20 == 0 {
+                    return Some(());
+                }
+            }
+        }
+
+        let contour_count = contour_count as usize;
+        let end_points: Vec<usize> = (0..contour_count).map(|i| read_u16(data, 10 + i * 2).map(usize::from)).collect::<Option<_>>()?;
+        let point_count = end_points.last().map_or(0, |last| last + 1);
+
+        let instructions = read_u16(data, 10 + contour_count * 2)? as usize;
+        let mut position = 12 + contour_count * 2 + instructions;
+
+        //flags can repeat, coordinates are deltas that are either a byte with the sign in the flags or a full i16
+        let mut flags = Vec::with_capacity(point_count);
+        while flags.len() < point_count {
+            let flag = read_u8(data, position)?;
+            position += 1;
+
+            let repeat = if flag & 0x8 != 0 {
+                position += 1;
+                read_u8(data, position - 1)? as usize
+            } else {
+                0
+            };
+            flags.extend(std::iter::repeat_n(flag, repeat + 1));
+        }
+        flags.truncate(point_count);
+
+        let mut coordinates = |short: u8, same_or_positive: u8| -> Option<Vec<f32>> {
+            let mut value = 0;
+
+            flags.iter().map(|flag| {
+                if flag & short != 0 {
+                    let delta = read_u8(data, position)? as i32;
+                    position += 1;
+                    value += if flag & same_or_positive != 0 { delta } else { -delta };
+                } else if flag & same_or_positive == 0 {
+                    value += read_i16(data, position)? as i32;
+                    position += 2;
+                }
+                Some(value as f32)
+            }).collect()
+        };
+
+        let xs = coordinates(0x2, 0x10)?;
+        let ys = coordinates(0x4, 0x20)?;
+
+        let mut first = 0;
+        for end in end_points {
+            if end < first || end >= point_count {
+                return None;
+            }
+
+            let points: Vec<_> = (first..=end).map(|i| (transform.transform_point(Vector2::new(xs[i], ys[i])), flags[i] & 0x1 != 0)).collect();
+            contours.extend(quadratic_contour(&points));
+            first = end + 1;
+        }
+
+        Some(())
+    }
+
+    //appends the glyph's contours with an em of size units and its origin at origin
+    pub(crate) fn append_glyph(&self, mut path: RDPath, glyph: u16, size: f32, origin: Vector2<f32>) -> RDPath {
+        let transform = RDAffine::from_translation(origin) * RDAffine::from_scale(Vector2::new(self.scale(size), self.scale(size)));
+
+        for contour in self.outline(glyph) {
+            let start = transform.transform_point(contour.start);
+            path = path.to(start.x, start.y);
+
+            for segment in contour.segments {
+                path = match segment {
+                    PathSegment::Line(point) => {
+                        let point = transform.transform_point(point);
+                        path.line(point.x, point.y)
+                    }
+                    PathSegment::Cubic(a, b, point) => {
+                        let [a, b, point] = [a, b, point].map(|point| transform.transform_point(point));
+                        path.cubic(a.x, a.y, b.x, b.y, point.x, point.y)
+                    }
+                };
+            }
+
+            path = path.close();
+        }
+
+        path
+    }
+
+    //one line of text, glyph for character with kerning and no line breaking
+    pub fn shape(&self, text: &str, size: f32) -> Vec<RDGlyph> {
+        let mut glyphs: Vec<RDGlyph> = vec![];
+        let mut x = 0.0;
+
+        for (cluster, character) in text.char_indices() {
+            let id = self.glyph_index(character);
+
+            if let Some(previous) = glyphs.last_mut() {
+                let kerning = self.kerning(previous.id, id, size);
+                previous.advance += kerning;
+                x += kerning;
+            }
+
+            let advance = self.advance(id, size);
+            glyphs.push(RDGlyph { id, cluster, position: Vector2::new(x, 0.0), advance });
+            x += advance;
+        }
+
+        glyphs
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use cgmath::{InnerSpace, Vector2};
+
+    use crate::path_builder::PathSegment;
+
+    use super::{Contour, RDFont, RDFontError};
+
+    //a font file from its tables, the directory's search fields are left at zero since nothing reads them
+    fn font_file(version: &[u8; 4], tables: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let mut file = version.to_vec();
+        file.extend((tables.len() as u16).to_be_bytes());
+        file.extend([0; 6]);
+
+        let mut offset = 12 + tables.len() * 16;
+        let mut contents = vec![];
+
+        for (tag, data) in tables {
+            file.extend(*tag);
+            file.extend([0; 4]);
+            file.extend((offset as u32).to_be_bytes());
+            file.extend((data.len() as u32).to_be_bytes());
+
+            let mut data = data.clone();
+            data.resize(data.len().next_multiple_of(4), 0);
+            offset += data.len();
+            contents.extend(data);
+        }
+
+        file.extend(contents);
+        file
+    }
+
+    fn words(values: &[i32]) -> Vec<u8> {
+        values.iter().flat_map(|value| (*value as u16).to_be_bytes()).collect()
+    }
+
+    //the tables every font needs besides its outlines and cmap
+    fn metric_tables(glyph_count: i32, advances: &[i32], long_offsets: bool) -> Vec<(&'static [u8; 4], Vec<u8>)> {
+        let mut head = vec![0; 54];
+        head[18..20].copy_from_slice(&1000u16.to_be_bytes());
+        head[50..52].copy_from_slice(&(long_offsets as u16).to_be_bytes());
+
+        let mut hhea = vec![0; 36];
+        hhea[4..10].copy_from_slice(&words(&[800, -200, 100]));
+        hhea[34..36].copy_from_slice(&(advances.len() as u16).to_be_bytes());
+
+        vec![
+            (b"head", head),
+            (b"hhea", hhea),
+            (b"hmtx", advances.iter().flat_map(|advance| words(&[*advance, 0])).collect()),
+            (b"maxp", [0x0000_5000u32.to_be_bytes().to_vec(), words(&[glyph_count])].concat()),
+        ]
+    }
+
+    //points are (x, y, on curve), coordinates go in as full words
+    fn simple_glyph(contours: &[&[(i32, i32, bool)]]) -> Vec<u8> {
+        let points: Vec<_> = contours.iter().flat_map(|contour| contour.iter()).collect();
+        let mut end = -1;
+        let end_points: Vec<i32> = contours.iter().map(|contour| {
+            end += contour.len() as i32;
+            end
+        }).collect();
+
+        let mut glyph = words(&[contours.len() as i32, 0, 0, 0, 0]);
+        glyph.extend(words(&end_points));
+        glyph.extend(words(&[0]));
+        glyph.extend(points.iter().map(|(_, _, on_curve)| *on_curve as u8));
+
+        for axis in [0, 1] {
+            let mut last = 0;
+            for point in points.iter() {
+                let value = if axis == 0 { point.0 } else { point.1 };
+                glyph.extend(words(&[value - last]));
+                last = value;
+            }
+        }
+
+        glyph
+    }
+
+    //glyph 1 is a square with a square hole, glyph 2 a quadratic hump, glyph 3 a half sized glyph 1 moved right
+    //'o' and 'p' map by delta, 'q' through the glyph array
+    pub(crate) fn truetype_font() -> RDFont {
+        let square = simple_glyph(&[
+            &[(0, 0, true), (600, 0, true), (600, 600, true), (0, 600, true)],
+            &[(200, 200, true), (200, 400, true), (400, 400, true), (400, 200, true)],
+        ]);
+        let hump = simple_glyph(&[&[(0, 0, true), (0, 100, false), (100, 100, false), (100, 0, true)]]);
+        //words for the offset, a scale of one half
+        let composite = [words(&[-1, 0, 0, 0, 0]), words(&[0x0b, 1, 700, 0, 0x2000])].concat();
+
+        let mut glyf = vec![];
+        let mut loca = vec![0];
+        for glyph in [vec![], square, hump, composite] {
+            glyf.extend(glyph);
+            glyf.resize(glyf.len().next_multiple_of(2), 0);
+            loca.push(glyf.len() as i32 / 2);
+        }
+
+        let cmap = [
+            words(&[0, 1, 3, 1]),
+            0x0cu32.to_be_bytes().to_vec(),
+            //segment count times two, then end codes, a pad, start codes, deltas, range offsets and the glyph array
+            words(&[4, 0, 0, 6, 0, 0, 0]),
+            words(&[0x70, 0x71, 0xffff, 0]),
+            words(&[0x6f, 0x71, 0xffff]),
+            words(&[1 - 0x6f, 0, 1]),
+            words(&[0, 4, 0]),
+            words(&[3]),
+        ].concat();
+
+        let kern = words(&[0, 1, 0, 20, 0x0001, 1, 0, 0, 0, 1, 2, -100]);
+
+        let mut tables = metric_tables(4, &[500, 700], false);
+        tables.extend([(b"cmap", cmap), (b"glyf", glyf), (b"kern", kern), (b"loca", words(&loca))]);
+
+        RDFont::from_bytes(font_file(&[0, 1, 0, 0], &tables)).unwrap()
+    }
+
+    fn cff_number(value: i32) -> Vec<u8> {
+        match value {
+            -107..=107 => vec![(value + 139) as u8],
+            108..=1131 => vec![((value - 108) / 256 + 247) as u8, ((value - 108) % 256) as u8],
+            -1131..=-108 => vec![((-value - 108) / 256 + 251) as u8, ((-value - 108) % 256) as u8],
+            _ => [vec![28], words(&[value])].concat(),
+        }
+    }
+
+    fn cff_index(items: &[Vec<u8>]) -> Vec<u8> {
+        let mut index = words(&[items.len() as i32]);
+        if items.is_empty() {
+            return index;
+        }
+
+        index.push(2);
+        let mut offset = 1;
+        index.extend(words(&[offset]));
+        for item in items {
+            offset += item.len() as i32;
+            index.extend(words(&[offset]));
+        }
+
+        index.extend(items.concat());
+        index
+    }
+
+    //a cff outline that uses hints, both kinds of subroutines and an alternating curve,
+    //glyph 1 is mapped from 'A' and an emoji through a format 12 cmap and kerned against itself by class in gpos
+    fn opentype_font() -> RDFont {
+        let operator = |values: &[i32], operator: u8| [values.iter().flat_map(|value| cff_number(*value)).collect(), vec![operator]].concat();
+
+        let glyph = [
+            //the width, then a stem and a hint mask with a byte for it
+            operator(&[500, 0, 10], 1),
+            vec![19, 0x80],
+            operator(&[10, 20], 21),
+            operator(&[100, 100], 6),
+            //the global subroutine finishes the line with the operand left here
+            operator(&[-100, -107], 29),
+            operator(&[-107], 10),
+            operator(&[300, 0], 21),
+            operator(&[50, 50, 50, 50], 30),
+            vec![14],
+        ].concat();
+
+        let char_strings = cff_index(&[vec![14], glyph]);
+        let global_subrs = cff_index(&[[cff_number(0), vec![5, 11]].concat()]);
+        let local_subrs = cff_index(&[[cff_number(0), cff_number(-100), vec![5, 11]].concat()]);
+
+        //five byte integers keep the top dict the same size whatever the offsets come to
+        let long = |value: usize| [vec![29], (value as u32).to_be_bytes().to_vec()].concat();
+        let private = [long(6), vec![19]].concat();
+
+        let header = vec![1, 0, 4, 1];
+        let names = cff_index(&[b"A".to_vec()]);
+        let top_dict_size = 3 + 2 * 2 + 17;
+        let char_strings_offset = header.len() + names.len() + top_dict_size + 2 + global_subrs.len();
+        let private_offset = char_strings_offset + char_strings.len();
+
+        let top_dict = [long(char_strings_offset), vec![17], long(private.len()), long(private_offset), vec![18]].concat();
+        let cff = [header, names, cff_index(&[top_dict]), cff_index(&[]), global_subrs, char_strings, private, local_subrs].concat();
+
+        let cmap = [
+            words(&[0, 1, 3, 10]),
+            0x0cu32.to_be_bytes().to_vec(),
+            words(&[12, 0]),
+            [40u32, 0, 2, 0x41, 0x41, 1, 0x1f600, 0x1f600, 1].iter().flat_map(|value| value.to_be_bytes()).collect(),
+        ].concat();
+
+        //one kern feature with one lookup holding a class based pair adjustment, glyph 1 is in class 1 on both sides
+        let gpos = [
+            words(&[1, 0, 10, 12, 26]),
+            words(&[0]),
+            words(&[1]), b"kern".to_vec(), words(&[8, 0, 1, 0]),
+            words(&[1, 4]),
+            words(&[2, 0, 1, 8]),
+            words(&[2, 24, 4, 0, 30, 40, 2, 2, 0, 0, 0, -80]),
+            words(&[1, 1, 1]),
+            words(&[2, 1, 1, 1, 1]),
+            words(&[1, 1, 1, 1]),
+        ].concat();
+
+        let mut tables = metric_tables(2, &[500, 600], false);
+        tables.extend([(b"CFF ", cff), (b"GPOS", gpos), (b"cmap", cmap)]);
+
+        RDFont::from_bytes(font_file(b"OTTO", &tables)).unwrap()
+    }
+
+    fn points(contour: &Contour) -> Vec<Vector2<f32>> {
+        contour.segments.iter().map(|segment| match segment {
+            PathSegment::Line(point) | PathSegment::Cubic(_, _, point) => *point,
+        }).collect()
+    }
+
+    #[test]
+    fn test_truetype() {
+        let font = truetype_font();
+
+        assert_eq!(['o', 'p', 'q', 'x'].map(|character| font.glyph_index(character)), [1, 2, 3, 0]);
+        assert_eq!((font.glyph_count(), font.units_per_em()), (4, 1000.0));
+        assert_eq!((font.ascent(10.0), font.descent(10.0), font.line_height(10.0)), (8.0, -2.0, 11.0));
+        assert_eq!([0, 1, 2, 3].map(|glyph| font.advance(glyph, 1000.0)), [500.0, 700.0, 700.0, 700.0]);
+        assert_eq!((font.kerning(1, 2, 10.0), font.kerning(2, 1, 10.0)), (-1.0, 0.0));
+
+        let glyphs = font.shape("opo", 10.0);
+        assert_eq!(glyphs.iter().map(|glyph| (glyph.cluster, glyph.position.x, glyph.advance)).collect::<Vec<_>>(), [(0, 0.0, 6.0), (1, 6.0, 7.0), (2, 13.0, 7.0)]);
+
+        let square = font.outline(1);
+        assert_eq!(square.len(), 2);
+        assert_eq!(square[1].start, Vector2::new(200.0, 200.0));
+        assert_eq!(points(&square[1]), [Vector2::new(200.0, 400.0), Vector2::new(400.0, 400.0), Vector2::new(400.0, 200.0), Vector2::new(200.0, 200.0)]);
+
+        //two controls in a row have an on curve point halfway between them
+        let hump = &font.outline(2)[0];
+        assert_eq!(points(hump), [Vector2::new(50.0, 100.0), Vector2::new(100.0, 0.0), Vector2::new(0.0, 0.0)]);
+        let PathSegment::Cubic(control_a, control_b, _) = hump.segments[0] else { panic!() };
+        assert!((control_a - Vector2::new(0.0, 200.0 / 3.0)).magnitude() < 1e-4);
+        assert!((control_b - Vector2::new(50.0 / 3.0, 100.0)).magnitude() < 1e-4);
+
+        let composite = font.outline(3);
+        assert_eq!(composite.len(), 2);
+        assert_eq!(composite[0].start, Vector2::new(700.0, 0.0));
+        assert_eq!(points(&composite[0])[1], Vector2::new(1000.0, 300.0));
+
+        assert!(font.outline(0).is_empty());
+        assert!(matches!(RDFont::from_bytes(vec![0, 1, 0, 0, 0, 1]), Err(RDFontError::Truncated)));
+        assert!(matches!(RDFont::from_bytes(font_file(&[0, 1, 0, 0], &[])), Err(RDFontError::MissingTable("head"))));
+    }
+
+    #[test]
+    fn test_cff() {
+        let font = opentype_font();
+
+        assert_eq!(['A', '😀', 'B'].map(|character| font.glyph_index(character)), [1, 1, 0]);
+        assert_eq!((font.kerning(1, 1, 1000.0), font.kerning(1, 0, 1000.0), font.kerning(0, 1, 1000.0)), (-80.0, 0.0, 0.0));
+
+        let contours = font.outline(1);
+        assert_eq!(contours.len(), 2);
+
+        assert_eq!(contours[0].start, Vector2::new(10.0, 20.0));
+        assert_eq!(points(&contours[0]), [Vector2::new(110.0, 20.0), Vector2::new(110.0, 120.0), Vector2::new(10.0, 120.0), Vector2::new(10.0, 20.0)]);
+
+        assert_eq!(contours[1].start, Vector2::new(310.0, 20.0));
+        assert_eq!(contours[1].segments, [PathSegment::Cubic(Vector2::new(310.0, 70.0), Vector2::new(360.0, 120.0), Vector2::new(410.0, 120.0))]);
+    }
+}
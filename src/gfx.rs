@@ -0,0 +1,834 @@
+use std::{fmt, sync::Arc};
+
+use cgmath::Vector2;
+use winit::window::Window;
+
+use crate::{atlas::RDAtlasUpdate, backend::{RDBackend, RDWindowBackend}, buffer::{BufferTooLarge, GfxBuffer}, image::RDImage, math::RDRect, scene::{RDGfxUpdate, RDView}, RDMultisample, RDOptions, RDRenderStats, RDStorage, RDVertex, CLEAR_COLOR};
+
+//rgba so a readback needs no swizzling, srgb to match what a window surface shows
+pub(crate) const OFFSCREEN_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+//starting sizes, the buffers double whenever the scene outgrows them
+const INITIAL_VERTEX_CAPACITY: u64 = 1024;
+const INITIAL_INDEX_CAPACITY: u64 = 2048;
+const INITIAL_OBJECT_CAPACITY: u64 = 256;
+const INITIAL_CAMERA_CAPACITY: u64 = 4;
+
+const CAMERA_SIZE: u64 = std::mem::size_of::<[[f32; 2]; 3]>() as u64;
+
+#[derive(Debug)]
+pub enum RDGfxError {
+    Surface(wgpu::SurfaceError),
+    //the scene needs a buffer past the device's limit, both in bytes
+    BufferTooLarge { requested: u64, limit: u64 },
+}
+
+impl fmt::Display for RDGfxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RDGfxError::Surface(error) => write!(f, "failed to get the next frame: {error}"),
+            RDGfxError::BufferTooLarge { requested, limit } => write!(f, "a {requested} byte buffer is past the device limit of {limit} bytes"),
+        }
+    }
+}
+
+impl std::error::Error for RDGfxError {}
+
+impl From<wgpu::SurfaceError> for RDGfxError {
+    fn from(error: wgpu::SurfaceError) -> Self {
+        RDGfxError::Surface(error)
+    }
+}
+
+impl From<BufferTooLarge> for RDGfxError {
+    fn from(BufferTooLarge { requested, limit }: BufferTooLarge) -> Self {
+        RDGfxError::BufferTooLarge { requested, limit }
+    }
+}
+
+//everything needed to draw a scene, into the window surface or an offscreen texture
+pub(crate) struct GfxState {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    format: wgpu::TextureFormat,
+    //size of the textures drawn into
+    width: u32,
+    height: u32,
+    render_pipeline: wgpu::RenderPipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    camera_bind_group_layout: wgpu::BindGroupLayout,
+    camera_bind_group: wgpu::BindGroup,
+    //each camera's matrix sits on its own dynamic offset
+    camera_stride: u64,
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    texture_bind_group: wgpu::BindGroup,
+    //a single empty pixel until the scene's glyph atlas first goes up
+    atlas: wgpu::Texture,
+    atlas_sampler: wgpu::Sampler,
+    sample_count: u32,
+    msaa_view: Option<wgpu::TextureView>,
+    
+    vertex_buffer: GfxBuffer,
+    index_buffer: GfxBuffer,
+    storage_buffer: GfxBuffer,
+    camera_buffer: GfxBuffer,
+}
+
+pub(crate) struct WindowSurface {
+    pub(crate) surface: wgpu::Surface<'static>,
+    pub(crate) config: wgpu::SurfaceConfiguration,
+}
+
+impl GfxState {
+    //the multisampled target the pipeline draws into, resolved onto the surface texture
+    fn create_msaa_view(device: &wgpu::Device, format: wgpu::TextureFormat, width: u32, height: u32, sample_count: u32) -> Option<wgpu::TextureView> {
+        if sample_count == 1 {
+            return None;
+        }
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Multisample Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+
+        Some(texture.create_view(&wgpu::TextureViewDescriptor::default()))
+    }
+
+    fn create_atlas(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Glyph Atlas"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        })
+    }
+
+    pub(crate) async fn request_device(adapter: &wgpu::Adapter) -> Result<(wgpu::Device, wgpu::Queue), wgpu::RequestDeviceError> {
+        adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                required_features: wgpu::Features::default(),
+                required_limits: wgpu::Limits::default(),
+                label: None,
+                memory_hints: Default::default(),
+            },
+            None,
+        ).await
+    }
+
+    pub(crate) fn new(adapter: &wgpu::Adapter, device: wgpu::Device, queue: wgpu::Queue, format: wgpu::TextureFormat, width: u32, height: u32, multisample: RDMultisample) -> Self {
+        //fall back to the largest supported count below the requested one
+        let format_flags = adapter.get_texture_format_features(format).flags;
+        let sample_count = [8, 4, 1].into_iter()
+            .filter(|count| *count <= multisample.sample_count())
+            .find(|count| format_flags.sample_count_supported(*count))
+            .unwrap_or(1);
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    count: None,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer { 
+                        ty: wgpu::BufferBindingType::Storage { read_only: true }, 
+                        has_dynamic_offset: false, 
+                        min_binding_size: None,
+                    }
+                }
+            ],
+        });
+        
+        let storage_buffer = GfxBuffer::new(
+            &device,
+            "Storage Buffer",
+            wgpu::BufferUsages::STORAGE,
+            std::mem::size_of::<RDStorage>() as u64 * INITIAL_OBJECT_CAPACITY,
+        );
+        
+        let bind_group = GfxState::create_bind_group(&device, &bind_group_layout, &storage_buffer);
+
+        let camera_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Camera Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    count: None,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(CAMERA_SIZE),
+                    }
+                }
+            ],
+        });
+
+        let camera_stride = CAMERA_SIZE.next_multiple_of(device.limits().min_uniform_buffer_offset_alignment as u64);
+
+        let camera_buffer = GfxBuffer::new(
+            &device,
+            "Camera Buffer",
+            wgpu::BufferUsages::UNIFORM,
+            camera_stride * INITIAL_CAMERA_CAPACITY,
+        );
+
+        let camera_bind_group = GfxState::create_camera_bind_group(&device, &camera_bind_group_layout, &camera_buffer);
+
+        let texture_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Texture Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    count: None,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    }
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    count: None,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                }
+            ],
+        });
+
+        //linear filtering is what lets the distance field be sampled between its pixels
+        let atlas_sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Glyph Atlas Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let atlas = GfxState::create_atlas(&device, 1, 1);
+        let texture_bind_group = GfxState::create_texture_bind_group(&device, &texture_bind_group_layout, &atlas, &atlas_sampler);
+
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Render Pipeline Layout"),
+                bind_group_layouts: &[
+                    &bind_group_layout,
+                    &camera_bind_group_layout,
+                    &texture_bind_group_layout,
+                ],
+                push_constant_ranges: &[],
+            });
+
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main", // 1.
+                buffers: &[
+                    RDVertex::desc(),
+                ], // 2.
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState { // 3.
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState { // 4.
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList, // 1.
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw, // 2.
+                //negative scale mirrors triangles, which flips their winding
+                cull_mode: None,
+                // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
+                polygon_mode: wgpu::PolygonMode::Fill,
+                // Requires Features::DEPTH_CLIP_CONTROL
+                unclipped_depth: false,
+                // Requires Features::CONSERVATIVE_RASTERIZATION
+                conservative: false,
+            },
+            depth_stencil: None, // 1.
+            multisample: wgpu::MultisampleState {
+                count: sample_count, // 2.
+                mask: !0, // 3.
+                alpha_to_coverage_enabled: false, // 4.
+            },
+            multiview: None, // 5.
+            cache: None, // 6.
+        });
+
+        let vertex_buffer = GfxBuffer::new(
+            &device,
+            "Vertex Buffer",
+            wgpu::BufferUsages::VERTEX,
+            std::mem::size_of::<RDVertex>() as u64 * INITIAL_VERTEX_CAPACITY,
+        );
+                
+        let index_buffer = GfxBuffer::new(
+            &device,
+            "Index Buffer",
+            wgpu::BufferUsages::INDEX,
+            std::mem::size_of::<u32>() as u64 * INITIAL_INDEX_CAPACITY,
+        );
+
+        let msaa_view = GfxState::create_msaa_view(&device, format, width, height, sample_count);
+
+        GfxState {
+            bind_group_layout,
+            bind_group,
+            camera_bind_group_layout,
+            camera_bind_group,
+            camera_stride,
+            camera_buffer,
+            texture_bind_group_layout,
+            texture_bind_group,
+            atlas,
+            atlas_sampler,
+            sample_count,
+            msaa_view,
+            storage_buffer,
+            index_buffer,
+            vertex_buffer,
+            device,
+            queue,
+            format,
+            width,
+            height,
+            render_pipeline,
+        }
+    }
+
+    pub(crate) fn resize(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+        self.msaa_view = GfxState::create_msaa_view(&self.device, self.format, width, height, self.sample_count);
+    }
+
+    //records the views' draws into the target, which must be width x height
+    fn draw(&mut self, encoder: &mut wgpu::CommandEncoder, target: &wgpu::TextureView, views: &[RDView]) -> Result<(), BufferTooLarge> {
+        if self.camera_buffer.reserve(&self.device, &self.queue, views.len() as u64 * self.camera_stride)? {
+            self.camera_bind_group = GfxState::create_camera_bind_group(&self.device, &self.camera_bind_group_layout, &self.camera_buffer);
+        }
+
+        for (i, view) in views.iter().enumerate() {
+            self.queue.write_buffer(&self.camera_buffer.buffer, i as u64 * self.camera_stride, bytemuck::cast_slice(&view.transform));
+        }
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: self.msaa_view.as_ref().unwrap_or(target),
+                resolve_target: self.msaa_view.as_ref().map(|_| target),
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color {
+                        r: CLEAR_COLOR[0] as f64,
+                        g: CLEAR_COLOR[1] as f64,
+                        b: CLEAR_COLOR[2] as f64,
+                        a: CLEAR_COLOR[3] as f64,
+                    }),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.set_bind_group(2, &self.texture_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.buffer.slice(..), wgpu::IndexFormat::Uint32);
+
+        let surface = RDRect::new(Vector2::new(0.0, 0.0), Vector2::new(self.width as f32, self.height as f32));
+
+        for (i, view) in views.iter().enumerate() {
+            //the scene already cut the viewport down to the surface, this only guards against rounding
+            let Some(viewport) = view.viewport.intersection(&surface) else { continue };
+            if viewport.width() < 1.0 || viewport.height() < 1.0 {
+                continue;
+            }
+
+            render_pass.set_viewport(viewport.min.x, viewport.min.y, viewport.width(), viewport.height(), 0.0, 1.0);
+            render_pass.set_scissor_rect(viewport.min.x as u32, viewport.min.y as u32, viewport.width() as u32, viewport.height() as u32);
+            render_pass.set_bind_group(1, &self.camera_bind_group, &[(i as u64 * self.camera_stride) as u32]);
+
+            for draw in view.draws.iter() {
+                render_pass.draw_indexed(draw.indicies.clone(), draw.base_vertex, 0..1);
+            }
+        }
+
+        //the gl backend resolves multisampling with the last scissor still set, leaving the rest unresolved
+        render_pass.set_scissor_rect(0, 0, self.width, self.height);
+
+        Ok(())
+    }
+
+    //writing past the end of a buffer that couldn't grow would fail validation, nothing is written then
+    fn upload_geometry(&mut self, update: &RDGfxUpdate, stats: &mut RDRenderStats) -> Result<(), BufferTooLarge> {
+        let vertex_size = std::mem::size_of::<RDVertex>() as u64;
+        let index_size = std::mem::size_of::<u32>() as u64;
+
+        for (buffer, size) in [(&mut self.vertex_buffer, update.vertex_capacity as u64 * vertex_size), (&mut self.index_buffer, update.index_capacity as u64 * index_size)] {
+            if buffer.reserve(&self.device, &self.queue, size)? {
+                stats.buffer_reallocations += 1;
+            }
+        }
+
+        for (offset, vertices) in update.vertices.iter() {
+            let bytes: &[u8] = bytemuck::cast_slice(vertices);
+            self.queue.write_buffer(&self.vertex_buffer.buffer, *offset as u64 * vertex_size, bytes);
+            stats.uploaded_bytes += bytes.len() as u64;
+        }
+        for (offset, indicies) in update.indicies.iter() {
+            let bytes: &[u8] = bytemuck::cast_slice(indicies);
+            self.queue.write_buffer(&self.index_buffer.buffer, *offset as u64 * index_size, bytes);
+            stats.uploaded_bytes += bytes.len() as u64;
+        }
+
+        stats.vertex_buffer_size = self.vertex_buffer.size();
+        stats.index_buffer_size = self.index_buffer.size();
+
+        Ok(())
+    }
+
+    fn upload_objects(&mut self, update: &RDGfxUpdate, stats: &mut RDRenderStats) -> Result<(), BufferTooLarge> {
+        let object_size = std::mem::size_of::<RDStorage>() as u64;
+
+        if self.storage_buffer.reserve(&self.device, &self.queue, update.object_capacity as u64 * object_size)? {
+            self.bind_group = GfxState::create_bind_group(&self.device, &self.bind_group_layout, &self.storage_buffer);
+            stats.buffer_reallocations += 1;
+        }
+
+        for (offset, storage) in update.storage.iter() {
+            let bytes: &[u8] = bytemuck::cast_slice(storage);
+            self.queue.write_buffer(&self.storage_buffer.buffer, *offset as u64 * object_size, bytes);
+            stats.uploaded_bytes += bytes.len() as u64;
+        }
+
+        stats.storage_buffer_size = self.storage_buffer.size();
+
+        Ok(())
+    }
+
+    fn upload_textures(&mut self, update: &RDGfxUpdate, stats: &mut RDRenderStats) {
+        let Some(RDAtlasUpdate { width, height, rows, pixels }) = &update.atlas else { return };
+
+        if (self.atlas.width(), self.atlas.height()) != (*width, *height) {
+            self.atlas = GfxState::create_atlas(&self.device, *width, *height);
+            self.texture_bind_group = GfxState::create_texture_bind_group(&self.device, &self.texture_bind_group_layout, &self.atlas, &self.atlas_sampler);
+        }
+
+        self.queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.atlas,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x: 0, y: rows.start, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            pixels,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(*width),
+                rows_per_image: None,
+            },
+            wgpu::Extent3d {
+                width: *width,
+                height: rows.len() as u32,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        stats.uploaded_bytes += pixels.len() as u64;
+    }
+
+    fn create_texture_bind_group(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, atlas: &wgpu::Texture, sampler: &wgpu::Sampler) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Glyph Atlas"),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&atlas.create_view(&wgpu::TextureViewDescriptor::default())),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                }
+            ],
+            layout,
+        })
+    }
+
+    fn create_camera_bind_group(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, camera_buffer: &GfxBuffer) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Camera Buffer"),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: &camera_buffer.buffer,
+                        offset: 0,
+                        size: wgpu::BufferSize::new(CAMERA_SIZE),
+                    }),
+                }
+            ],
+            layout,
+        })
+    }
+
+    fn create_bind_group(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, storage_buffer: &GfxBuffer) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Storage Buffer"),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: storage_buffer.buffer.as_entire_binding(),
+                }
+            ],
+            layout,
+        })
+    }
+}
+
+enum WgpuTarget {
+    Window(WindowSurface),
+    //recreated whenever the size changes
+    Offscreen(Option<wgpu::Texture>),
+}
+
+struct WgpuFrame {
+    encoder: wgpu::CommandEncoder,
+    view: wgpu::TextureView,
+    //only set when drawing into the window
+    surface_texture: Option<wgpu::SurfaceTexture>,
+    //a copy of the frame for surfaces that can't be copied from directly
+    mirror: Option<wgpu::Texture>,
+}
+
+//readbacks only handle 8 bit rgba, the flag says whether red and blue are swapped
+fn pixel_order(format: wgpu::TextureFormat) -> Option<bool> {
+    match format {
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => Some(false),
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => Some(true),
+        _ => None,
+    }
+}
+
+//draws with wgpu into a window surface or an offscreen texture that is read back after every frame
+pub struct RDWgpuBackend {
+    gfx: GfxState,
+    target: WgpuTarget,
+    frame: Option<WgpuFrame>,
+    //the next window frame is read back before it is presented
+    capture: bool,
+}
+
+impl RDWgpuBackend {
+    //picks an adapter able to draw into the window and configures its surface
+    pub(crate) async fn for_window(window: Arc<Window>, multisample: RDMultisample) -> Self {
+        let size = window.inner_size();
+
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            ..Default::default()
+        });
+        
+        let surface = instance.create_surface(window).unwrap();
+
+        let adapter = instance.request_adapter(
+            &wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: Some(&surface),
+                force_fallback_adapter: false,
+            },
+        ).await.unwrap();
+
+        let (device, queue) = GfxState::request_device(&adapter).await.unwrap();
+
+        let surface_caps = surface.get_capabilities(&adapter);
+
+        let surface_format = surface_caps.formats.iter()
+            .find(|f| f.is_srgb())
+            .copied()
+            .unwrap_or(surface_caps.formats[0]);
+
+        //copying straight from the surface saves drawing a mirror for screenshots
+        let usage = wgpu::TextureUsages::RENDER_ATTACHMENT | (surface_caps.usages & wgpu::TextureUsages::COPY_SRC);
+
+        let config = wgpu::SurfaceConfiguration {
+            usage,
+            format: surface_format,
+            width: size.width,
+            height: size.height,
+            present_mode: surface_caps.present_modes[0],
+            alpha_mode: surface_caps.alpha_modes[0],
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+
+        surface.configure(&device, &config);
+
+        Self {
+            gfx: GfxState::new(&adapter, device, queue, surface_format, size.width, size.height, multisample),
+            target: WgpuTarget::Window(WindowSurface { surface, config }),
+            frame: None,
+            capture: false,
+        }
+    }
+
+    //the gfx state has to be created for OFFSCREEN_FORMAT
+    pub(crate) fn offscreen(gfx: GfxState) -> Self {
+        Self {
+            gfx,
+            target: WgpuTarget::Offscreen(None),
+            frame: None,
+            capture: false,
+        }
+    }
+
+    fn create_target(&self, label: &str, format: wgpu::TextureFormat) -> wgpu::Texture {
+        self.gfx.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width: self.gfx.width,
+                height: self.gfx.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        })
+    }
+
+    pub(crate) fn resize(&mut self, width: u32, height: u32) {
+        if width == 0 || height == 0 || (width, height) == self.size() {
+            return;
+        }
+
+        match &mut self.target {
+            WgpuTarget::Window(window_surface) => {
+                window_surface.config.width = width;
+                window_surface.config.height = height;
+                window_surface.surface.configure(&self.gfx.device, &window_surface.config);
+            }
+            WgpuTarget::Offscreen(texture) => *texture = None,
+        }
+
+        self.gfx.resize(width, height);
+    }
+
+    //submits the encoder along with the copy, the texture has to be in one of the formats pixel_order knows
+    fn read_back(&self, mut encoder: wgpu::CommandEncoder, texture: &wgpu::Texture) -> RDImage {
+        let (width, height) = (texture.width(), texture.height());
+        let swap_red_blue = pixel_order(texture.format()).expect("unreadable texture format");
+
+        //rows in a texture copy have to start on 256 byte boundaries
+        let row_size = width * 4;
+        let padded_row_size = row_size.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+
+        let readback = self.gfx.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Readback Buffer"),
+            size: padded_row_size as u64 * height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &readback,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_size),
+                    rows_per_image: Some(height),
+                },
+            },
+            texture.size(),
+        );
+
+        self.gfx.queue.submit(std::iter::once(encoder.finish()));
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        readback.slice(..).map_async(wgpu::MapMode::Read, move |result| sender.send(result).unwrap());
+        self.gfx.device.poll(wgpu::Maintain::Wait);
+        receiver.recv().unwrap().expect("failed to map the readback buffer");
+
+        let mut image = RDImage::new(width, height);
+
+        {
+            let data = readback.slice(..).get_mapped_range();
+
+            for (row, padded_row) in image.pixels.chunks_exact_mut(row_size as usize).zip(data.chunks_exact(padded_row_size as usize)) {
+                row.copy_from_slice(&padded_row[..row_size as usize]);
+
+                if swap_red_blue {
+                    row.chunks_exact_mut(4).for_each(|pixel| pixel.swap(0, 2));
+                }
+            }
+        }
+
+        readback.unmap();
+
+        image
+    }
+}
+
+impl RDBackend for RDWgpuBackend {
+    type Error = RDGfxError;
+
+    fn begin_frame(&mut self) -> Result<(), RDGfxError> {
+        //a frame that failed half way is dropped without being presented, which frees its surface texture
+        self.frame = None;
+
+        let (surface_texture, view) = match &mut self.target {
+            WgpuTarget::Window(window_surface) => {
+                let surface_texture = window_surface.surface.get_current_texture()?;
+                let view = surface_texture.texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+                (Some(surface_texture), view)
+            }
+            WgpuTarget::Offscreen(None) => {
+                let texture = self.create_target("Offscreen Target", OFFSCREEN_FORMAT);
+                let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+                self.target = WgpuTarget::Offscreen(Some(texture));
+
+                (None, view)
+            }
+            WgpuTarget::Offscreen(Some(texture)) => (None, texture.create_view(&wgpu::TextureViewDescriptor::default())),
+        };
+
+        let encoder = self.gfx.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Render Encoder"),
+        });
+
+        self.frame = Some(WgpuFrame { encoder, view, surface_texture, mirror: None });
+
+        Ok(())
+    }
+
+    fn size(&self) -> (u32, u32) {
+        (self.gfx.width, self.gfx.height)
+    }
+
+    fn upload_geometry(&mut self, update: &RDGfxUpdate, stats: &mut RDRenderStats) -> Result<(), RDGfxError> {
+        Ok(self.gfx.upload_geometry(update, stats)?)
+    }
+
+    fn upload_objects(&mut self, update: &RDGfxUpdate, stats: &mut RDRenderStats) -> Result<(), RDGfxError> {
+        Ok(self.gfx.upload_objects(update, stats)?)
+    }
+
+    fn upload_textures(&mut self, update: &RDGfxUpdate, stats: &mut RDRenderStats) -> Result<(), RDGfxError> {
+        self.gfx.upload_textures(update, stats);
+        Ok(())
+    }
+
+    fn draw(&mut self, views: &[RDView]) -> Result<(), RDGfxError> {
+        let Some(mut frame) = self.frame.take() else { panic!("draw outside of a frame"); };
+        self.gfx.draw(&mut frame.encoder, &frame.view, views)?;
+
+        //draw the same views a second time into a texture that can be copied
+        if let WgpuTarget::Window(window_surface) = &self.target {
+            if self.capture && !window_surface.config.usage.contains(wgpu::TextureUsages::COPY_SRC) {
+                let mirror = self.create_target("Capture Mirror", self.gfx.format);
+                self.gfx.draw(&mut frame.encoder, &mirror.create_view(&wgpu::TextureViewDescriptor::default()), views)?;
+                frame.mirror = Some(mirror);
+            }
+        }
+
+        self.frame = Some(frame);
+
+        Ok(())
+    }
+
+    fn end_frame(&mut self) -> Option<RDImage> {
+        let frame = self.frame.take()?;
+
+        if let WgpuTarget::Offscreen(Some(texture)) = &self.target {
+            return Some(self.read_back(frame.encoder, texture));
+        }
+
+        let WgpuFrame { encoder, surface_texture, mirror, .. } = frame;
+        let capture = std::mem::take(&mut self.capture);
+
+        let source = mirror.as_ref()
+            .or(surface_texture.as_ref().map(|surface_texture| &surface_texture.texture))
+            .filter(|texture| capture && pixel_order(texture.format()).is_some());
+
+        let image = if let Some(texture) = source {
+            Some(self.read_back(encoder, texture))
+        } else {
+            // submit will accept anything that implements IntoIter
+            self.gfx.queue.submit(std::iter::once(encoder.finish()));
+            None
+        };
+
+        if let Some(surface_texture) = surface_texture {
+            surface_texture.present();
+        }
+
+        image
+    }
+}
+
+impl RDWindowBackend for RDWgpuBackend {
+    fn create(window: Arc<Window>, options: &RDOptions) -> Self {
+        pollster::block_on(RDWgpuBackend::for_window(window, options.multisample))
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        RDWgpuBackend::resize(self, width, height);
+    }
+
+    fn recover(&mut self, error: RDGfxError) -> Result<(), RDGfxError> {
+        match error {
+            // Reconfigure the surface if lost
+            RDGfxError::Surface(wgpu::SurfaceError::Lost) => {
+                if let WgpuTarget::Window(window_surface) = &self.target {
+                    window_surface.surface.configure(&self.gfx.device, &window_surface.config);
+                }
+                Ok(())
+            }
+            // Outdated and Timeout should be resolved by the next frame
+            RDGfxError::Surface(wgpu::SurfaceError::Outdated | wgpu::SurfaceError::Timeout) => Ok(()),
+            // The system is out of memory or the scene outgrew the device, we should probably quit
+            error => Err(error),
+        }
+    }
+
+    fn capture_next_frame(&mut self) {
+        self.capture = true;
+    }
+}
@@ -0,0 +1,219 @@
+use std::fmt;
+
+use crate::{backend::render_frame, gfx::{GfxState, RDGfxError, RDWgpuBackend, OFFSCREEN_FORMAT}, image::RDImage, scene::RDScene, RDOptions, RDRenderStats};
+#[cfg(feature = "software")]
+use crate::software::RDSoftwareBackend;
+
+#[derive(Debug)]
+pub enum RDHeadlessError {
+    //not even a software adapter was found, with the software feature the cpu rasterizer takes over instead
+    NoAdapter,
+    RequestDevice(wgpu::RequestDeviceError),
+}
+
+impl fmt::Display for RDHeadlessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RDHeadlessError::NoAdapter => write!(f, "no graphics adapter available"),
+            RDHeadlessError::RequestDevice(error) => write!(f, "failed to create a device: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for RDHeadlessError {}
+
+enum HeadlessBackend {
+    Wgpu(Box<RDWgpuBackend>),
+    //stands in when wgpu has no adapter at all
+    #[cfg(feature = "software")]
+    Software(RDSoftwareBackend),
+}
+
+//renders the scene into offscreen textures, no window or event loop involved
+pub struct HeadlessRenderer {
+    pub scene: RDScene,
+    stats: RDRenderStats,
+    adapter_info: wgpu::AdapterInfo,
+    backend: HeadlessBackend,
+}
+
+impl HeadlessRenderer {
+    pub fn new() -> Result<Self, RDHeadlessError> {
+        pollster::block_on(HeadlessRenderer::async_with_options(RDOptions::default()))
+    }
+
+    pub fn with_options(options: RDOptions) -> Result<Self, RDHeadlessError> {
+        pollster::block_on(HeadlessRenderer::async_with_options(options))
+    }
+
+    //tries a hardware adapter first unless told otherwise, then the software one
+    //with the software feature a machine without any adapter gets the cpu rasterizer
+    pub async fn async_with_options(options: RDOptions) -> Result<Self, RDHeadlessError> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+
+        let mut adapter = None;
+
+        for force_fallback_adapter in [options.force_fallback_adapter, true] {
+            adapter = instance.request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: None,
+                force_fallback_adapter,
+            }).await;
+
+            if adapter.is_some() {
+                break;
+            }
+        }
+
+        #[cfg(feature = "software")]
+        if adapter.is_none() {
+            return Ok(HeadlessRenderer::software(options));
+        }
+
+        let adapter = adapter.ok_or(RDHeadlessError::NoAdapter)?;
+        let (device, queue) = GfxState::request_device(&adapter).await.map_err(RDHeadlessError::RequestDevice)?;
+
+        Ok(Self {
+            scene: RDScene::new(10),
+            stats: RDRenderStats::default(),
+            adapter_info: adapter.get_info(),
+            backend: HeadlessBackend::Wgpu(Box::new(RDWgpuBackend::offscreen(GfxState::new(&adapter, device, queue, OFFSCREEN_FORMAT, 1, 1, options.multisample)))),
+        })
+    }
+
+    #[cfg(feature = "software")]
+    fn software(options: RDOptions) -> Self {
+        Self {
+            scene: RDScene::new(10),
+            stats: RDRenderStats::default(),
+            adapter_info: wgpu::AdapterInfo {
+                name: "raindeer software".to_string(),
+                vendor: 0,
+                device: 0,
+                device_type: wgpu::DeviceType::Cpu,
+                driver: String::new(),
+                driver_info: String::new(),
+                backend: wgpu::Backend::Empty,
+            },
+            backend: HeadlessBackend::Software(RDSoftwareBackend::new(options)),
+        }
+    }
+
+    pub fn stats(&self) -> RDRenderStats {
+        self.stats
+    }
+
+    //which adapter ended up being used, a software one and the cpu rasterizer report DeviceType::Cpu
+    pub fn adapter_info(&self) -> &wgpu::AdapterInfo {
+        &self.adapter_info
+    }
+
+    //fails when the scene needs bigger buffers than the device has, there is no surface to lose
+    pub fn render_to_image(&mut self, width: u32, height: u32) -> Result<RDImage, RDGfxError> {
+        //wgpu rejects empty textures
+        if width == 0 || height == 0 {
+            return Ok(RDImage::new(width, height));
+        }
+
+        let image = match &mut self.backend {
+            HeadlessBackend::Wgpu(backend) => {
+                backend.resize(width, height);
+                render_frame(&mut self.scene, backend.as_mut(), &mut self.stats)?
+            }
+            #[cfg(feature = "software")]
+            HeadlessBackend::Software(backend) => {
+                backend.resize(width, height);
+                let Ok(image) = render_frame(&mut self.scene, backend, &mut self.stats);
+                image
+            }
+        };
+
+        Ok(image.expect("offscreen frames are always read back"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Vector2;
+
+    use crate::{color::RDColor, font::tests::truetype_font, math::RDRect, path_builder::{RDPath, RDStroke}, text::RDText, RDOptions};
+
+    use super::HeadlessRenderer;
+    #[cfg(feature = "software")]
+    use super::HeadlessBackend;
+
+    #[test]
+    fn test_render_to_image() {
+        let renderer = HeadlessRenderer::with_options(RDOptions {
+            force_fallback_adapter: true,
+            ..Default::default()
+        });
+
+        //the software adapter is forced, a machine without one fails here instead of passing unchecked
+        let mut renderer = renderer.unwrap_or_else(|error| panic!("{error}"));
+
+        let mut square = RDPath::new()
+            .to(-1.0, -1.0)
+            .line(1.0, -1.0)
+            .line(1.0, 1.0)
+            .line(-1.0, 1.0)
+            .close()
+            .stroke(RDStroke { weight: 0.0, color: RDColor::BLACK })
+            .fill(RDColor::RED)
+            .to_node();
+        square.transform.position = Vector2::new(5.0, 0.0);
+        renderer.scene.add_root(square);
+
+        //ppu 10 over 100 pixels shows 20 units, the square covers pixels 70..80 by 45..55
+        let image = renderer.render_to_image(100, 100).unwrap();
+
+        assert_eq!((image.width, image.height, image.pixels.len()), (100, 100, 40000));
+        assert_eq!(image.pixel(75, 50), RDColor::RED);
+        assert_ne!(image.pixel(50, 50), RDColor::RED);
+        assert_eq!(image.pixel(0, 0).a, 255);
+
+        //odd widths exercise the row padding
+        let image = renderer.render_to_image(33, 20).unwrap();
+        assert_eq!(image.pixels.len(), 33 * 20 * 4);
+        assert_eq!(image.pixel(0, 0), image.pixel(32, 19));
+
+        //distance field text, the ring of the square glyph covers pixels 50..60 and its hole 60..70
+        let font = truetype_font();
+        let text = RDText::new(&font, "o").size(10.0).fill(RDColor::RED).to_sdf_node(renderer.scene.glyph_atlas_mut());
+        renderer.scene.add_root(text);
+
+        let image = renderer.render_to_image(100, 100).unwrap();
+        assert_eq!(image.pixel(55, 35), RDColor::RED);
+        assert_eq!(image.pixel(65, 35), image.pixel(0, 0));
+
+        //a camera sliding in from the left, half of its viewport is off the surface
+        //the square now sits 50 pixels further left and nothing is drawn right of the viewport
+        renderer.scene.camera_mut().viewport = RDRect::new(Vector2::new(-0.5, 0.0), Vector2::new(0.5, 1.0));
+        let image = renderer.render_to_image(100, 100).unwrap();
+        assert_eq!(image.pixel(25, 50), RDColor::RED);
+        assert_eq!(image.pixel(75, 50), image.pixel(99, 99));
+    }
+
+    #[cfg(feature = "software")]
+    #[test]
+    fn test_software_fallback() {
+        //what async_with_options hands out when no adapter turns up at all
+        let mut renderer = HeadlessRenderer::software(RDOptions::default());
+        assert!(matches!(renderer.backend, HeadlessBackend::Software(_)));
+        assert_eq!(renderer.adapter_info().device_type, wgpu::DeviceType::Cpu);
+
+        renderer.scene.add_root(RDPath::new()
+            .to(-1.0, -1.0)
+            .line(1.0, -1.0)
+            .line(1.0, 1.0)
+            .line(-1.0, 1.0)
+            .close()
+            .stroke(RDStroke { weight: 0.0, color: RDColor::BLACK })
+            .fill(RDColor::RED)
+            .to_node());
+
+        let image = renderer.render_to_image(100, 100).unwrap();
+        assert_eq!(image.pixel(50, 50), RDColor::RED);
+        assert_ne!(image.pixel(0, 0), RDColor::RED);
+    }
+}
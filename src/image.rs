@@ -0,0 +1,175 @@
+use std::{io, path::Path};
+
+use crate::color::RDColor;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+//the most a stored deflate block can hold
+const STORED_BLOCK_SIZE: usize = 65535;
+
+//8 bit rgba, rows top to bottom, colors are not premultiplied
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RDImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl RDImage {
+    //transparent black
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+        }
+    }
+
+    fn offset(&self, x: u32, y: u32) -> usize {
+        debug_assert!(x < self.width && y < self.height);
+        (y as usize * self.width as usize + x as usize) * 4
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> RDColor {
+        let offset = self.offset(x, y);
+        let [r, g, b, a] = self.pixels[offset..offset + 4] else { unreachable!() };
+
+        RDColor::new(r, g, b, a)
+    }
+
+    pub fn set_pixel(&mut self, x: u32, y: u32, color: &RDColor) {
+        let offset = self.offset(x, y);
+        self.pixels[offset..offset + 4].copy_from_slice(&[color.r, color.g, color.b, color.a]);
+    }
+
+    //uncompressed png, stored deflate blocks keep it simple at the cost of size
+    pub fn to_png(&self) -> Vec<u8> {
+        let row_size = self.width as usize * 4;
+
+        //every row starts with filter type 0, none
+        let mut raw = Vec::with_capacity((row_size + 1) * self.height as usize);
+        for row in self.pixels.chunks_exact(row_size.max(1)).take(self.height as usize) {
+            raw.push(0);
+            raw.extend_from_slice(row);
+        }
+
+        //zlib header, no compression and no preset dictionary
+        let mut data = vec![0x78, 0x01];
+        let mut blocks = raw.chunks(STORED_BLOCK_SIZE).peekable();
+        if blocks.peek().is_none() {
+            data.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+        }
+        while let Some(block) = blocks.next() {
+            let len = block.len() as u16;
+            data.push(blocks.peek().is_none() as u8);
+            data.extend_from_slice(&len.to_le_bytes());
+            data.extend_from_slice(&(!len).to_le_bytes());
+            data.extend_from_slice(block);
+        }
+        data.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+        let mut header = vec![];
+        header.extend_from_slice(&self.width.to_be_bytes());
+        header.extend_from_slice(&self.height.to_be_bytes());
+        //8 bits per channel, rgba, then the only compression, filter and interlace methods
+        header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+        let mut png = PNG_SIGNATURE.to_vec();
+        write_chunk(&mut png, b"IHDR", &header);
+        write_chunk(&mut png, b"IDAT", &data);
+        write_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        std::fs::write(path, self.to_png())
+    }
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+
+    //the crc covers the chunk type but not the length
+    let crc = !kind.iter().chain(data).fold(!0, |crc, byte| crc32_byte(crc, *byte));
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32_byte(crc: u32, byte: u8) -> u32 {
+    (0..8).fold(crc ^ byte as u32, |crc, _| if crc & 1 == 1 { 0xedb88320 ^ (crc >> 1) } else { crc >> 1 })
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), byte| {
+        let a = (a + *byte as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+
+    b << 16 | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //walks the chunks and undoes the stored blocks, checking every crc on the way
+    fn decode(png: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        assert_eq!(png[..8], PNG_SIGNATURE);
+
+        let (mut header, mut data) = (vec![], vec![]);
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let (kind, body) = (&rest[4..8], &rest[8..8 + len]);
+
+            let crc = !rest[4..8 + len].iter().fold(!0, |crc, byte| crc32_byte(crc, *byte));
+            assert_eq!(rest[8 + len..12 + len], crc.to_be_bytes());
+
+            match kind {
+                b"IHDR" => header = body.to_vec(),
+                b"IDAT" => data.extend_from_slice(body),
+                _ => (),
+            }
+            rest = &rest[12 + len..];
+        }
+
+        let mut raw = vec![];
+        let mut blocks = &data[2..data.len() - 4];
+        loop {
+            let len = u16::from_le_bytes([blocks[1], blocks[2]]) as usize;
+            assert_eq!(!(len as u16), u16::from_le_bytes([blocks[3], blocks[4]]));
+            raw.extend_from_slice(&blocks[5..5 + len]);
+
+            if blocks[0] == 1 {
+                break;
+            }
+            blocks = &blocks[5 + len..];
+        }
+        assert_eq!(data[data.len() - 4..], adler32(&raw).to_be_bytes());
+
+        (header, raw)
+    }
+
+    #[test]
+    fn test_png() {
+        //known values for the checksums
+        assert_eq!(!b"IEND".iter().fold(!0, |crc, byte| crc32_byte(crc, *byte)), 0xae426082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+
+        //big enough to need more than one stored block
+        let mut image = RDImage::new(200, 100);
+        image.set_pixel(3, 2, &RDColor::RED);
+        image.set_pixel(199, 99, &RDColor::new(1, 2, 3, 4));
+
+        let (header, raw) = decode(&image.to_png());
+        assert_eq!(header, [0, 0, 0, 200, 0, 0, 0, 100, 8, 6, 0, 0, 0]);
+
+        let rows: Vec<_> = raw.chunks_exact(801).collect();
+        assert_eq!(rows.len(), 100);
+        assert!(rows.iter().all(|row| row[0] == 0));
+        assert_eq!(rows.iter().flat_map(|row| &row[1..]).copied().collect::<Vec<_>>(), image.pixels);
+
+        let (_, raw) = decode(&RDImage::new(0, 0).to_png());
+        assert!(raw.is_empty());
+    }
+}
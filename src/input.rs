@@ -0,0 +1,203 @@
+use cgmath::Vector2;
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+
+use crate::{interaction::RDNodeEvent, scene::RDScene};
+
+pub use winit::keyboard::{Key, KeyCode, NamedKey, PhysicalKey};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RDMouseButton {
+    Left,
+    Right,
+    Middle,
+    Back,
+    Forward,
+    Other(u16),
+}
+
+impl From<MouseButton> for RDMouseButton {
+    fn from(button: MouseButton) -> Self {
+        match button {
+            MouseButton::Left => RDMouseButton::Left,
+            MouseButton::Right => RDMouseButton::Right,
+            MouseButton::Middle => RDMouseButton::Middle,
+            MouseButton::Back => RDMouseButton::Back,
+            MouseButton::Forward => RDMouseButton::Forward,
+            MouseButton::Other(other) => RDMouseButton::Other(other),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RDModifiers {
+    pub shift: bool,
+    pub control: bool,
+    pub alt: bool,
+    //command on macos, the windows key elsewhere
+    pub logo: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RDPointer {
+    //physical pixels with the origin at the top left of the window
+    pub screen: Vector2<f32>,
+    //None when the pointer is outside every camera's viewport
+    pub world: Option<Vector2<f32>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RDWheelDelta {
+    //notches of a regular mouse wheel
+    Lines(Vector2<f32>),
+    //touchpads and precise wheels
+    Pixels(Vector2<f32>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RDEvent {
+    PointerMoved { pointer: RDPointer },
+    PointerPressed { pointer: RDPointer, button: RDMouseButton, modifiers: RDModifiers },
+    PointerReleased { pointer: RDPointer, button: RDMouseButton, modifiers: RDModifiers },
+    //the pointer left the window
+    PointerLeft,
+    Wheel { pointer: RDPointer, delta: RDWheelDelta, modifiers: RDModifiers },
+    Key { key: Key, code: PhysicalKey, pressed: bool, repeat: bool, modifiers: RDModifiers },
+    //what the key presses typed, already run through the keyboard layout
+    Text(String),
+    Focused(bool),
+    ScaleFactorChanged(f64),
+    Resized { width: u32, height: u32 },
+    //pointer events routed to the scene node under the pointer, queued right after the raw event
+    Node(RDNodeEvent),
+}
+
+//what winit doesn't repeat with every event
+#[derive(Debug, Default)]
+pub(crate) struct InputState {
+    cursor: Option<Vector2<f32>>,
+    modifiers: RDModifiers,
+}
+
+impl InputState {
+    fn pointer(&self, scene: &RDScene) -> Option<RDPointer> {
+        let screen = self.cursor?;
+
+        Some(RDPointer {
+            screen,
+            world: scene.screen_to_world(screen),
+        })
+    }
+
+    //appends whatever the window event means to the application, most events map to one or none
+    pub(crate) fn translate(&mut self, event: &WindowEvent, scene: &RDScene, events: &mut Vec<RDEvent>) {
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor = Some(Vector2::new(position.x as f32, position.y as f32));
+                events.extend(self.pointer(scene).map(|pointer| RDEvent::PointerMoved { pointer }));
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.cursor = None;
+                events.push(RDEvent::PointerLeft);
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                let Some(pointer) = self.pointer(scene) else { return };
+                let (button, modifiers) = ((*button).into(), self.modifiers);
+
+                events.push(match state {
+                    ElementState::Pressed => RDEvent::PointerPressed { pointer, button, modifiers },
+                    ElementState::Released => RDEvent::PointerReleased { pointer, button, modifiers },
+                });
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let Some(pointer) = self.pointer(scene) else { return };
+
+                let delta = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => RDWheelDelta::Lines(Vector2::new(*x, *y)),
+                    MouseScrollDelta::PixelDelta(position) => RDWheelDelta::Pixels(Vector2::new(position.x as f32, position.y as f32)),
+                };
+
+                events.push(RDEvent::Wheel { pointer, delta, modifiers: self.modifiers });
+            }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                let state = modifiers.state();
+
+                self.modifiers = RDModifiers {
+                    shift: state.shift_key(),
+                    control: state.control_key(),
+                    alt: state.alt_key(),
+                    logo: state.super_key(),
+                };
+            }
+            WindowEvent::KeyboardInput { event, .. } => {
+                let pressed = event.state == ElementState::Pressed;
+
+                events.push(RDEvent::Key {
+                    key: event.logical_key.clone(),
+                    code: event.physical_key,
+                    pressed,
+                    repeat: event.repeat,
+                    modifiers: self.modifiers,
+                });
+
+                //control characters like backspace come through as text too, they are left to the key event
+                if let Some(text) = event.text.as_ref().filter(|text| pressed && !text.chars().all(char::is_control)) {
+                    events.push(RDEvent::Text(text.to_string()));
+                }
+            }
+            WindowEvent::Ime(winit::event::Ime::Commit(text)) => events.push(RDEvent::Text(text.clone())),
+            WindowEvent::Focused(focused) => {
+                //keys released while unfocused never arrive, don't leave modifiers stuck
+                if !focused {
+                    self.modifiers = RDModifiers::default();
+                }
+
+                events.push(RDEvent::Focused(*focused));
+            }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => events.push(RDEvent::ScaleFactorChanged(*scale_factor)),
+            WindowEvent::Resized(size) => events.push(RDEvent::Resized { width: size.width, height: size.height }),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Vector2;
+    use winit::{dpi::PhysicalPosition, event::{DeviceId, ElementState, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent}};
+
+    use crate::scene::RDScene;
+
+    use super::*;
+
+    #[test]
+    fn test_translate() {
+        let device_id = DeviceId::dummy();
+
+        let mut scene = RDScene::new(10);
+        scene.output_gfx(800.0, 800.0);
+
+        let mut state = InputState::default();
+        let mut events = vec![];
+
+        //no position yet, a click can't be placed anywhere
+        state.translate(&WindowEvent::MouseInput { device_id, state: ElementState::Pressed, button: MouseButton::Left }, &scene, &mut events);
+        assert!(events.is_empty());
+
+        state.translate(&WindowEvent::CursorMoved { device_id, position: PhysicalPosition::new(400.0, 400.0) }, &scene, &mut events);
+        state.translate(&WindowEvent::MouseInput { device_id, state: ElementState::Released, button: MouseButton::Right }, &scene, &mut events);
+        state.translate(&WindowEvent::MouseWheel { device_id, delta: MouseScrollDelta::LineDelta(0.0, -1.0), phase: TouchPhase::Moved }, &scene, &mut events);
+        state.translate(&WindowEvent::CursorLeft { device_id }, &scene, &mut events);
+
+        let pointer = RDPointer {
+            screen: Vector2::new(400.0, 400.0),
+            world: Some(Vector2::new(0.0, 0.0)),
+        };
+
+        assert_eq!(events, vec![
+            RDEvent::PointerMoved { pointer },
+            RDEvent::PointerReleased { pointer, button: RDMouseButton::Right, modifiers: RDModifiers::default() },
+            RDEvent::Wheel { pointer, delta: RDWheelDelta::Lines(Vector2::new(0.0, -1.0)), modifiers: RDModifiers::default() },
+            RDEvent::PointerLeft,
+        ]);
+    }
+}
@@ -0,0 +1,280 @@
+use cgmath::{InnerSpace, Vector2};
+
+use crate::{input::{RDEvent, RDMouseButton, RDPointer}, scene::{RDNodeId, RDScene}};
+
+//how far in pixels the pointer has to travel with a button held before it counts as a drag instead of a click
+const DRAG_THRESHOLD: f32 = 4.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RDNodeEventKind {
+    //enter and leave don't bubble, every node along the way gets its own
+    PointerEnter,
+    PointerLeave,
+    PointerDown(RDMouseButton),
+    PointerUp(RDMouseButton),
+    //pressed and released without dragging, on the nearest node both ends have in common
+    Click(RDMouseButton),
+    DragStart(RDMouseButton),
+    //world space movement since the last drag event
+    Drag { delta: Vector2<f32> },
+    DragEnd,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RDNodeEvent {
+    pub kind: RDNodeEventKind,
+    //the node under the pointer, or the one holding the capture during a press
+    pub target: RDNodeId,
+    //target first then each ancestor up to the top of its tree, the route the event bubbles along
+    pub path: Vec<RDNodeId>,
+    pub pointer: RDPointer,
+}
+
+impl RDNodeEvent {
+    //true when the event happened on the node or bubbled up to it from a descendant
+    pub fn reaches(&self, id: RDNodeId) -> bool {
+        self.path.contains(&id)
+    }
+}
+
+#[derive(Debug)]
+struct Press {
+    button: RDMouseButton,
+    //the pressed node holds the capture until the button is released
+    path: Vec<RDNodeId>,
+    start: Vector2<f32>,
+    last_world: Option<Vector2<f32>>,
+    dragging: bool,
+}
+
+//turns raw pointer events into enter, leave, click and drag events on scene nodes
+#[derive(Debug, Default)]
+pub(crate) struct PointerRouter {
+    //the hovered node and its ancestors, deepest first
+    hovered: Vec<RDNodeId>,
+    press: Option<Press>,
+    //where the pointer was last seen, for the events sent once it is gone
+    last_pointer: Option<RDPointer>,
+}
+
+//the scene's own root sits above every tree and never takes part
+fn ancestry(scene: &RDScene, id: RDNodeId) -> Vec<RDNodeId> {
+    std::iter::successors(Some(id), |id| scene.parent(*id)).take_while(|id| *id != scene.root()).collect()
+}
+
+impl PointerRouter {
+    pub(crate) fn route(&mut self, event: &RDEvent, scene: &RDScene, events: &mut Vec<RDEvent>) {
+        //nodes removed since the last event can't receive anything
+        self.hovered.retain(|id| scene.contains(*id));
+        if let Some(press) = &mut self.press {
+            press.path.retain(|id| scene.contains(*id));
+        }
+
+        let mut emit = |kind: RDNodeEventKind, path: &[RDNodeId], pointer: RDPointer| {
+            if let Some(target) = path.first() {
+                events.push(RDEvent::Node(RDNodeEvent { kind, target: *target, path: path.to_vec(), pointer }));
+            }
+        };
+
+        match event {
+            RDEvent::PointerMoved { pointer } => {
+                self.last_pointer = Some(*pointer);
+
+                let Some(press) = &mut self.press else {
+                    self.hover(scene, *pointer, &mut emit);
+                    return;
+                };
+
+                if !press.dragging && (pointer.screen - press.start).magnitude() >= DRAG_THRESHOLD {
+                    press.dragging = true;
+                    emit(RDNodeEventKind::DragStart(press.button), &press.path, *pointer);
+                }
+
+                if press.dragging {
+                    if let (Some(last), Some(world)) = (press.last_world, pointer.world) {
+                        emit(RDNodeEventKind::Drag { delta: world - last }, &press.path, *pointer);
+                    }
+                }
+
+                press.last_world = pointer.world.or(press.last_world);
+            }
+            RDEvent::PointerPressed { pointer, button, .. } => {
+                self.hover(scene, *pointer, &mut emit);
+
+                //other buttons pressed during a capture go to the captured node
+                let path = self.press.as_ref().map_or(&self.hovered, |press| &press.path);
+                emit(RDNodeEventKind::PointerDown(*button), path, *pointer);
+
+                if self.press.is_none() && !self.hovered.is_empty() {
+                    self.press = Some(Press {
+                        button: *button,
+                        path: self.hovered.clone(),
+                        start: pointer.screen,
+                        last_world: pointer.world,
+                        dragging: false,
+                    });
+                }
+            }
+            RDEvent::PointerReleased { pointer, button, .. } => {
+                let Some(press) = self.press.take_if(|press| press.button == *button) else {
+                    let path = self.press.as_ref().map_or(&self.hovered, |press| &press.path);
+                    emit(RDNodeEventKind::PointerUp(*button), path, *pointer);
+                    return;
+                };
+
+                emit(RDNodeEventKind::PointerUp(*button), &press.path, *pointer);
+
+                if press.dragging {
+                    emit(RDNodeEventKind::DragEnd, &press.path, *pointer);
+                } else {
+                    let released = pointer.world.and_then(|world| scene.pick_world(world)).map_or(vec![], |id| ancestry(scene, id));
+                    let common: Vec<_> = press.path.iter().copied().filter(|id| released.contains(id)).collect();
+
+                    emit(RDNodeEventKind::Click(*button), &common, *pointer);
+                }
+
+                //hover was frozen during the capture
+                self.hover(scene, *pointer, &mut emit);
+            }
+            RDEvent::PointerLeft | RDEvent::Focused(false) => {
+                let Some(pointer) = self.last_pointer.take() else { return };
+
+                if let Some(press) = self.press.take().filter(|press| press.dragging) {
+                    emit(RDNodeEventKind::DragEnd, &press.path, pointer);
+                }
+
+                for (i, _) in self.hovered.iter().enumerate() {
+                    emit(RDNodeEventKind::PointerLeave, &self.hovered[i..], pointer);
+                }
+                self.hovered.clear();
+            }
+            _ => (),
+        }
+    }
+
+    fn hover(&mut self, scene: &RDScene, pointer: RDPointer, emit: &mut impl FnMut(RDNodeEventKind, &[RDNodeId], RDPointer)) {
+        let hovered = pointer.world.and_then(|world| scene.pick_world(world)).map_or(vec![], |id| ancestry(scene, id));
+
+        //deepest first for leaving, outermost first for entering, like the dom
+        for i in 0..self.hovered.len() {
+            if !hovered.contains(&self.hovered[i]) {
+                emit(RDNodeEventKind::PointerLeave, &self.hovered[i..], pointer);
+            }
+        }
+
+        for i in (0..hovered.len()).rev() {
+            if !self.hovered.contains(&hovered[i]) {
+                emit(RDNodeEventKind::PointerEnter, &hovered[i..], pointer);
+            }
+        }
+
+        self.hovered = hovered;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Vector2;
+
+    use crate::{color::RDColor, input::{RDEvent, RDModifiers, RDMouseButton, RDPointer}, path_builder::{RDPath, RDStroke}, scene::{RDNode, RDScene}};
+
+    use super::*;
+
+    fn square(x: f32) -> RDNode {
+        let mut node = RDPath::new()
+            .to(0.0, 0.0)
+            .line(1.0, 0.0)
+            .line(1.0, 1.0)
+            .line(0.0, 1.0)
+            .close()
+            .stroke(RDStroke { weight: 0.0, color: RDColor::BLACK })
+            .fill(RDColor::RED)
+            .to_node();
+
+        node.transform.position = Vector2::new(x, 0.0);
+        node
+    }
+
+    fn kinds(events: &[RDEvent]) -> Vec<(RDNodeEventKind, RDNodeId)> {
+        events.iter().filter_map(|event| match event {
+            RDEvent::Node(event) => Some((event.kind, event.target)),
+            _ => None,
+        }).collect()
+    }
+
+    #[test]
+    fn test_pointer_routing() {
+        let mut scene = RDScene::new(10);
+        let group = scene.add_root(RDNode::new());
+        let a = scene.add(group, square(0.0));
+        let b = scene.add(group, square(5.0));
+        let (fill_a, fill_b) = (scene.children(a)[0], scene.children(b)[0]);
+
+        let mut router = PointerRouter::default();
+        let mut events = vec![];
+
+        let pointer = |x: f32| RDPointer { screen: Vector2::new(x * 10.0, 0.0), world: Some(Vector2::new(x, 0.5)) };
+        let moved = |x: f32| RDEvent::PointerMoved { pointer: pointer(x) };
+        let pressed = |x: f32| RDEvent::PointerPressed { pointer: pointer(x), button: RDMouseButton::Left, modifiers: RDModifiers::default() };
+        let released = |x: f32| RDEvent::PointerReleased { pointer: pointer(x), button: RDMouseButton::Left, modifiers: RDModifiers::default() };
+
+        router.route(&moved(0.5), &scene, &mut events);
+        assert_eq!(kinds(&events), vec![
+            (RDNodeEventKind::PointerEnter, group),
+            (RDNodeEventKind::PointerEnter, a),
+            (RDNodeEventKind::PointerEnter, fill_a),
+        ]);
+
+        //moving between siblings only leaves and enters below the shared group
+        events.clear();
+        router.route(&moved(5.5), &scene, &mut events);
+        assert_eq!(kinds(&events), vec![
+            (RDNodeEventKind::PointerLeave, fill_a),
+            (RDNodeEventKind::PointerLeave, a),
+            (RDNodeEventKind::PointerEnter, b),
+            (RDNodeEventKind::PointerEnter, fill_b),
+        ]);
+
+        events.clear();
+        router.route(&pressed(5.5), &scene, &mut events);
+        router.route(&released(5.6), &scene, &mut events);
+        assert_eq!(kinds(&events), vec![
+            (RDNodeEventKind::PointerDown(RDMouseButton::Left), fill_b),
+            (RDNodeEventKind::PointerUp(RDMouseButton::Left), fill_b),
+            (RDNodeEventKind::Click(RDMouseButton::Left), fill_b),
+        ]);
+
+        let RDEvent::Node(click) = &events[2] else { panic!() };
+        assert_eq!(click.path, vec![fill_b, b, group]);
+        assert!(click.reaches(b) && !click.reaches(a));
+
+        //pressing on one square and releasing on the other clicks their common ancestor
+        events.clear();
+        router.route(&pressed(0.5), &scene, &mut events);
+        router.route(&released(0.5 + 0.3), &scene, &mut events);
+        router.route(&pressed(0.5), &scene, &mut events);
+        router.route(&released(5.5), &scene, &mut events);
+        let clicks: Vec<_> = kinds(&events).into_iter().filter(|(kind, _)| matches!(kind, RDNodeEventKind::Click(_))).collect();
+        assert_eq!(clicks, vec![(RDNodeEventKind::Click(RDMouseButton::Left), fill_a), (RDNodeEventKind::Click(RDMouseButton::Left), group)]);
+
+        //dragging keeps the capture on the pressed node even when the pointer passes over another one
+        router.route(&moved(0.5), &scene, &mut events);
+        events.clear();
+        router.route(&pressed(0.5), &scene, &mut events);
+        router.route(&moved(2.5), &scene, &mut events);
+        router.route(&moved(5.5), &scene, &mut events);
+        router.route(&released(5.5), &scene, &mut events);
+        assert_eq!(kinds(&events), vec![
+            (RDNodeEventKind::PointerDown(RDMouseButton::Left), fill_a),
+            (RDNodeEventKind::DragStart(RDMouseButton::Left), fill_a),
+            (RDNodeEventKind::Drag { delta: Vector2::new(2.0, 0.0) }, fill_a),
+            (RDNodeEventKind::Drag { delta: Vector2::new(3.0, 0.0) }, fill_a),
+            (RDNodeEventKind::PointerUp(RDMouseButton::Left), fill_a),
+            (RDNodeEventKind::DragEnd, fill_a),
+            (RDNodeEventKind::PointerLeave, fill_a),
+            (RDNodeEventKind::PointerLeave, a),
+            (RDNodeEventKind::PointerEnter, b),
+            (RDNodeEventKind::PointerEnter, fill_b),
+        ]);
+    }
+}
@@ -0,0 +1,469 @@
+use std::{iter, ops::Range};
+
+use cgmath::Vector2;
+
+use crate::{font::{RDFont, RDGlyph}, math::RDRect};
+
+//room for advances that add up to a hair over the width they exactly fill, in ems
+const FIT_TOLERANCE: f32 = 1e-4;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RDWrap {
+    //lines only end at newlines, the max width just decides where an ellipsis goes
+    None,
+    //between words, a word longer than a whole line is broken between its characters
+    #[default]
+    Word,
+    //wherever the line is full
+    Character,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RDAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+    //spaces are stretched until the line fills the width, the last line of a paragraph stays left aligned
+    Justify,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RDLayoutOptions {
+    //without one lines are aligned to the widest of them
+    pub max_width: Option<f32>,
+    //lines that don't fit in full are left out
+    pub max_height: Option<f32>,
+    pub wrap: RDWrap,
+    pub align: RDAlign,
+    //a multiple of the font's own line height
+    pub line_height: f32,
+    //ends the last line shown with an ellipsis whenever text was left out
+    pub ellipsis: bool,
+}
+
+impl Default for RDLayoutOptions {
+    fn default() -> Self {
+        Self {
+            max_width: None,
+            max_height: None,
+            wrap: RDWrap::default(),
+            align: RDAlign::default(),
+            line_height: 1.0,
+            ellipsis: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RDLine {
+    //bytes of the text shown on the line, the newline it ended at is left out
+    pub range: Range<usize>,
+    //placed in layout space, ellipsis glyphs come last and point at the end of the range
+    pub glyphs: Vec<RDGlyph>,
+    pub baseline: f32,
+    //the line's box, line height apart
+    pub top: f32,
+    pub bottom: f32,
+    //where the line starts once aligned and how far its ink reaches, spaces it broke after don't count
+    pub x: f32,
+    pub width: f32,
+}
+
+impl RDLine {
+    //x of the caret before each character of the line and after its last one
+    fn stops(&self) -> impl Iterator<Item = (usize, f32)> + '_ {
+        let end = self.glyphs.iter().find(|glyph| glyph.cluster >= self.range.end).map(|glyph| glyph.position.x)
+            .or(self.glyphs.last().map(|glyph| glyph.position.x + glyph.advance))
+            .unwrap_or(self.x);
+
+        self.glyphs.iter()
+            .filter(|glyph| glyph.cluster < self.range.end)
+            .map(|glyph| (glyph.cluster, glyph.position.x))
+            .chain(iter::once((self.range.end, end)))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RDCaret {
+    //byte offset in the text the caret sits before
+    pub index: usize,
+    pub line: usize,
+    //on the line's baseline, the line's top and bottom give its height
+    pub position: Vector2<f32>,
+}
+
+//text broken into lines and aligned, the first baseline sits at y 0 and lines go down from there
+#[derive(Clone, Debug)]
+pub struct RDTextLayout {
+    pub lines: Vec<RDLine>,
+    //the box the lines were aligned in, from the first line's top to the last one's bottom
+    pub bounds: RDRect,
+    //some of the text didn't fit and was left out
+    pub truncated: bool,
+}
+
+//spaces a line may break after, no-break spaces excluded
+fn breaks_after(text: &str, glyph: &RDGlyph) -> bool {
+    text[glyph.cluster..].chars().next().is_some_and(|character| character.is_whitespace() && character != '\u{a0}')
+}
+
+//a line before it is aligned and moved onto its baseline
+struct PendingLine {
+    range: Range<usize>,
+    glyphs: Vec<RDGlyph>,
+    width: f32,
+    //ended by a newline or the end of the text rather than wrapping
+    paragraph_end: bool,
+    ellipsized: bool,
+}
+
+impl RDTextLayout {
+    pub fn new(font: &RDFont, text: &str, size: f32, options: &RDLayoutOptions) -> Self {
+        let mut pending = vec![];
+        let mut offset = 0;
+
+        for paragraph in text.split('\n') {
+            let content = paragraph.strip_suffix('\r').unwrap_or(paragraph);
+            break_paragraph(font, text, offset..offset + content.len(), size, options, &mut pending);
+            offset += paragraph.len() + 1;
+        }
+
+        let line_height = font.line_height(size) * options.line_height;
+        let mut truncated = false;
+
+        if let (RDWrap::None, Some(max_width)) = (options.wrap, options.max_width) {
+            for line in pending.iter_mut().filter(|line| line.width > max_width + FIT_TOLERANCE * size) {
+                truncated = true;
+
+                if options.ellipsis {
+                    ellipsize(font, text, line, size, max_width);
+                }
+            }
+        }
+
+        if let Some(max_height) = options.max_height {
+            let fitting = ((max_height + FIT_TOLERANCE * size) / line_height).floor().max(0.0) as usize;
+
+            if pending.len() > fitting {
+                pending.truncate(fitting);
+                truncated = true;
+
+                if let Some(last) = pending.last_mut().filter(|last| options.ellipsis && !last.ellipsized) {
+                    ellipsize(font, text, last, size, options.max_width.unwrap_or(f32::INFINITY));
+                }
+            }
+        }
+
+        let widest = pending.iter().map(|line| line.width).fold(0.0, f32::max);
+        let available = options.max_width.unwrap_or(widest);
+        //half of what a taller line adds goes above the ascent
+        let top = font.ascent(size) + (line_height - font.ascent(size) + font.descent(size)) * 0.5;
+
+        let lines: Vec<_> = pending.into_iter().enumerate().map(|(i, mut line)| {
+            let baseline = -(i as f32) * line_height;
+
+            let x = match options.align {
+                RDAlign::Left => 0.0,
+                RDAlign::Center => (available - line.width) * 0.5,
+                RDAlign::Right => available - line.width,
+                RDAlign::Justify => {
+                    if !line.paragraph_end && !line.ellipsized {
+                        justify(text, &mut line, available);
+                    }
+                    0.0
+                }
+            };
+
+            for glyph in line.glyphs.iter_mut() {
+                glyph.position += Vector2::new(x, baseline);
+            }
+
+            RDLine {
+                range: line.range,
+                glyphs: line.glyphs,
+                baseline,
+                top: top + baseline,
+                bottom: top + baseline - line_height,
+                x,
+                width: line.width,
+            }
+        }).collect();
+
+        let bottom = top - lines.len() as f32 * line_height;
+
+        Self {
+            lines,
+            bounds: RDRect::new(Vector2::new(0.0, bottom), Vector2::new(available, top)),
+            truncated,
+        }
+    }
+
+    //every glyph of every line, in the order they were laid out
+    pub fn glyphs(&self) -> impl Iterator<Item = &RDGlyph> {
+        self.lines.iter().flat_map(|line| line.glyphs.iter())
+    }
+
+    //where a caret before the byte at index goes, a break between two lines counts as the start of the second
+    //indices in text that was left out end up at the end of the last line shown
+    pub fn caret(&self, index: usize) -> Option<RDCaret> {
+        let line = self.lines.iter().rposition(|line| line.range.start <= index)?;
+        let (index, x) = self.lines[line].stops().take_while(|(stop, _)| *stop <= index).last()?;
+
+        Some(RDCaret { index, line, position: Vector2::new(x, self.lines[line].baseline) })
+    }
+
+    //the caret closest to a point in layout space, points above or below the text go to the first or last line
+    pub fn hit_test(&self, point: Vector2<f32>) -> Option<RDCaret> {
+        let line = self.lines.iter().position(|line| point.y > line.bottom).unwrap_or(self.lines.len().checked_sub(1)?);
+        let (index, x) = self.lines[line].stops().min_by(|(_, a), (_, b)| (a - point.x).abs().total_cmp(&(b - point.x).abs()))?;
+
+        Some(RDCaret { index, line, position: Vector2::new(x, self.lines[line].baseline) })
+    }
+}
+
+//splits one paragraph, the text between two newlines, into lines that fit the max width
+fn break_paragraph(font: &RDFont, text: &str, paragraph: Range<usize>, size: f32, options: &RDLayoutOptions, lines: &mut Vec<PendingLine>) {
+    let mut glyphs = font.shape(&text[paragraph.clone()], size);
+    for glyph in glyphs.iter_mut() {
+        glyph.cluster += paragraph.start;
+    }
+
+    let limit = options.max_width.filter(|_| options.wrap != RDWrap::None);
+    let mut start = 0;
+
+    loop {
+        let end = match limit {
+            Some(limit) => start + line_length(font, text, &glyphs[start..], size, limit, options.wrap),
+            None => glyphs.len(),
+        };
+
+        let range = glyphs.get(start).map_or(paragraph.start, |glyph| glyph.cluster)..glyphs.get(end).map_or(paragraph.end, |glyph| glyph.cluster);
+        let origin = glyphs.get(start).map_or(0.0, |glyph| glyph.position.x);
+
+        let mut line_glyphs: Vec<_> = glyphs[start..end].iter().map(|glyph| RDGlyph {
+            position: glyph.position - Vector2::new(origin, 0.0),
+            ..*glyph
+        }).collect();
+
+        //kerning with the first glyph of the next line doesn't apply anymore
+        if let Some(last) = line_glyphs.last_mut() {
+            last.advance = font.advance(last.id, size);
+        }
+
+        let width = ink_width(font, text, &line_glyphs, size);
+        lines.push(PendingLine { range, glyphs: line_glyphs, width, paragraph_end: end == glyphs.len(), ellipsized: false });
+
+        start = end;
+        if start >= glyphs.len() {
+            break;
+        }
+    }
+}
+
+//how many of the glyphs go on the line, at least one unless an empty paragraph has none
+fn line_length(font: &RDFont, text: &str, glyphs: &[RDGlyph], size: f32, limit: f32, wrap: RDWrap) -> usize {
+    let Some(first) = glyphs.first() else { return 0 };
+    let origin = first.position.x;
+    let mut word_start = None;
+
+    for (i, glyph) in glyphs.iter().enumerate() {
+        //spaces may hang past the end of the line
+        if breaks_after(text, glyph) {
+            word_start = Some(i + 1);
+            continue;
+        }
+
+        let right = glyph.position.x + font.advance(glyph.id, size) - origin;
+
+        if i > 0 && right > limit + FIT_TOLERANCE * size {
+            return match (wrap, word_start) {
+                (RDWrap::Word, Some(word_start)) => word_start,
+                _ => i,
+            };
+        }
+    }
+
+    glyphs.len()
+}
+
+//from the line's start to the far side of its last glyph that isn't a space
+fn ink_width(font: &RDFont, text: &str, glyphs: &[RDGlyph], size: f32) -> f32 {
+    glyphs.iter().rev()
+        .find(|glyph| !breaks_after(text, glyph))
+        .map_or(0.0, |glyph| glyph.position.x + font.advance(glyph.id, size))
+}
+
+//drops glyphs off the end of the line until an ellipsis fits behind them, three dots for fonts without one
+fn ellipsize(font: &RDFont, text: &str, line: &mut PendingLine, size: f32, max_width: f32) {
+    let ellipsis = match font.glyph_index('\u{2026}') {
+        0 => vec![font.glyph_index('.'); 3],
+        glyph => vec![glyph],
+    };
+    let ellipsis_width: f32 = ellipsis.iter().map(|glyph| font.advance(*glyph, size)).sum();
+
+    let mut cut = line.range.end;
+
+    while let Some(last) = line.glyphs.last() {
+        let end = last.position.x + font.advance(last.id, size);
+
+        if !breaks_after(text, last) && end + ellipsis_width <= max_width + FIT_TOLERANCE * size {
+            break;
+        }
+
+        cut = last.cluster;
+        line.glyphs.pop();
+    }
+
+    let mut x = 0.0;
+    if let Some(last) = line.glyphs.last_mut() {
+        last.advance = font.advance(last.id, size);
+        x = last.position.x + last.advance;
+    }
+
+    for id in ellipsis {
+        let advance = font.advance(id, size);
+        line.glyphs.push(RDGlyph { id, cluster: cut, position: Vector2::new(x, 0.0), advance });
+        x += advance;
+    }
+
+    line.range.end = cut;
+    line.width = x;
+    line.ellipsized = true;
+}
+
+//spreads the room left on the line over the spaces between its words
+fn justify(text: &str, line: &mut PendingLine, available: f32) {
+    let Some(last_ink) = line.glyphs.iter().rposition(|glyph| !breaks_after(text, glyph)) else { return };
+    let gaps = line.glyphs[..last_ink].iter().filter(|glyph| breaks_after(text, glyph)).count();
+
+    if gaps == 0 || line.width >= available {
+        return;
+    }
+
+    let extra = (available - line.width) / gaps as f32;
+    let mut shift = 0.0;
+
+    for (i, glyph) in line.glyphs.iter_mut().enumerate() {
+        glyph.position.x += shift;
+
+        if i < last_ink && breaks_after(text, glyph) {
+            glyph.advance += extra;
+            shift += extra;
+        }
+    }
+
+    line.width = available;
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Vector2;
+
+    use crate::{font::tests::truetype_font, math::RDRect};
+
+    use super::{RDAlign, RDCaret, RDLayoutOptions, RDTextLayout, RDWrap};
+
+    //'o' is 7 wide at size 10, spaces 5 and lines 11 apart with their tops 8.5 above the baseline
+    fn layout(text: &str, options: RDLayoutOptions) -> RDTextLayout {
+        RDTextLayout::new(&truetype_font(), text, 10.0, &options)
+    }
+
+    fn ranges(layout: &RDTextLayout) -> Vec<(usize, usize)> {
+        layout.lines.iter().map(|line| (line.range.start, line.range.end)).collect()
+    }
+
+    #[test]
+    fn test_wrapping() {
+        //words move down whole, the spaces they broke after stay behind and don't count towards the width
+        let words = layout("oo oo oo", RDLayoutOptions { max_width: Some(30.0), ..Default::default() });
+        assert_eq!(ranges(&words), [(0, 3), (3, 6), (6, 8)]);
+        assert!(words.lines.iter().all(|line| line.width == 14.0));
+        assert_eq!(words.bounds, RDRect::new(Vector2::new(0.0, -24.5), Vector2::new(30.0, 8.5)));
+        assert_eq!((words.lines[1].baseline, words.lines[1].top, words.lines[1].bottom), (-11.0, -2.5, -13.5));
+        assert_eq!(words.lines[1].glyphs[1].position, Vector2::new(7.0, -11.0));
+        assert!(!words.truncated);
+
+        //a word longer than the line falls back to breaking between characters
+        let long = layout("oooooo", RDLayoutOptions { max_width: Some(30.0), ..Default::default() });
+        let characters = layout("oo oooo", RDLayoutOptions { max_width: Some(30.0), wrap: RDWrap::Character, ..Default::default() });
+        assert_eq!(ranges(&long), [(0, 4), (4, 6)]);
+        assert_eq!(ranges(&characters), [(0, 4), (4, 7)]);
+
+        //newlines always break, an empty paragraph still gets its line
+        let paragraphs = layout("oo\r\n\noo", RDLayoutOptions::default());
+        assert_eq!(ranges(&paragraphs), [(0, 2), (4, 4), (5, 7)]);
+        assert_eq!(paragraphs.bounds.max.x, 14.0);
+
+        //the same holds when lines are broken to a width
+        let options = RDLayoutOptions { max_width: Some(30.0), ..Default::default() };
+        assert_eq!(ranges(&layout("", options)), [(0, 0)]);
+        assert_eq!(ranges(&layout("oo\n", options)), [(0, 2), (3, 3)]);
+        assert_eq!(ranges(&layout("oo\n\noo", options)), [(0, 2), (3, 3), (4, 6)]);
+    }
+
+    #[test]
+    fn test_alignment() {
+        let options = RDLayoutOptions { max_width: Some(40.0), ..Default::default() };
+
+        let center = layout("oo oo oo", RDLayoutOptions { align: RDAlign::Center, ..options });
+        let right = layout("oo oo oo", RDLayoutOptions { align: RDAlign::Right, ..options });
+        assert_eq!(center.lines.iter().map(|line| line.x).collect::<Vec<_>>(), [3.5, 13.0]);
+        assert_eq!(right.lines[1].glyphs[0].position.x, 26.0);
+
+        //the gap between the words takes up all the slack, the trailing space and the last line are left alone
+        let justify = layout("oo oo oo", RDLayoutOptions { align: RDAlign::Justify, ..options });
+        let first: Vec<_> = justify.lines[0].glyphs.iter().map(|glyph| glyph.position.x).collect();
+        assert_eq!(first, [0.0, 7.0, 14.0, 26.0, 33.0, 40.0]);
+        assert_eq!((justify.lines[0].width, justify.lines[1].width), (40.0, 14.0));
+
+        //lines without a width to fill are aligned to the widest one
+        let unbounded = layout("o\nooo", RDLayoutOptions { align: RDAlign::Right, ..Default::default() });
+        assert_eq!(unbounded.lines[0].x, 14.0);
+    }
+
+    #[test]
+    fn test_ellipsis() {
+        //two lines of 11 fit into 25, the font has neither an ellipsis nor a period so three missing glyphs stand in
+        let options = RDLayoutOptions { max_width: Some(30.0), max_height: Some(25.0), ellipsis: true, ..Default::default() };
+        let clipped = layout("oo oo oo", options);
+        assert!(clipped.truncated);
+        assert_eq!(ranges(&clipped), [(0, 3), (3, 5)]);
+        assert_eq!(clipped.lines[1].glyphs.iter().map(|glyph| (glyph.id, glyph.cluster)).collect::<Vec<_>>(), [(1, 3), (1, 4), (0, 5), (0, 5), (0, 5)]);
+        assert_eq!(clipped.lines[1].width, 29.0);
+
+        //a single line is cut wherever the ellipsis still fits
+        let single = layout("oooooo", RDLayoutOptions { wrap: RDWrap::None, max_height: None, ..options });
+        assert_eq!(ranges(&single), [(0, 2)]);
+        assert_eq!(single.glyphs().count(), 5);
+
+        //without an ellipsis the text is only left out
+        let plain = layout("oo oo oo", RDLayoutOptions { ellipsis: false, ..options });
+        assert!(plain.truncated);
+        assert_eq!(ranges(&plain), [(0, 3), (3, 6)]);
+    }
+
+    #[test]
+    fn test_carets() {
+        let words = layout("oo oo oo", RDLayoutOptions { max_width: Some(30.0), ..Default::default() });
+
+        //a break belongs to the line after it
+        assert_eq!(words.caret(4), Some(RDCaret { index: 4, line: 1, position: Vector2::new(7.0, -11.0) }));
+        assert_eq!(words.caret(3).map(|caret| (caret.line, caret.position.x)), Some((1, 0.0)));
+        assert_eq!(words.caret(8).map(|caret| (caret.line, caret.position.x)), Some((2, 14.0)));
+
+        //clicks snap to the nearest caret, above and below the text they stay on the outer lines
+        assert_eq!(words.hit_test(Vector2::new(13.0, -12.0)).map(|caret| caret.index), Some(5));
+        assert_eq!(words.hit_test(Vector2::new(100.0, -100.0)).map(|caret| caret.index), Some(8));
+        assert_eq!(words.hit_test(Vector2::new(-5.0, 50.0)).map(|caret| caret.index), Some(0));
+
+        //text behind an ellipsis can't be reached, the caret stops in front of it
+        let single = layout("oooooo", RDLayoutOptions { max_width: Some(30.0), wrap: RDWrap::None, ellipsis: true, ..Default::default() });
+        assert_eq!(single.caret(5).map(|caret| (caret.index, caret.position.x)), Some((2, 14.0)));
+        assert_eq!(single.hit_test(Vector2::new(28.0, 0.0)).map(|caret| caret.index), Some(2));
+
+        let empty = layout("", RDLayoutOptions::default());
+        assert_eq!(empty.caret(0).map(|caret| caret.position), Some(Vector2::new(0.0, 0.0)));
+        assert_eq!(empty.hit_test(Vector2::new(3.0, 3.0)).map(|caret| caret.index), Some(0));
+    }
+}
@@ -1,15 +1,50 @@
+mod allocator;
+mod arena;
+mod buffer;
+mod export;
+mod gfx;
 mod triangulate;
+mod xml;
+pub mod animation;
+pub mod atlas;
+pub mod backend;
+pub mod camera;
 pub mod color;
+pub mod font;
+pub mod headless;
+pub mod image;
+pub mod input;
+pub mod interaction;
+pub mod layout;
+pub mod math;
 pub mod path_builder;
+pub mod pdf;
 pub mod scene;
+#[cfg(feature = "software")]
+pub mod software;
+pub mod spatial;
+pub mod svg;
+pub mod text;
 pub mod treemap;
 
+pub use gfx::{RDGfxError, RDWgpuBackend};
+
+use std::io;
+use std::path::Path;
 use std::process::ExitCode;
 use std::sync::Arc;
-use std::time::Duration;
-
+use std::time::{Duration, Instant};
+
+use backend::render_frame;
+use backend::RDWindowBackend;
+use cgmath::Vector2;
+use color::RDColor;
+use math::RDAffine;
+use image::RDImage;
+use input::{RDEvent, InputState};
+use interaction::PointerRouter;
 use bytemuck::{Pod, Zeroable};
-use scene::{RDScene, VAO};
+use scene::RDScene;
 use winit::application::ApplicationHandler;
 use winit::dpi::{PhysicalSize, Size};
 use winit::event::{ElementState, KeyEvent, WindowEvent};
@@ -18,26 +53,67 @@ use winit::keyboard::{KeyCode, PhysicalKey};
 use winit::platform::pump_events::{EventLoopExtPumpEvents, PumpStatus};
 use winit::window::{Window, WindowId};
 
-struct GfxState {
-    device: wgpu::Device,
-    queue: wgpu::Queue,
-    config: wgpu::SurfaceConfiguration,
-    surface: wgpu::Surface<'static>,
-    render_pipeline: wgpu::RenderPipeline,
-    bind_group: wgpu::BindGroup,
-    
-    vertex_buffer: wgpu::Buffer,
-    index_buffer: wgpu::Buffer,
-    storage_buffer: wgpu::Buffer,
+//linear rgba, every backend clears to it
+const CLEAR_COLOR: [f32; 4] = [0.1, 0.2, 0.3, 1.0];
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RDRenderStats {
+    pub vertex_count: u32,
+    pub index_count: u32,
+    pub object_count: u32,
+    //sizes in bytes of the gpu buffers currently allocated
+    pub vertex_buffer_size: u64,
+    pub index_buffer_size: u64,
+    pub storage_buffer_size: u64,
+    pub buffer_reallocations: u32,
+    pub draw_calls: u32,
+    pub culled_draws: u32,
+    //bytes written to the gpu buffers during the last frame
+    pub uploaded_bytes: u64,
 }
 
-pub struct Raindeer {
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RDMultisample {
+    Off,
+    #[default]
+    X4,
+    X8,
+}
+
+impl RDMultisample {
+    pub fn sample_count(&self) -> u32 {
+        match self {
+            RDMultisample::Off => 1,
+            RDMultisample::X4 => 4,
+            RDMultisample::X8 => 8,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RDOptions {
+    pub multisample: RDMultisample,
+    //skip straight to a software adapter, only used by headless rendering
+    pub force_fallback_adapter: bool,
+}
+
+//drives a window, its events and a backend drawing the scene into it, wgpu unless told otherwise
+pub struct Raindeer<B: RDWindowBackend = RDWgpuBackend> {
     pub scene: RDScene,
-    size: winit::dpi::PhysicalSize<u32>,
+    options: RDOptions,
+    stats: RDRenderStats,
+    input: InputState,
+    router: PointerRouter,
+    //waiting for the application to poll them
+    events: Vec<RDEvent>,
+    //when run was last called, animations advance by the time in between
+    last_run: Option<Instant>,
 
     window: Option<Arc<Window>>,
     event_loop: Option<EventLoop<()>>,
-    gfx_state: Option<GfxState>,
+    backend: Option<B>,
+    //the frame error the backend couldn't recover from, it stopped the event loop
+    error: Option<B::Error>,
 }
 
 #[repr(C)]
@@ -49,17 +125,57 @@ pub struct RDVertex {
     id: u32,
 }
 
-#[repr(C, align(16))]
+//matches the wgsl Object struct, a mat3x2 followed by two u32s
+#[repr(C, align(8))]
 #[derive(Copy, Clone, Debug)]
 pub struct RDStorage {
-    transform: [[f32; 4]; 4],
+    transform: [[f32; 2]; 3],
     texture: u32,
+    //linear rgba the vertex colors are multiplied by
+    tint: u32,
 }
 
 unsafe impl Zeroable for RDStorage {}
 unsafe impl Pod for RDStorage {}
 
+//read access for backends living outside the crate, the bytes themselves can go to a gpu as they are through bytemuck
+impl RDStorage {
+    //local to world space
+    pub fn transform(&self) -> RDAffine {
+        RDAffine::from(self.transform)
+    }
+
+    //0 for plain meshes, atlas::GLYPH_ATLAS_TEXTURE for distance field text
+    pub fn texture(&self) -> u32 {
+        self.texture
+    }
+
+    pub fn tint(&self) -> RDColor {
+        RDColor::from_u32(self.tint)
+    }
+}
+
 impl RDVertex {
+    //linear, see RDColor::to_u32
+    pub fn color(&self) -> RDColor {
+        RDColor::from_u32(self.color)
+    }
+
+    //local to the object
+    pub fn position(&self) -> Vector2<f32> {
+        self.position.into()
+    }
+
+    //in atlas pixels, only used by textured objects
+    pub fn texture_position(&self) -> Vector2<f32> {
+        self.texture_position.into()
+    }
+
+    //the RDGfxUpdate::storage slot holding this vertex's transform
+    pub fn object(&self) -> u32 {
+        self.id
+    }
+
     fn desc() -> wgpu::VertexBufferLayout<'static> {
         wgpu::VertexBufferLayout {
             array_stride: std::mem::size_of::<RDVertex>() as wgpu::BufferAddress,
@@ -93,7 +209,7 @@ impl RDVertex {
 unsafe impl Zeroable for RDVertex {}
 unsafe impl Pod for RDVertex {}
 
-impl ApplicationHandler for Raindeer {
+impl<B: RDWindowBackend> ApplicationHandler for Raindeer<B> {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         let window_attibutes = Window::default_attributes()
             .with_title("heya")
@@ -107,8 +223,16 @@ impl ApplicationHandler for Raindeer {
         self.window = Some(window.clone());
     }
 
-    fn window_event(&mut self, event_loop: &ActiveEventLoop, id: WindowId, event: WindowEvent) {
-        self.window.clone().take().unwrap().request_redraw();
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _id: WindowId, event: WindowEvent) {
+        self.window.clone().unwrap().request_redraw();
+
+        let mut raw = vec![];
+        self.input.translate(&event, &self.scene, &mut raw);
+
+        for event in raw {
+            self.events.push(event.clone());
+            self.router.route(&event, &self.scene, &mut self.events);
+        }
 
         match event {
             WindowEvent::CloseRequested
@@ -125,14 +249,14 @@ impl ApplicationHandler for Raindeer {
                 self.resize(physical_size);
             }
             WindowEvent::RedrawRequested => {
-                match self.render() {
-                    Ok(_) => {}
-                    // Reconfigure the surface if lost
-                    Err(wgpu::SurfaceError::Lost) => self.resize(self.size),
-                    // The system is out of memory, we should probably quit
-                    Err(wgpu::SurfaceError::OutOfMemory) => event_loop.exit(),
-                    // All other errors (Outdated, Timeout) should be resolved by the next frame
-                    Err(e) => eprintln!("{:?}", e),
+                let recovered = match (self.render(), self.backend.as_mut()) {
+                    (Err(error), Some(backend)) => backend.recover(error),
+                    (result, _) => result,
+                };
+
+                if let Err(error) = recovered {
+                    self.error = Some(error);
+                    event_loop.exit();
                 }
             }
             _ => (),
@@ -140,271 +264,102 @@ impl ApplicationHandler for Raindeer {
     }
 }
 
+
+impl Default for Raindeer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Raindeer {
     pub fn new() -> Self {
         pollster::block_on(Raindeer::async_new())
     }
 
+    pub fn with_options(options: RDOptions) -> Self {
+        pollster::block_on(Raindeer::async_with_options(options))
+    }
+
     pub async fn async_new() -> Self {
+        Raindeer::async_with_options(RDOptions::default()).await
+    }
+
+    pub async fn async_with_options(options: RDOptions) -> Self {
+        Raindeer::with_backend(options)
+    }
+
+    pub async fn async_init_graphics(&mut self, window: Arc<Window>) {
+        self.backend = Some(RDWgpuBackend::for_window(window, self.options.multisample).await);
+    }
+}
+
+impl<B: RDWindowBackend> Raindeer<B> {
+    //for backends other than wgpu, as in Raindeer::<MyBackend>::with_backend(options)
+    pub fn with_backend(options: RDOptions) -> Self {
         let event_loop = EventLoop::new().unwrap();
         event_loop.set_control_flow(ControlFlow::Poll);
 
         Self {
             scene: RDScene::new(10),
-            size: PhysicalSize::new(800, 800),
+            options,
+            stats: RDRenderStats::default(),
+            input: InputState::default(),
+            router: PointerRouter::default(),
+            events: vec![],
+            last_run: None,
             window: None,
-            gfx_state: None,
+            backend: None,
+            error: None,
             event_loop: Some(event_loop),
         }
     }
 
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
-        let Some(ref mut gfx) = self.gfx_state else { panic!("gfx state uninitialized"); };
+        let Some(backend) = self.backend.as_mut() else { panic!("gfx state uninitialized"); };
 
         if new_size.width > 0 && new_size.height > 0 {
-            self.size = new_size;
-            gfx.config.width = new_size.width;
-            gfx.config.height = new_size.height;
-            gfx.surface.configure(&gfx.device, &gfx.config);
+            backend.resize(new_size.width, new_size.height);
         }
     }
 
-    pub async fn async_init_graphics(&mut self, window: Arc<Window>) {
-        let size = window.inner_size();
-
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            ..Default::default()
-        });
-        
-        let surface = instance.create_surface(window).unwrap();
-
-        let adapter = instance.request_adapter(
-            &wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            },
-        ).await.unwrap();
-
-        let (device, queue) = adapter.request_device(
-            &wgpu::DeviceDescriptor {
-                required_features: wgpu::Features::default(),
-                required_limits: wgpu::Limits::default(),
-                label: None,
-                memory_hints: Default::default(),
-            },
-            None,
-        ).await.unwrap();
-
-        let surface_caps = surface.get_capabilities(&adapter);
-
-        let surface_format = surface_caps.formats.iter()
-            .find(|f| f.is_srgb())
-            .copied()
-            .unwrap_or(surface_caps.formats[0]);
-
-        let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: surface_format,
-            width: size.width,
-            height: size.height,
-            present_mode: surface_caps.present_modes[0],
-            alpha_mode: surface_caps.alpha_modes[0],
-            view_formats: vec![],
-            desired_maximum_frame_latency: 2,
-        };
-
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
-        });
-
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Bind Group Layout"),
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    count: None,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer { 
-                        ty: wgpu::BufferBindingType::Storage { read_only: true }, 
-                        has_dynamic_offset: false, 
-                        min_binding_size: None,
-                    }
-                }
-            ],
-        });
-        
-        let storage_buffer = device.create_buffer(
-            &wgpu::BufferDescriptor {
-                label: Some("Storage Buffer"),
-                mapped_at_creation: false,
-                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
-                size: std::mem::size_of::<RDStorage>() as u64 * 16384,
-            }
-        );
-        
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Storage Buffer"),
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: storage_buffer.as_entire_binding(),
-                }
-            ],
-            layout: &bind_group_layout,
-        });
-
-        let render_pipeline_layout =
-            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("Render Pipeline Layout"),
-                bind_group_layouts: &[
-                    &bind_group_layout,
-                ],
-                push_constant_ranges: &[],
-            });
-
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vs_main", // 1.
-                buffers: &[
-                    RDVertex::desc(),
-                ], // 2.
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            },
-            fragment: Some(wgpu::FragmentState { // 3.
-                module: &shader,
-                entry_point: "fs_main",
-                targets: &[Some(wgpu::ColorTargetState { // 4.
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList, // 1.
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw, // 2.
-                cull_mode: Some(wgpu::Face::Back),
-                // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
-                polygon_mode: wgpu::PolygonMode::Fill,
-                // Requires Features::DEPTH_CLIP_CONTROL
-                unclipped_depth: false,
-                // Requires Features::CONSERVATIVE_RASTERIZATION
-                conservative: false,
-            },
-            depth_stencil: None, // 1.
-            multisample: wgpu::MultisampleState {
-                count: 1, // 2.
-                mask: !0, // 3.
-                alpha_to_coverage_enabled: false, // 4.
-            },
-            multiview: None, // 5.
-            cache: None, // 6.
-        });
-
-        let vertex_buffer = device.create_buffer(
-            &wgpu::BufferDescriptor {
-                label: Some("Vertex Buffer"),
-                mapped_at_creation: false,
-                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-                size: std::mem::size_of::<RDVertex>() as u64 * 16384,
-            }
-        );
-                
-        let index_buffer = device.create_buffer(
-            &wgpu::BufferDescriptor {
-                label: Some("Index Buffer"),
-                mapped_at_creation: false,
-                usage: wgpu::BufferUsages::INDEX | wgpu::BufferUsages::COPY_DST,
-                size: std::mem::size_of::<u32>() as u64 * 32768,
-            }
-        );
-
-        self.gfx_state = Some(GfxState {
-            bind_group,
-            storage_buffer,
-            index_buffer,
-            vertex_buffer,
-            device,
-            queue,
-            surface,
-            config,
-            render_pipeline,
-        });
-    }
-
     pub fn init_graphics(&mut self, window: Arc<Window>) {
-        pollster::block_on(self.async_init_graphics(window));
+        self.backend = Some(B::create(window, &self.options));
     }
 
-    pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let mut gfx_wrapper = self.gfx_state.take();
-        let Some(ref mut gfx) = gfx_wrapper else { panic!("gfx state uninitialized"); };
-
-        let output = gfx.surface.get_current_texture()?;
-        let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
+    pub fn render(&mut self) -> Result<(), B::Error> {
+        let Some(backend) = self.backend.as_mut() else { panic!("gfx state uninitialized"); };
 
-        let mut encoder = gfx.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Render Encoder"),
-        });
-        
-        let storage = self.scene.output_gfx_storage(self.size.height as f32, self.size.width as f32);
-        gfx.queue.write_buffer(&gfx.storage_buffer, 0, bytemuck::cast_slice(&storage));
+        render_frame(&mut self.scene, backend, &mut self.stats)?;
 
-        if !self.scene.vertex_cache {
-            let VAO { vertices, indicies } = self.scene.output_gfx_vao();
-
-            println!("{:#?}", storage);
-            println!("{:#?}", vertices);
-            println!("{:?}", indicies);
+        Ok(())
+    }
 
-            gfx.queue.write_buffer(&gfx.vertex_buffer, 0, bytemuck::cast_slice(&vertices));
-            gfx.queue.write_buffer(&gfx.index_buffer, 0, bytemuck::cast_slice(&indicies));
+    pub fn stats(&self) -> RDRenderStats {
+        self.stats
+    }
 
-            self.scene.vertex_cache = true;
-            self.scene.index_count = indicies.len() as u32;
-        }
+    //renders a frame and reads back exactly what gets presented
+    //None before the window exists, when the frame fails or when the surface format isn't 8 bit rgba or bgra
+    pub fn capture_frame(&mut self) -> Option<RDImage> {
+        let backend = self.backend.as_mut()?;
+        backend.capture_next_frame();
 
-        {
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.1,
-                            g: 0.2,
-                            b: 0.3,
-                            a: 1.0,
-                        }),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                occlusion_query_set: None,
-                timestamp_writes: None,
-            });
-
-            render_pass.set_pipeline(&gfx.render_pipeline);
-            render_pass.set_bind_group(0, &gfx.bind_group, &[]);
-            render_pass.set_vertex_buffer(0, gfx.vertex_buffer.slice(..));
-            render_pass.set_index_buffer(gfx.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-            render_pass.draw_indexed(0..self.scene.index_count, 0, 0..1);
-        }
+        render_frame(&mut self.scene, backend, &mut self.stats).ok().flatten()
+    }
 
-        // submit will accept anything that implements IntoIter
-        gfx.queue.submit(std::iter::once(encoder.finish()));
-        output.present();
+    pub fn save_screenshot<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        let image = self.capture_frame().ok_or_else(|| io::Error::other("no frame could be captured"))?;
+        image.save_png(path)
+    }
 
-        self.gfx_state = gfx_wrapper;
+    //why run returned an exit code, when it was a frame that failed
+    pub fn take_error(&mut self) -> Option<B::Error> {
+        self.error.take()
+    }
 
-        Ok(())
+    //everything that happened since the last call, oldest first
+    pub fn poll_events(&mut self) -> std::vec::Drain<'_, RDEvent> {
+        self.events.drain(..)
     }
 
     pub fn run(&mut self) -> Result<(), ExitCode> {
@@ -414,6 +369,11 @@ impl Raindeer {
             panic!("no event loop");
         };
 
+        let now = Instant::now();
+        if let Some(last_run) = self.last_run.replace(now) {
+            self.scene.advance_animations(now - last_run);
+        }
+
         let status = event_loop.pump_app_events(Some(Duration::ZERO), self);
 
         self.event_loop = event_loop_wrapper;
@@ -422,6 +382,6 @@ impl Raindeer {
             return Err(ExitCode::from(exitcode as u8));
         }
 
-        return Ok(());
+        Ok(())
     }
 }
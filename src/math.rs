@@ -0,0 +1,260 @@
+use std::ops::Mul;
+
+use cgmath::Vector2;
+
+//axis aligned, min is the lower left corner
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RDRect {
+    pub min: Vector2<f32>,
+    pub max: Vector2<f32>,
+}
+
+impl RDRect {
+    pub fn new(min: Vector2<f32>, max: Vector2<f32>) -> Self {
+        Self { min, max }
+    }
+
+    pub fn from_points<I: IntoIterator<Item = Vector2<f32>>>(points: I) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+
+        Some(points.fold(RDRect::new(first, first), |rect, point| rect.include(point)))
+    }
+
+    pub fn include(&self, point: Vector2<f32>) -> Self {
+        Self {
+            min: Vector2::new(self.min.x.min(point.x), self.min.y.min(point.y)),
+            max: Vector2::new(self.max.x.max(point.x), self.max.y.max(point.y)),
+        }
+    }
+
+    pub fn union(&self, other: &RDRect) -> Self {
+        self.include(other.min).include(other.max)
+    }
+
+    pub fn expand(&self, amount: f32) -> Self {
+        Self {
+            min: self.min - Vector2::new(amount, amount),
+            max: self.max + Vector2::new(amount, amount),
+        }
+    }
+
+    pub fn intersection(&self, other: &RDRect) -> Option<Self> {
+        self.intersects(other).then(|| Self {
+            min: Vector2::new(self.min.x.max(other.min.x), self.min.y.max(other.min.y)),
+            max: Vector2::new(self.max.x.min(other.max.x), self.max.y.min(other.max.y)),
+        })
+    }
+
+    pub fn width(&self) -> f32 {
+        self.max.x - self.min.x
+    }
+
+    pub fn height(&self) -> f32 {
+        self.max.y - self.min.y
+    }
+
+    pub fn center(&self) -> Vector2<f32> {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn corners(&self) -> [Vector2<f32>; 4] {
+        [
+            self.min,
+            Vector2::new(self.max.x, self.min.y),
+            self.max,
+            Vector2::new(self.min.x, self.max.y),
+        ]
+    }
+
+    pub fn contains(&self, point: Vector2<f32>) -> bool {
+        point.x >= self.min.x && point.x <= self.max.x && point.y >= self.min.y && point.y <= self.max.y
+    }
+
+    pub fn intersects(&self, other: &RDRect) -> bool {
+        self.min.x <= other.max.x && self.max.x >= other.min.x && self.min.y <= other.max.y && self.max.y >= other.min.y
+    }
+
+    //bounds of the transformed rect, which can be larger than the rect itself once rotated
+    pub fn transform(&self, matrix: &RDAffine) -> Self {
+        RDRect::from_points(self.corners().map(|corner| matrix.transform_point(corner))).unwrap()
+    }
+}
+
+//2x3 affine matrix stored as three columns, the last one being the translation
+//laid out like a wgsl mat3x2 so it can be uploaded as is
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RDAffine {
+    pub x: Vector2<f32>,
+    pub y: Vector2<f32>,
+    pub w: Vector2<f32>,
+}
+
+impl Default for RDAffine {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl RDAffine {
+    pub fn new(x: Vector2<f32>, y: Vector2<f32>, w: Vector2<f32>) -> Self {
+        Self { x, y, w }
+    }
+
+    pub fn identity() -> Self {
+        Self::from_scale(Vector2::new(1.0, 1.0))
+    }
+
+    pub fn from_translation(translation: Vector2<f32>) -> Self {
+        Self::new(Vector2::new(1.0, 0.0), Vector2::new(0.0, 1.0), translation)
+    }
+
+    //counter clockwise, in radians
+    pub fn from_rotation(angle: f32) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self::new(Vector2::new(cos, sin), Vector2::new(-sin, cos), Vector2::new(0.0, 0.0))
+    }
+
+    pub fn from_scale(scale: Vector2<f32>) -> Self {
+        Self::new(Vector2::new(scale.x, 0.0), Vector2::new(0.0, scale.y), Vector2::new(0.0, 0.0))
+    }
+
+    //angles in radians, x leans vertical lines sideways and y tilts horizontal lines up
+    pub fn from_skew(skew: Vector2<f32>) -> Self {
+        Self::new(Vector2::new(1.0, skew.y.tan()), Vector2::new(skew.x.tan(), 1.0), Vector2::new(0.0, 0.0))
+    }
+
+    //applies other first, then self
+    pub fn compose(&self, other: &RDAffine) -> Self {
+        Self::new(
+            self.transform_vector(other.x),
+            self.transform_vector(other.y),
+            self.transform_point(other.w),
+        )
+    }
+
+    pub fn determinant(&self) -> f32 {
+        self.x.x * self.y.y - self.y.x * self.x.y
+    }
+
+    //None if the matrix collapses everything onto a line or point
+    pub fn inverse(&self) -> Option<Self> {
+        let determinant = self.determinant();
+
+        if determinant.abs() < f32::EPSILON {
+            return None;
+        }
+
+        let x = Vector2::new(self.y.y, -self.x.y) / determinant;
+        let y = Vector2::new(-self.y.x, self.x.x) / determinant;
+        let w = -(x * self.w.x + y * self.w.y);
+
+        Some(Self::new(x, y, w))
+    }
+
+    pub fn transform_point(&self, point: Vector2<f32>) -> Vector2<f32> {
+        self.transform_vector(point) + self.w
+    }
+
+    //ignores the translation
+    pub fn transform_vector(&self, vector: Vector2<f32>) -> Vector2<f32> {
+        self.x * vector.x + self.y * vector.y
+    }
+}
+
+impl Mul for RDAffine {
+    type Output = RDAffine;
+
+    fn mul(self, other: RDAffine) -> RDAffine {
+        self.compose(&other)
+    }
+}
+
+impl From<[[f32; 2]; 3]> for RDAffine {
+    fn from([x, y, w]: [[f32; 2]; 3]) -> Self {
+        Self::new(x.into(), y.into(), w.into())
+    }
+}
+
+impl From<RDAffine> for [[f32; 2]; 3] {
+    fn from(matrix: RDAffine) -> Self {
+        [matrix.x.into(), matrix.y.into(), matrix.w.into()]
+    }
+}
+
+//either winding, points on an edge count as inside
+pub(crate) fn triangle_contains(a: Vector2<f32>, b: Vector2<f32>, c: Vector2<f32>, p: Vector2<f32>) -> bool {
+    let d1 = (b - a).perp_dot(p - a);
+    let d2 = (c - b).perp_dot(p - b);
+    let d3 = (a - c).perp_dot(p - c);
+
+    let has_negative = d1 < 0.0 || d2 < 0.0 || d3 < 0.0;
+    let has_positive = d1 > 0.0 || d2 > 0.0 || d3 > 0.0;
+
+    !(has_negative && has_positive)
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{InnerSpace, Vector2};
+
+    use super::*;
+
+    #[test]
+    fn test_rect() {
+        let rect = RDRect::from_points([
+            Vector2::new(1.0, 2.0),
+            Vector2::new(-1.0, 4.0),
+            Vector2::new(3.0, 0.0),
+        ]).unwrap();
+
+        assert_eq!(rect, RDRect::new(Vector2::new(-1.0, 0.0), Vector2::new(3.0, 4.0)));
+        assert_eq!(rect.center(), Vector2::new(1.0, 2.0));
+        assert!(rect.contains(Vector2::new(3.0, 4.0)));
+        assert!(!rect.contains(Vector2::new(3.5, 4.0)));
+        assert!(rect.intersects(&RDRect::new(Vector2::new(3.0, 4.0), Vector2::new(5.0, 5.0))));
+        assert!(!rect.intersects(&RDRect::new(Vector2::new(3.1, 4.0), Vector2::new(5.0, 5.0))));
+        assert_eq!(RDRect::from_points([]), None);
+
+        let moved = rect.transform(&(RDAffine::from_translation(Vector2::new(1.0, 1.0)) * RDAffine::from_rotation(std::f32::consts::FRAC_PI_2)));
+        assert!((moved.min - Vector2::new(-3.0, 0.0)).magnitude() < 1e-5);
+        assert!((moved.max - Vector2::new(1.0, 4.0)).magnitude() < 1e-5);
+    }
+
+    #[test]
+    fn test_affine() {
+        let matrix = RDAffine::from_translation(Vector2::new(3.0, -2.0))
+            * RDAffine::from_rotation(0.7)
+            * RDAffine::from_skew(Vector2::new(0.3, -0.2))
+            * RDAffine::from_scale(Vector2::new(2.0, -0.5));
+
+        let point = Vector2::new(1.5, -4.0);
+        let back = matrix.inverse().unwrap().transform_point(matrix.transform_point(point));
+        assert!((back - point).magnitude() < 1e-5);
+
+        let identity = matrix * matrix.inverse().unwrap();
+        assert!((identity.x - Vector2::new(1.0, 0.0)).magnitude() < 1e-5);
+        assert!((identity.y - Vector2::new(0.0, 1.0)).magnitude() < 1e-5);
+        assert!(identity.w.magnitude() < 1e-5);
+
+        let rotated = RDAffine::from_rotation(std::f32::consts::FRAC_PI_2).transform_point(Vector2::new(1.0, 0.0));
+        assert!((rotated - Vector2::new(0.0, 1.0)).magnitude() < 1e-6);
+
+        let skewed = RDAffine::from_skew(Vector2::new(std::f32::consts::FRAC_PI_4, 0.0)).transform_point(Vector2::new(0.0, 2.0));
+        assert!((skewed - Vector2::new(2.0, 2.0)).magnitude() < 1e-6);
+
+        assert_eq!(RDAffine::from_scale(Vector2::new(0.0, 1.0)).inverse(), None);
+        assert_eq!(<[[f32; 2]; 3]>::from(RDAffine::from_translation(Vector2::new(4.0, 5.0)))[2], [4.0, 5.0]);
+    }
+
+    #[test]
+    fn test_triangle_contains() {
+        let (a, b, c) = (Vector2::new(0.0, 0.0), Vector2::new(10.0, 0.0), Vector2::new(0.0, 10.0));
+
+        assert!(triangle_contains(a, b, c, Vector2::new(2.0, 2.0)));
+        assert!(triangle_contains(c, b, a, Vector2::new(2.0, 2.0)));
+        assert!(triangle_contains(a, b, c, Vector2::new(5.0, 5.0)));
+        assert!(!triangle_contains(a, b, c, Vector2::new(6.0, 6.0)));
+        assert!(!triangle_contains(a, b, c, Vector2::new(-0.1, 2.0)));
+    }
+}
@@ -1,8 +1,11 @@
-use cgmath::Vector2;
-use crate::{color::RDColor, scene::{RDMesh, RDNode, RDTransform, VAO}, triangulate::{triangulate, triangulate_stroke}, RDVertex};
+use std::sync::Arc;
+
+use cgmath::{InnerSpace, Vector2};
+use crate::{color::RDColor, math::{RDAffine, RDRect}, scene::{RDMesh, RDMeshSource, RDNode, VAO}, triangulate::{bridge_holes, even_odd_parents, signed_area, subdivide, triangulate, triangulate_fringe, triangulate_open_stroke, triangulate_stroke}, RDVertex};
 
 //cap style
 //stroke style (in, out, middle)
+#[derive(Clone)]
 pub struct RDStroke {
     pub weight: f32,
     pub color: RDColor,
@@ -17,15 +20,210 @@ impl Default for RDStroke {
     }
 }
 
-struct ContinousPath {
-    points: Vec<Vector2<f32>>,
-    closed: bool,
+#[derive(Clone, Debug, PartialEq)]
+pub enum RDGradientShape {
+    Linear { start: Vector2<f32>, end: Vector2<f32> },
+    //offset 0 sits at the focus and offset 1 on the circle
+    Radial { center: Vector2<f32>, radius: f32, focus: Vector2<f32> },
+}
+
+//colors are evaluated at the fill's vertices, so the fill is subdivided to give the gradient room
+//colors beyond the first and last stop are padded
+#[derive(Clone, Debug, PartialEq)]
+pub struct RDGradient {
+    pub shape: RDGradientShape,
+    //offsets in 0..1, in ascending order
+    pub stops: Vec<(f32, RDColor)>,
+    //from the gradient's own space into path space
+    pub transform: RDAffine,
+}
+
+impl RDGradient {
+    pub fn linear(start: Vector2<f32>, end: Vector2<f32>, stops: Vec<(f32, RDColor)>) -> Self {
+        Self {
+            shape: RDGradientShape::Linear { start, end },
+            stops,
+            transform: RDAffine::identity(),
+        }
+    }
+
+    pub fn radial(center: Vector2<f32>, radius: f32, stops: Vec<(f32, RDColor)>) -> Self {
+        Self {
+            shape: RDGradientShape::Radial { center, radius, focus: center },
+            stops,
+            transform: RDAffine::identity(),
+        }
+    }
+
+    //the gradient's offset at a point in path space
+    fn offset_at(&self, point: Vector2<f32>) -> f32 {
+        let Some(inverse) = self.transform.inverse() else { return 0.0 };
+        let point = inverse.transform_point(point);
+
+        let offset = match self.shape {
+            RDGradientShape::Linear { start, end } => {
+                let direction = end - start;
+                if direction.magnitude2() == 0.0 { 0.0 } else { (point - start).dot(direction) / direction.magnitude2() }
+            }
+            //the offset t puts the point on the circle around focus + (center - focus) * t with radius * t
+            RDGradientShape::Radial { center, radius, focus } => {
+                let (d, e) = (point - focus, center - focus);
+                let a = e.dot(e) - radius * radius;
+                let (b, c) = (d.dot(e), d.dot(d));
+
+                if a.abs() < f32::EPSILON {
+                    if b == 0.0 { 0.0 } else { c / (2.0 * b) }
+                } else {
+                    (b - (b * b - a * c).max(0.0).sqrt()) / a
+                }
+            }
+        };
+
+        if offset.is_finite() { offset.clamp(0.0, 1.0) } else { 0.0 }
+    }
+
+    pub fn color_at(&self, point: Vector2<f32>) -> RDColor {
+        let offset = self.offset_at(point);
+
+        let Some(next) = self.stops.iter().position(|(stop, _)| *stop >= offset) else {
+            return self.stops.last().map_or(RDColor::TRANSPARENT, |(_, color)| color.clone());
+        };
+
+        if next == 0 {
+            return self.stops[0].1.clone();
+        }
+
+        let ((from, from_color), (to, to_color)) = (&self.stops[next - 1], &self.stops[next]);
+        let t = if to > from { (offset - from) / (to - from) } else { 1.0 };
+        from_color.lerp(to_color, t)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RDAntiAliasing {
+    None,
+    //fades the outer edges over a strip this wide (in path units), for targets without msaa
+    Fringe(f32),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RDFillRule {
+    //every subpath is filled on its own, overlaps just add up
+    #[default]
+    Union,
+    //subpaths inside an odd number of others are cut out, like the counters of glyphs
+    EvenOdd,
+}
+
+//how far in path units a flattened curve may stray from the real one
+const CURVE_TOLERANCE: f32 = 0.01;
+const MAX_CURVE_STEPS: u32 = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum PathSegment {
+    Line(Vector2<f32>),
+    //two control points then the end point
+    Cubic(Vector2<f32>, Vector2<f32>, Vector2<f32>),
+}
+
+#[derive(Clone)]
+pub(crate) struct ContinousPath {
+    pub(crate) start: Vector2<f32>,
+    //what was drawn, exporters write these out as they are
+    pub(crate) segments: Vec<PathSegment>,
+    //the segments flattened into a polygon for tessellation, starting with `start`
+    pub(crate) points: Vec<Vector2<f32>>,
+    pub(crate) closed: bool,
 }
 
+impl ContinousPath {
+    fn new(start: Vector2<f32>) -> Self {
+        Self {
+            start,
+            segments: vec![],
+            points: vec![start],
+            closed: false,
+        }
+    }
+
+    pub(crate) fn polygon(points: &[Vector2<f32>]) -> Self {
+        let mut path = Self::new(points[0]);
+        for point in points[1..].iter() {
+            path.segments.push(PathSegment::Line(*point));
+            path.push_point(*point);
+        }
+        path.closed = true;
+        path
+    }
+
+    //repeated points would give the stroke zero length edges to offset
+    fn push_point(&mut self, point: Vector2<f32>) {
+        if self.points.last() != Some(&point) {
+            self.points.push(point);
+        }
+    }
+
+    fn cubic(&mut self, control_a: Vector2<f32>, control_b: Vector2<f32>, end: Vector2<f32>) {
+        let start = *self.points.last().unwrap();
+        self.segments.push(PathSegment::Cubic(control_a, control_b, end));
+
+        //wang's formula, enough steps to keep every line within the tolerance of the curve
+        let second_difference = (start - control_a * 2.0 + control_b).magnitude().max((control_a - control_b * 2.0 + end).magnitude());
+        let steps = ((0.75 * second_difference / CURVE_TOLERANCE).sqrt().ceil() as u32).clamp(1, MAX_CURVE_STEPS);
+
+        for step in 1..=steps {
+            let t = step as f32 / steps as f32;
+            let u = 1.0 - t;
+
+            self.push_point(start * (u * u * u) + control_a * (3.0 * u * u * t) + control_b * (3.0 * u * t * t) + end * (t * t * t));
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct RDPath {
     continous_paths: Vec<ContinousPath>,
     pub color: RDColor,
+    //replaces color on the fill when set, exporters still write color
+    pub gradient: Option<RDGradient>,
     pub stroke: RDStroke,
+    pub anti_aliasing: RDAntiAliasing,
+    pub fill_rule: RDFillRule,
+    //open subpaths are only filled unless this is set, the svg loader sets it as svg strokes them
+    pub stroke_open: bool,
+}
+
+impl Default for RDPath {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn colored_vao(points: &[Vector2<f32>], indicies: Vec<u32>, color: &RDColor) -> VAO {
+    let mut vertices = vec![];
+
+    for point in points.iter() {
+        vertices.push(RDVertex {
+            id: 0,
+            position: (*point).into(),
+            texture_position: [0.0, 0.0], 
+            color: color.to_u32(), 
+        })
+    }
+
+    VAO { vertices, indicies }
+}
+
+fn fringe_vao(ring: &[Vector2<f32>], width: f32, outward: bool, color: &RDColor) -> VAO {
+    let (points, indicies) = triangulate_fringe(ring, width, outward);
+    let mut vao = colored_vao(&points, indicies, color);
+
+    let faded = RDColor { a: 0, ..color.clone() }.to_u32();
+    for vertex in vao.vertices.iter_mut().skip(1).step_by(2) {
+        vertex.color = faded;
+    }
+
+    vao
 }
 
 impl RDPath {
@@ -33,15 +231,16 @@ impl RDPath {
         Self {
             continous_paths: vec![],
             color: RDColor::WHITE,
+            gradient: None,
             stroke: RDStroke::default(),
+            anti_aliasing: RDAntiAliasing::None,
+            fill_rule: RDFillRule::Union,
+            stroke_open: false,
         }
     }
 
     pub fn to(mut self, x: f32, y: f32) -> Self {
-        self.continous_paths.push(ContinousPath {
-            points: vec![Vector2::new(x, y)],
-            closed: false,
-        });
+        self.continous_paths.push(ContinousPath::new(Vector2::new(x, y)));
         self
     }
     
@@ -50,16 +249,45 @@ impl RDPath {
 
         debug_assert!(!self.continous_paths[last].closed);
 
-        self.continous_paths[last].points.push(Vector2::new(x, y));
+        let point = Vector2::new(x, y);
+        self.continous_paths[last].segments.push(PathSegment::Line(point));
+        self.continous_paths[last].push_point(point);
         self 
     }
 
-    pub fn close(mut self) -> Self {
+    //cubic bezier from the current point through two control points, flattened for drawing but exported as a curve
+    pub fn cubic(mut self, control_ax: f32, control_ay: f32, control_bx: f32, control_by: f32, x: f32, y: f32) -> Self {
         let last = self.continous_paths.len()-1;
 
         debug_assert!(!self.continous_paths[last].closed);
 
-        self.continous_paths[last].closed = true;
+        self.continous_paths[last].cubic(Vector2::new(control_ax, control_ay), Vector2::new(control_bx, control_by), Vector2::new(x, y));
+        self
+    }
+
+    //quadratic bezier, stored as the cubic that traces the same curve
+    pub fn quad(self, control_x: f32, control_y: f32, x: f32, y: f32) -> Self {
+        let start = *self.continous_paths.last().unwrap().points.last().unwrap();
+        let (control, end) = (Vector2::new(control_x, control_y), Vector2::new(x, y));
+
+        let control_a = start + (control - start) * (2.0 / 3.0);
+        let control_b = end + (control - end) * (2.0 / 3.0);
+
+        self.cubic(control_a.x, control_a.y, control_b.x, control_b.y, x, y)
+    }
+
+    pub fn close(mut self) -> Self {
+        let last = self.continous_paths.len()-1;
+        let path = &mut self.continous_paths[last];
+
+        debug_assert!(!path.closed);
+
+        //closing adds the edge back to the start, it mustn't also be a point of its own
+        while path.points.len() > 1 && path.points.last() == Some(&path.start) {
+            path.points.pop();
+        }
+
+        path.closed = true;
         self
     }
     
@@ -68,72 +296,155 @@ impl RDPath {
         self
     }
     
+    pub fn gradient(mut self, gradient: RDGradient) -> Self {
+        self.gradient = Some(gradient);
+        self
+    }
+
     pub fn stroke(mut self, config: RDStroke) -> Self {
         self.stroke = config;
         self
     }
 
+    pub fn anti_alias(mut self, mode: RDAntiAliasing) -> Self {
+        self.anti_aliasing = mode;
+        self
+    }
+
+    pub fn fill_rule(mut self, rule: RDFillRule) -> Self {
+        self.fill_rule = rule;
+        self
+    }
+
+    pub fn stroke_open(mut self, stroke_open: bool) -> Self {
+        self.stroke_open = stroke_open;
+        self
+    }
+
+    pub(crate) fn subpaths(&self) -> &[ContinousPath] {
+        &self.continous_paths
+    }
+
+    //covers the stroke and any anti aliasing fringe, not just the points
+    pub fn bounds(&self) -> Option<RDRect> {
+        let mut bounds: Option<RDRect> = None;
+
+        for path in self.continous_paths.iter() {
+            let (stroke_points, _) = self.stroke_path(path);
+            let path_bounds = RDRect::from_points(stroke_points.into_iter().chain(path.points.iter().copied()));
+
+            if let Some(path_bounds) = path_bounds {
+                bounds = Some(bounds.map_or(path_bounds, |bounds| bounds.union(&path_bounds)));
+            }
+        }
+
+        match self.anti_aliasing {
+            RDAntiAliasing::Fringe(width) => bounds.map(|bounds| bounds.expand(width)),
+            RDAntiAliasing::None => bounds,
+        }
+    }
+
+    pub(crate) fn is_stroked(&self, path: &ContinousPath) -> bool {
+        self.stroke.weight > 0.0 && (path.closed || self.stroke_open)
+    }
+
+    //open subpaths get square ends, a closed one needs at least a triangle to go around
+    fn stroke_path(&self, path: &ContinousPath) -> (Vec<Vector2<f32>>, Vec<u32>) {
+        if !self.is_stroked(path) {
+            (vec![], vec![])
+        } else if path.closed && path.points.len() > 2 {
+            triangulate_stroke(&path.points, &self.stroke)
+        } else {
+            triangulate_open_stroke(&path.points, &self.stroke)
+        }
+    }
+
+    fn fill_vao(&self, points: &[Vector2<f32>], indicies: Vec<u32>) -> VAO {
+        let Some(gradient) = &self.gradient else {
+            return colored_vao(points, indicies, &self.color);
+        };
+
+        //enough triangles across the path for the gradient to show between its stops
+        let size = RDRect::from_points(points.iter().copied()).map_or(0.0, |bounds| bounds.width().max(bounds.height()));
+        let (points, indicies) = subdivide(points, &indicies, size / 16.0);
+
+        let mut vao = colored_vao(&points, indicies, &self.color);
+        for vertex in vao.vertices.iter_mut() {
+            vertex.color = gradient.color_at(vertex.position.into()).to_u32();
+        }
+        vao
+    }
+
     pub fn to_node(&self) -> RDNode {
         let mut fill_vao = VAO::new();
         let mut stroke_vao = VAO::new();
 
-        for path in self.continous_paths.iter() {
-            if path.closed {
-                let (points, indicies) = triangulate_stroke(&path.points, &self.stroke);
-    
-                let mut vertices = vec![];
-
-                for point in points.iter() {
-                    vertices.push(RDVertex {
-                        id: 0,
-                        position: point.clone().into(),
-                        texture_position: [0.0, 0.0], 
-                        color: self.stroke.color.clone().to_u32(), 
-                    })
-                }
+        let parents = match self.fill_rule {
+            RDFillRule::Union => vec![None; self.continous_paths.len()],
+            RDFillRule::EvenOdd => even_odd_parents(&self.continous_paths.iter().map(|path| path.points.as_slice()).collect::<Vec<_>>()),
+        };
+
+        for (i, path) in self.continous_paths.iter().enumerate() {
+            let (points, indicies) = self.stroke_path(path);
+            let stroked = !indicies.is_empty();
 
-                let vao = VAO { vertices, indicies };
-                stroke_vao.merge(vao);
+            if stroked {
+                stroke_vao.merge(colored_vao(&points, indicies, &self.stroke.color));
+
+                //the fringe goes around the two rings of a closed stroke, open ones are left without
+                if let (RDAntiAliasing::Fringe(width), true) = (self.anti_aliasing, path.closed && path.points.len() > 2) {
+                    let ring_a: Vec<_> = points.iter().copied().step_by(2).collect();
+                    let ring_b: Vec<_> = points.iter().copied().skip(1).step_by(2).collect();
+
+                    let (outer, inner) = if signed_area(&ring_a).abs() > signed_area(&ring_b).abs() {
+                        (ring_a, ring_b)
+                    } else {
+                        (ring_b, ring_a)
+                    };
+
+                    stroke_vao.merge(fringe_vao(&outer, width, true, &self.stroke.color));
+                    stroke_vao.merge(fringe_vao(&inner, width, false, &self.stroke.color));
+                }
             }
                 
-            let indicies = triangulate(&path.points);
-    
-            let mut vertices = vec![];
-
-            for point in path.points.iter() {
-                vertices.push(RDVertex {
-                    id: 0,
-                    position: point.clone().into(),
-                    texture_position: [0.0, 0.0], 
-                    color: self.color.to_u32(), 
-                })
+            //holes are filled as part of the outline around them
+            if parents[i].is_none() {
+                let holes: Vec<_> = (0..parents.len()).filter(|j| parents[*j] == Some(i)).map(|j| self.continous_paths[j].points.as_slice()).collect();
+                let points = if holes.is_empty() { path.points.clone() } else { bridge_holes(&path.points, &holes) };
+
+                let indicies = triangulate(&points);
+                fill_vao.merge(self.fill_vao(&points, indicies));
             }
 
-            let vao = VAO { vertices, indicies };
-            fill_vao.merge(vao);
-        }
+            //a stroke already covers the fill's edge, so only bare fills need their own fringe
+            //a hole's fringe fades into the hole instead of into its own area
+            if let (RDAntiAliasing::Fringe(width), false) = (self.anti_aliasing, stroked) {
+                let mut fringe = fringe_vao(&path.points, width, parents[i].is_none(), &self.color);
+
+                if let Some(gradient) = &self.gradient {
+                    for vertex in fringe.vertices.iter_mut().step_by(2) {
+                        vertex.color = gradient.color_at(vertex.position.into()).to_u32();
+                    }
+                }
 
-        RDNode {
-            transform: RDTransform::default(),
-            mesh: None,
-            children: vec![
-                RDNode {
-                    mesh: Some(RDMesh {
-                        texture: 0,
-                        vao: stroke_vao,
-                    }),
-                    children: vec![],
-                    transform: RDTransform::default(),
-                },
-                RDNode {
-                    mesh: Some(RDMesh {
-                        texture: 0,
-                        vao: fill_vao,
-                    }),
-                    children: vec![],
-                    transform: RDTransform::default(),
-                },
-            ]
+                fill_vao.merge(fringe);
+            }
         }
+
+        let source = Arc::new(self.clone());
+
+        let mut node = RDNode::new();
+        //the stroke comes second so it is drawn over the inner half of its own edge
+        node.children.push(RDNode::with_mesh(RDMesh {
+            texture: 0,
+            vao: fill_vao,
+            source: Some(RDMeshSource::Fill(source.clone())),
+        }).named("fill"));
+        node.children.push(RDNode::with_mesh(RDMesh {
+            texture: 0,
+            vao: stroke_vao,
+            source: Some(RDMeshSource::Stroke(source)),
+        }).named("stroke"));
+        node
     }
 }
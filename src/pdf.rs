@@ -0,0 +1,209 @@
+use std::{fmt::Write, io, path::Path};
+
+use cgmath::Vector2;
+
+use crate::{color::RDColor, export::{page_frame, write_scene, VectorWriter}, math::RDAffine, path_builder::{ContinousPath, PathSegment, RDFillRule, RDStroke}, scene::RDScene};
+
+//one pixel of the frame becomes one point on the page
+struct PdfPage {
+    width: f32,
+    height: f32,
+    content: String,
+    //alpha values painted with, each gets a graphics state named after it
+    opacities: Vec<u8>,
+}
+
+impl PdfPage {
+    fn path(&mut self, subpaths: &[&ContinousPath]) {
+        for subpath in subpaths {
+            writeln!(self.content, "{} {} m", subpath.start.x, subpath.start.y).unwrap();
+
+            for segment in subpath.segments.iter() {
+                match segment {
+                    PathSegment::Line(point) => writeln!(self.content, "{} {} l", point.x, point.y).unwrap(),
+                    PathSegment::Cubic(a, b, point) => writeln!(self.content, "{} {} {} {} {} {} c", a.x, a.y, b.x, b.y, point.x, point.y).unwrap(),
+                }
+            }
+
+            if subpath.closed {
+                self.content.push_str("h\n");
+            }
+        }
+    }
+
+    //pdf colors have no alpha, it comes from an ExtGState instead
+    fn paint(&mut self, color: &RDColor, operator: &str) {
+        let color = color.to_srgb();
+
+        if color.a < 255 {
+            if !self.opacities.contains(&color.a) {
+                self.opacities.push(color.a);
+            }
+            writeln!(self.content, "/A{} gs", color.a).unwrap();
+        }
+
+        writeln!(self.content, "{} {} {} {operator}", color.r as f32 / 255.0, color.g as f32 / 255.0, color.b as f32 / 255.0).unwrap();
+    }
+}
+
+//each paint is wrapped in q and Q so its color and opacity don't leak into the next one
+impl VectorWriter for PdfPage {
+    fn begin_group(&mut self, transform: &RDAffine) {
+        self.content.push_str("q\n");
+
+        if *transform != RDAffine::identity() {
+            writeln!(self.content, "{} {} {} {} {} {} cm", transform.x.x, transform.x.y, transform.y.x, transform.y.y, transform.w.x, transform.w.y).unwrap();
+        }
+    }
+
+    fn end_group(&mut self) {
+        self.content.push_str("Q\n");
+    }
+
+    //union fills use the nonzero rule, which matches as long as the subpaths don't overlap with opposite windings
+    fn fill(&mut self, subpaths: &[&ContinousPath], color: &RDColor, rule: RDFillRule) {
+        self.content.push_str("q\n");
+        self.paint(color, "rg");
+        self.path(subpaths);
+        self.content.push_str(match rule {
+            RDFillRule::Union => "f\nQ\n",
+            RDFillRule::EvenOdd => "f*\nQ\n",
+        });
+    }
+
+    //mitered joins like the tessellated strokes
+    fn stroke(&mut self, subpaths: &[&ContinousPath], stroke: &RDStroke) {
+        self.content.push_str("q\n");
+        self.paint(&stroke.color, "RG");
+        writeln!(self.content, "{} w\n0 j\n100 M", stroke.weight).unwrap();
+        self.path(subpaths);
+        self.content.push_str("S\nQ\n");
+    }
+}
+
+//vector pdf built page by page from scenes, nothing is rasterized
+#[derive(Default)]
+pub struct RDPdfWriter {
+    pages: Vec<PdfPage>,
+}
+
+impl RDPdfWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //adds what the scene's first camera shows as a new page, framed the same way as RDScene::to_svg
+    pub fn add_page(&mut self, scene: &RDScene) {
+        let (size, view) = page_frame(scene);
+        let (width, height) = (size.x.max(1.0), size.y.max(1.0));
+
+        let mut page = PdfPage { width, height, content: String::new(), opacities: vec![] };
+
+        //the frame has y pointing down, pdf has it pointing up
+        let flip = RDAffine::from_translation(Vector2::new(0.0, height)) * RDAffine::from_scale(Vector2::new(1.0, -1.0));
+        page.begin_group(&(flip * view));
+        write_scene(scene, &mut page);
+        page.end_group();
+
+        self.pages.push(page);
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        //the catalog and page tree come first, then a page and its content stream for every page
+        let mut objects = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                (0..self.pages.len()).map(|i| format!("{} 0 R", 3 + i * 2)).collect::<Vec<_>>().join(" "),
+                self.pages.len(),
+            ),
+        ];
+
+        for (i, page) in self.pages.iter().enumerate() {
+            let states: String = page.opacities.iter()
+                .map(|alpha| format!(" /A{alpha} << /ca {0} /CA {0} >>", *alpha as f32 / 255.0))
+                .collect();
+
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /ExtGState <<{states} >> >> /Contents {} 0 R >>",
+                page.width, page.height, 4 + i * 2,
+            ));
+            objects.push(format!("<< /Length {} >>\nstream\n{}endstream", page.content.len(), page.content));
+        }
+
+        //the binary comment tells transfer tools not to treat the file as text
+        let mut pdf = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+        let mut offsets = vec![];
+
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend_from_slice(format!("{} 0 obj\n{object}\nendobj\n", i + 1).as_bytes());
+        }
+
+        //every cross reference entry is exactly 20 bytes
+        let xref = pdf.len();
+        let mut trailer = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+        for offset in offsets {
+            writeln!(trailer, "{offset:010} 00000 n ").unwrap();
+        }
+        write!(trailer, "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n", objects.len() + 1).unwrap();
+
+        pdf.extend_from_slice(trailer.as_bytes());
+        pdf
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        std::fs::write(path, self.to_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{color::RDColor, path_builder::{RDPath, RDStroke}, scene::RDScene};
+
+    use super::RDPdfWriter;
+
+    #[test]
+    fn test_pdf() {
+        let mut scene = RDScene::new(10);
+        scene.add_root(RDPath::new()
+            .to(0.0, 0.0)
+            .cubic(0.0, 2.0, 4.0, 2.0, 4.0, 0.0)
+            .close()
+            .stroke(RDStroke { weight: 0.5, color: RDColor::new(0, 0, 255, 128) })
+            .fill(RDColor::RED)
+            .to_node());
+
+        let mut writer = RDPdfWriter::new();
+        writer.add_page(&scene);
+        scene.output_gfx(200.0, 400.0);
+        writer.add_page(&scene);
+        assert_eq!(writer.page_count(), 2);
+
+        let pdf = writer.to_bytes();
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.starts_with("%PDF-1.4\n") && text.ends_with("%%EOF\n"));
+        assert!(text.contains("/Count 2") && text.contains("/MediaBox [0 0 400 200]"));
+
+        //the curve stays a curve, closed and painted in both colors
+        assert!(text.contains("0 0 m\n0 2 4 2 4 0 c\nh\nf\n"));
+        assert!(text.contains("1 0 0 rg\n"));
+        assert!(text.contains("/A128 gs\n0 0 1 RG\n0.5 w\n"));
+        assert!(text.contains("/A128 << /ca 0.5019608 /CA 0.5019608 >>"));
+
+        //the cross reference table points at every object
+        let xref: usize = text.rsplit("startxref\n").next().unwrap().lines().next().unwrap().parse().unwrap();
+        let table = std::str::from_utf8(&pdf[xref..]).unwrap();
+        let entries: Vec<_> = table.lines().skip(3).take_while(|line| line.ends_with(" n ")).collect();
+        assert_eq!(entries.len(), 6);
+
+        for (i, entry) in entries.iter().enumerate() {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(format!("{} 0 obj\n", i + 1).as_bytes()));
+        }
+    }
+}
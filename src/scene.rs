@@ -1,17 +1,44 @@
-use cgmath::{Matrix4, Rad, Vector2, Vector3, Zero};
-use crate::{RDStorage, RDVertex};
+use std::{collections::HashSet, ops::{Deref, Range}, sync::Arc, time::Duration};
 
+use cgmath::{InnerSpace, Vector2, Zero};
+use crate::{allocator::RangeAllocator, animation::{Animation, RDAnimationId, RDTween}, arena::{Arena, ArenaId}, atlas::{RDAtlasUpdate, RDGlyphAtlas}, camera::RDCamera, color::RDColor, math::{triangle_contains, RDAffine, RDRect}, path_builder::RDPath, spatial::RDSpatialIndex, RDStorage, RDVertex};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct RDTransform {
     pub position: Vector2<f32>,
     pub rotation: f32,
     pub scale: Vector2<f32>,
+    //in radians, see RDAffine::from_skew
+    pub skew: Vector2<f32>,
+    //local point that rotation, skew and scale happen around, it ends up at position
+    pub pivot: Vector2<f32>,
 }
 
 impl RDTransform {
-    pub fn to_matrix(&self) -> Matrix4<f32> {
-        Matrix4::from_translation(Vector3::new(self.position.x, self.position.y, 0.0)) *
-        Matrix4::from_angle_z(Rad(self.rotation)) * 
-        Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, 0.0)
+    //splits any affine matrix into a rotation, an x skew and a scale, mirroring ends up as a negative y scale
+    pub fn from_matrix(matrix: &RDAffine) -> Self {
+        let scale_x = matrix.x.magnitude();
+        let rotation = matrix.x.y.atan2(matrix.x.x);
+
+        //what is left of the y axis once the rotation is undone
+        let y = RDAffine::from_rotation(-rotation).transform_vector(matrix.y);
+        let skew_x = if y.y == 0.0 { 0.0 } else { (y.x / y.y).atan() };
+
+        Self {
+            position: matrix.w,
+            rotation,
+            scale: Vector2::new(scale_x, y.y),
+            skew: Vector2::new(skew_x, 0.0),
+            pivot: Vector2::zero(),
+        }
+    }
+
+    pub fn to_matrix(&self) -> RDAffine {
+        RDAffine::from_translation(self.position) *
+        RDAffine::from_rotation(self.rotation) *
+        RDAffine::from_skew(self.skew) *
+        RDAffine::from_scale(self.scale) *
+        RDAffine::from_translation(-self.pivot)
     }
 }
 
@@ -20,7 +47,9 @@ impl Default for RDTransform {
         Self {
             position: Vector2::zero(),
             rotation: 0.0,
-            scale: Vector2::new(1.0, 1.0)
+            scale: Vector2::new(1.0, 1.0),
+            skew: Vector2::zero(),
+            pivot: Vector2::zero(),
         }
     }
 }
@@ -38,6 +67,10 @@ impl VAO {
             indicies: vec![],
         }
     }
+    pub fn bounds(&self) -> Option<RDRect> {
+        RDRect::from_points(self.vertices.iter().map(|vertex| Vector2::from(vertex.position)))
+    }
+
     pub(crate) fn merge(&mut self, other: VAO) {
         let VAO { mut vertices, indicies, .. } = other;
 
@@ -49,13 +82,33 @@ impl VAO {
     }
 }
 
+//the vector data a mesh was tessellated from, exporters use it instead of the triangles
+#[derive(Clone)]
+pub enum RDMeshSource {
+    Fill(Arc<RDPath>),
+    Stroke(Arc<RDPath>),
+}
+
 pub struct RDMesh {
-    //placeholder -- change to whatever texture atlas abstraction becomes
+    //0 for plain vertex colors, atlas::GLYPH_ATLAS_TEXTURE to shade with the scene's glyph atlas
     pub texture: u32,
     pub vao: VAO,
+    //None for meshes built by hand, those are exported as triangles
+    pub source: Option<RDMeshSource>,
+}
+
+//drawn back to front in this order, regardless of where nodes sit in the tree
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RDLayer {
+    Background,
+    #[default]
+    World,
+    UI,
+    Overlay,
 }
 
 impl RDMesh {
+    #[allow(dead_code)]
     fn combine(&mut self, mut other: RDMesh) {
         debug_assert!(self.texture == other.texture);
 
@@ -70,88 +123,1480 @@ impl RDMesh {
 }
 
 pub struct RDNode {
+    pub name: Option<Arc<str>>,
     pub children: Vec<RDNode>,
     pub transform: RDTransform,
-    pub mesh: Option<RDMesh>
+    pub mesh: Option<RDMesh>,
+    //relative to the parent's z, nodes with equal z keep their tree order
+    pub z_index: i32,
+    //None inherits the parent's layer
+    pub layer: Option<RDLayer>,
+    //hidden nodes keep their gpu allocation, their subtree is just left out of the draws
+    pub visible: bool,
+    //multiplies the colors of the node and its subtree, opacity scales their alpha on top
+    pub tint: RDColor,
+    pub opacity: f32,
+    pub(crate) geometry_dirty: bool,
+    pub(crate) transform_dirty: bool,
+    pub(crate) allocation: Option<GfxAllocation>,
+}
+
+impl Default for RDNode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RDNode {
+    pub fn new() -> Self {
+        Self {
+            name: None,
+            children: vec![],
+            transform: RDTransform::default(),
+            mesh: None,
+            z_index: 0,
+            layer: None,
+            visible: true,
+            tint: RDColor::WHITE,
+            opacity: 1.0,
+            geometry_dirty: true,
+            transform_dirty: true,
+            allocation: None,
+        }
+    }
+
+    pub fn with_mesh(mesh: RDMesh) -> Self {
+        Self {
+            mesh: Some(mesh),
+            ..Self::new()
+        }
+    }
+
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn with_z_index(mut self, z_index: i32) -> Self {
+        self.z_index = z_index;
+        self
+    }
+
+    pub fn on_layer(mut self, layer: RDLayer) -> Self {
+        self.layer = Some(layer);
+        self
+    }
+
+    pub fn hidden(mut self) -> Self {
+        self.visible = false;
+        self
+    }
+
+    pub fn with_tint(mut self, tint: RDColor) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    pub fn with_opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+
+    pub fn set_transform(&mut self, transform: RDTransform) {
+        self.transform = transform;
+        self.transform_dirty = true;
+    }
+
+    pub fn transform_mut(&mut self) -> &mut RDTransform {
+        self.transform_dirty = true;
+        &mut self.transform
+    }
+
+    //tint and opacity travel with the transform, changing them only re-uploads the object data
+    pub fn set_tint(&mut self, tint: RDColor) {
+        self.tint = tint;
+        self.transform_dirty = true;
+    }
+
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.opacity = opacity;
+        self.transform_dirty = true;
+    }
+
+    pub fn set_mesh(&mut self, mesh: Option<RDMesh>) {
+        self.mesh = mesh;
+        self.geometry_dirty = true;
+    }
+}
+
+//where a node's geometry and per object data live inside the gpu buffers
+#[derive(Debug, Clone)]
+pub(crate) struct GfxAllocation {
+    object: u32,
+    vertices: Range<u32>,
+    indicies: Range<u32>,
+    //local bounds of the uploaded mesh, kept so moving the node doesn't rescan its vertices
+    bounds: Option<RDRect>,
+}
+
+#[derive(Default)]
+struct GfxAllocator {
+    objects: RangeAllocator,
+    vertices: RangeAllocator,
+    indicies: RangeAllocator,
+}
+
+impl GfxAllocator {
+    fn allocate(&mut self, vao: &VAO) -> GfxAllocation {
+        GfxAllocation {
+            object: self.objects.allocate(1).start,
+            vertices: self.vertices.allocate(vao.vertices.len() as u32),
+            indicies: self.indicies.allocate(vao.indicies.len() as u32),
+            bounds: vao.bounds(),
+        }
+    }
+
+    fn free(&mut self, allocation: GfxAllocation) {
+        self.objects.free(allocation.object..allocation.object + 1);
+        self.vertices.free(allocation.vertices);
+        self.indicies.free(allocation.indicies);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RDDrawCall {
+    pub node: RDNodeId,
+    pub indicies: Range<u32>,
+    pub base_vertex: i32,
+    pub layer: RDLayer,
+    pub z_index: i32,
+}
+
+#[derive(Debug, Clone)]
+pub struct RDView {
+    //world to clip space, the per object transforms are in world space
+    pub transform: [[f32; 2]; 3],
+    //in pixels with the origin at the top left of the surface
+    pub viewport: RDRect,
+    pub draws: Vec<RDDrawCall>,
+}
+
+//the changed ranges since the last frame, offsets are in elements not bytes
+#[derive(Debug, Default)]
+pub struct RDGfxUpdate {
+    pub vertices: Vec<(u32, Vec<RDVertex>)>,
+    pub indicies: Vec<(u32, Vec<u32>)>,
+    pub storage: Vec<(u32, Vec<RDStorage>)>,
+    pub atlas: Option<RDAtlasUpdate>,
+    //one per camera, drawn in order
+    pub views: Vec<RDView>,
+    //draws left out because the spatial index put them outside a camera's view
+    pub culled_draws: u32,
+    //how many elements each buffer must be able to hold
+    pub vertex_capacity: u32,
+    pub index_capacity: u32,
+    pub object_capacity: u32,
+}
+
+impl RDGfxUpdate {
+    fn push_storage(&mut self, object: u32, storage: RDStorage) {
+        if let Some((offset, run)) = self.storage.last_mut() {
+            if *offset + run.len() as u32 == object {
+                run.push(storage);
+                return;
+            }
+        }
+
+        self.storage.push((object, vec![storage]));
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RDNodeId(pub(crate) ArenaId);
+
+//depth first, parents before their children, in draw order
+pub struct RDDescendants<'a> {
+    scene: &'a RDScene,
+    stack: Vec<RDNodeId>,
+}
+
+impl Iterator for RDDescendants<'_> {
+    type Item = RDNodeId;
+
+    fn next(&mut self) -> Option<RDNodeId> {
+        let id = self.stack.pop()?;
+        self.stack.extend(self.scene.children(id).iter().rev());
+        Some(id)
+    }
+}
+
+//`*` matches any run of characters and `?` exactly one
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            n = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+//a node inside a scene, its `children` are always empty since the scene links them through the arena
+//reading goes through deref, every setter marks just what it changes as dirty
+pub struct RDNodeMut<'a> {
+    scene: &'a mut RDScene,
+    id: RDNodeId,
+    //the transform or mesh changed, the spatial index catches up once the handle is dropped
+    moved: bool,
+}
+
+impl Deref for RDNodeMut<'_> {
+    type Target = RDNode;
+
+    fn deref(&self) -> &RDNode {
+        &self.scene.nodes[self.id.0].node
+    }
+}
+
+impl Drop for RDNodeMut<'_> {
+    fn drop(&mut self) {
+        if self.moved {
+            self.scene.refresh_spatial(self.id);
+        }
+    }
+}
+
+impl RDNodeMut<'_> {
+    fn node(&mut self) -> &mut RDNode {
+        &mut self.scene.nodes[self.id.0].node
+    }
+
+    pub fn set_name(&mut self, name: Option<&str>) {
+        self.node().name = name.map(Into::into);
+    }
+
+    pub fn set_transform(&mut self, transform: RDTransform) {
+        self.moved = true;
+        self.node().set_transform(transform);
+    }
+
+    pub fn transform_mut(&mut self) -> &mut RDTransform {
+        self.moved = true;
+        self.node().transform_mut()
+    }
+
+    pub fn set_mesh(&mut self, mesh: Option<RDMesh>) {
+        self.moved = true;
+        self.node().set_mesh(mesh);
+    }
+
+    pub fn mesh_mut(&mut self) -> Option<&mut RDMesh> {
+        self.moved = true;
+
+        let node = self.node();
+        node.geometry_dirty = true;
+        node.mesh.as_mut()
+    }
+
+    //draw order and visibility are worked out every frame, nothing needs uploading
+    pub fn set_z_index(&mut self, z_index: i32) {
+        self.node().z_index = z_index;
+    }
+
+    pub fn set_layer(&mut self, layer: Option<RDLayer>) {
+        self.node().layer = layer;
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.node().visible = visible;
+    }
+
+    pub fn set_tint(&mut self, tint: RDColor) {
+        self.node().set_tint(tint);
+    }
+
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.node().set_opacity(opacity);
+    }
+}
+
+//a node's tint on top of its parent's, opacity folds into the alpha
+pub(crate) fn inherit_tint(parent: &RDColor, node: &RDNode) -> RDColor {
+    let mut tint = parent.multiply(&node.tint);
+    tint.a = (tint.a as f32 * node.opacity.clamp(0.0, 1.0)).round() as u8;
+    tint
+}
+
+//a node as stored by the scene, its children are linked through the arena rather than `node.children`
+struct SceneEntry {
+    node: RDNode,
+    parent: Option<RDNodeId>,
+    children: Vec<RDNodeId>,
 }
 
 pub struct RDScene {
     ppu: u32,
-    root: RDNode,
-    pub(crate) index_count: u32,
-    pub(crate) vertex_cache: bool,
+    root: RDNodeId,
+    nodes: Arena<SceneEntry>,
+    allocator: GfxAllocator,
+    viewport: (f32, f32),
+    spatial: Option<RDSpatialIndex>,
+    cameras: Vec<RDCamera>,
+    glyph_atlas: RDGlyphAtlas,
+    animations: Vec<(RDNodeId, Animation)>,
+    next_animation: u64,
 }
 
 impl RDScene {
     pub fn new(ppu: u32) -> Self {
+        let mut nodes = Arena::default();
+        let root = RDNodeId(nodes.insert(SceneEntry {
+            node: RDNode::new(),
+            parent: None,
+            children: vec![],
+        }));
+
         Self {
             ppu,
-            index_count: 0,
-            vertex_cache: true,
-            root: RDNode {
-                mesh: None,
-                children: vec![],
-                transform: RDTransform {
-                    position: Vector2::zero(),
-                    rotation: 0.0,
-                    scale: Vector2::new(1.0, 1.0),
+            root,
+            nodes,
+            allocator: GfxAllocator::default(),
+            viewport: (0.0, 0.0),
+            spatial: None,
+            cameras: vec![RDCamera::default()],
+            glyph_atlas: RDGlyphAtlas::new(),
+            animations: vec![],
+            next_animation: 0,
+        }
+    } 
+
+    pub fn root(&self) -> RDNodeId {
+        self.root
+    }
+
+    pub fn add_root(&mut self, node: RDNode) -> RDNodeId {
+        self.add(self.root, node)
+    }
+    pub fn add(&mut self, parent: RDNodeId, node: RDNode) -> RDNodeId {
+        assert!(self.nodes.contains(parent.0), "parent is not in the scene");
+
+        let id = self.insert(parent, node);
+        self.refresh_spatial(id);
+        id
+    }
+
+    fn insert(&mut self, parent: RDNodeId, mut node: RDNode) -> RDNodeId {
+        let children = std::mem::take(&mut node.children);
+
+        let id = RDNodeId(self.nodes.insert(SceneEntry {
+            node,
+            parent: Some(parent),
+            children: vec![],
+        }));
+        self.nodes[parent.0].children.push(id);
+
+        for child in children {
+            self.insert(id, child);
+        }
+
+        id
+    }
+
+    //removes the node and its subtree, handing it back as a tree that can be added again
+    pub fn remove(&mut self, id: RDNodeId) -> Option<RDNode> {
+        if id == self.root {
+            return None;
+        }
+
+        let parent = self.nodes.get(id.0)?.parent?;
+        self.nodes[parent.0].children.retain(|child| *child != id);
+
+        Some(self.detach(id))
+    }
+
+    fn detach(&mut self, id: RDNodeId) -> RDNode {
+        let SceneEntry { mut node, children, .. } = self.nodes.remove(id.0).unwrap();
+
+        if let Some(allocation) = node.allocation.take() {
+            self.allocator.free(allocation);
+        }
+        if let Some(spatial) = &mut self.spatial {
+            spatial.remove(id);
+        }
+        node.geometry_dirty = true;
+        node.transform_dirty = true;
+
+        node.children = children.into_iter().map(|child| self.detach(child)).collect();
+        node
+    }
+
+    //moves the node under a new parent, it is drawn after the parent's existing children
+    //returns false if either node is missing or the parent lives inside the node's own subtree
+    pub fn reparent(&mut self, id: RDNodeId, parent: RDNodeId) -> bool {
+        if id == self.root || !self.nodes.contains(id.0) || !self.nodes.contains(parent.0) {
+            return false;
+        }
+
+        let mut ancestor = Some(parent);
+        while let Some(current) = ancestor {
+            if current == id {
+                return false;
+            }
+            ancestor = self.nodes[current.0].parent;
+        }
+
+        if let Some(old_parent) = self.nodes[id.0].parent {
+            self.nodes[old_parent.0].children.retain(|child| *child != id);
+        }
+        self.nodes[parent.0].children.push(id);
+
+        let entry = &mut self.nodes[id.0];
+        entry.parent = Some(parent);
+        entry.node.transform_dirty = true;
+
+        self.refresh_spatial(id);
+        true
+    }
+
+    pub fn get(&self, id: RDNodeId) -> Option<&RDNode> {
+        self.nodes.get(id.0).map(|entry| &entry.node)
+    }
+
+    //only what gets changed through the handle is uploaded again, children go through add, remove and reparent
+    pub fn get_mut(&mut self, id: RDNodeId) -> Option<RDNodeMut<'_>> {
+        if !self.nodes.contains(id.0) {
+            return None;
+        }
+
+        Some(RDNodeMut { scene: self, id, moved: false })
+    }
+
+    pub fn set_transform(&mut self, id: RDNodeId, transform: RDTransform) -> bool {
+        let Some(entry) = self.nodes.get_mut(id.0) else { return false };
+
+        entry.node.set_transform(transform);
+        self.refresh_spatial(id);
+        true
+    }
+
+    pub fn set_tint(&mut self, id: RDNodeId, tint: RDColor) -> bool {
+        let Some(entry) = self.nodes.get_mut(id.0) else { return false };
+
+        entry.node.set_tint(tint);
+        true
+    }
+
+    pub fn set_opacity(&mut self, id: RDNodeId, opacity: f32) -> bool {
+        let Some(entry) = self.nodes.get_mut(id.0) else { return false };
+
+        entry.node.set_opacity(opacity);
+        true
+    }
+
+    //toggling visibility only changes the draw list, nothing is re-uploaded
+    pub fn set_visible(&mut self, id: RDNodeId, visible: bool) -> bool {
+        let Some(entry) = self.nodes.get_mut(id.0) else { return false };
+
+        entry.node.visible = visible;
+        true
+    }
+
+    //false if the node or any of its ancestors is hidden
+    pub fn is_visible(&self, id: RDNodeId) -> bool {
+        let mut current = Some(id);
+
+        while let Some(id) = current {
+            let Some(entry) = self.nodes.get(id.0) else { return false };
+
+            if !entry.node.visible {
+                return false;
+            }
+            current = entry.parent;
+        }
+
+        true
+    }
+
+    pub fn parent(&self, id: RDNodeId) -> Option<RDNodeId> {
+        self.nodes.get(id.0)?.parent
+    }
+
+    pub fn children(&self, id: RDNodeId) -> &[RDNodeId] {
+        self.nodes.get(id.0).map(|entry| entry.children.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn contains(&self, id: RDNodeId) -> bool {
+        self.nodes.contains(id.0)
+    }
+
+    //looks up a slash separated path of node names starting below the root, eg. "hud/healthbar/fill"
+    pub fn find(&self, path: &str) -> Option<RDNodeId> {
+        self.find_from(self.root, path)
+    }
+
+    pub fn find_from(&self, start: RDNodeId, path: &str) -> Option<RDNodeId> {
+        let mut current = start;
+
+        for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+            current = *self.children(current).iter().find(|child| {
+                self.nodes[child.0].node.name.as_deref() == Some(segment)
+            })?;
+        }
+
+        self.nodes.contains(current.0).then_some(current)
+    }
+
+    //every node below `id`, not including `id` itself
+    pub fn descendants(&self, id: RDNodeId) -> RDDescendants<'_> {
+        RDDescendants {
+            scene: self,
+            stack: self.children(id).iter().rev().copied().collect(),
+        }
+    }
+
+    pub fn query<F: Fn(&RDNode) -> bool>(&self, predicate: F) -> Vec<RDNodeId> {
+        self.descendants(self.root)
+            .filter(|id| predicate(&self.nodes[id.0].node))
+            .collect()
+    }
+
+    pub fn query_name(&self, pattern: &str) -> Vec<RDNodeId> {
+        self.query(|node| node.name.as_deref().is_some_and(|name| glob_match(pattern, name)))
+    }
+
+    //the node's transform composed with all of its ancestors', without the screen mapping
+    pub fn world_transform(&self, id: RDNodeId) -> RDAffine {
+        let mut matrix = RDAffine::identity();
+        let mut current = Some(id);
+
+        while let Some(id) = current {
+            let Some(entry) = self.nodes.get(id.0) else { break };
+
+            matrix = entry.node.transform.to_matrix() * matrix;
+            current = entry.parent;
+        }
+
+        matrix
+    }
+
+    //bounds of the subtree's meshes in the node's own space, before its transform is applied
+    pub fn local_bounds(&self, id: RDNodeId) -> Option<RDRect> {
+        let entry = self.nodes.get(id.0)?;
+        let mut bounds = entry.node.mesh.as_ref().and_then(|mesh| mesh.vao.bounds());
+
+        for child in entry.children.iter() {
+            let child_transform = self.nodes[child.0].node.transform.to_matrix();
+
+            if let Some(child_bounds) = self.local_bounds(*child) {
+                let child_bounds = child_bounds.transform(&child_transform);
+                bounds = Some(bounds.map_or(child_bounds, |bounds| bounds.union(&child_bounds)));
+            }
+        }
+
+        bounds
+    }
+
+    pub fn world_bounds(&self, id: RDNodeId) -> Option<RDRect> {
+        let parent_transform = self.parent(id).map_or(RDAffine::identity(), |parent| self.world_transform(parent));
+        let node_transform = self.get(id)?.transform.to_matrix();
+
+        self.local_bounds(id).map(|bounds| bounds.transform(&(parent_transform * node_transform)))
+    }
+
+    //every visible mesh node with its world transform, back to front as the renderer draws them
+    pub(crate) fn draw_order(&self) -> Vec<(RDNodeId, RDAffine)> {
+        let mut nodes = vec![];
+        self.recurse_draw_order(self.root, RDAffine::identity(), (RDLayer::default(), 0), &mut nodes);
+
+        nodes.sort_by_key(|(layer, z_index, ..)| (*layer, *z_index));
+        nodes.into_iter().map(|(_, _, id, matrix)| (id, matrix)).collect()
+    }
+
+    fn recurse_draw_order(&self, id: RDNodeId, parent_matrix: RDAffine, (layer, z_index): (RDLayer, i32), nodes: &mut Vec<(RDLayer, i32, RDNodeId, RDAffine)>) {
+        let entry = &self.nodes[id.0];
+
+        if !entry.node.visible {
+            return;
+        }
+
+        let layer = entry.node.layer.unwrap_or(layer);
+        let z_index = z_index + entry.node.z_index;
+        let matrix = parent_matrix * entry.node.transform.to_matrix();
+
+        if entry.node.mesh.is_some() {
+            nodes.push((layer, z_index, id, matrix));
+        }
+
+        for child in entry.children.iter() {
+            self.recurse_draw_order(*child, matrix, (layer, z_index), nodes);
+        }
+    }
+
+    //the first camera, created with the scene and covering the whole surface
+    pub fn camera(&self) -> &RDCamera {
+        &self.cameras[0]
+    }
+
+    pub fn camera_mut(&mut self) -> &mut RDCamera {
+        &mut self.cameras[0]
+    }
+
+    pub fn cameras(&self) -> &[RDCamera] {
+        &self.cameras
+    }
+
+    pub fn cameras_mut(&mut self) -> &mut [RDCamera] {
+        &mut self.cameras
+    }
+
+    //later cameras draw over earlier ones where their viewports overlap
+    pub fn add_camera(&mut self, camera: RDCamera) -> usize {
+        self.cameras.push(camera);
+        self.cameras.len() - 1
+    }
+
+    pub fn remove_camera(&mut self, index: usize) -> RDCamera {
+        self.cameras.remove(index)
+    }
+
+    //shared by all text drawn with RDText::to_sdf_node, new glyphs go up with the next frame
+    pub fn glyph_atlas(&self) -> &RDGlyphAtlas {
+        &self.glyph_atlas
+    }
+
+    pub fn glyph_atlas_mut(&mut self) -> &mut RDGlyphAtlas {
+        &mut self.glyph_atlas
+    }
+
+    //tweens run in the order they were started, a later one wins when both animate the same property
+    pub fn animate(&mut self, id: RDNodeId, tween: RDTween) -> RDAnimationId {
+        assert!(self.nodes.contains(id.0), "node is not in the scene");
+
+        let animation = RDAnimationId(self.next_animation);
+        self.next_animation += 1;
+
+        self.animations.push((id, Animation { id: animation, tween, elapsed: Duration::ZERO }));
+        animation
+    }
+
+    //leaves the property wherever the tween had taken it
+    pub fn stop_animation(&mut self, animation: RDAnimationId) -> bool {
+        let count = self.animations.len();
+        self.animations.retain(|(_, running)| running.id != animation);
+        self.animations.len() != count
+    }
+
+    pub fn stop_animations(&mut self, id: RDNodeId) {
+        self.animations.retain(|(node, _)| *node != id);
+    }
+
+    pub fn is_animating(&self, animation: RDAnimationId) -> bool {
+        self.animations.iter().any(|(_, running)| running.id == animation)
+    }
+
+    //Raindeer::run calls this with the time between frames, call it yourself when rendering headless
+    //finished tweens leave their end value behind, the ones of removed nodes are dropped
+    pub fn advance_animations(&mut self, elapsed: Duration) {
+        //finished tweens still moved their node this time
+        let animated: HashSet<RDNodeId> = self.animations.iter().map(|(id, _)| *id).collect();
+        let nodes = &mut self.nodes;
+
+        self.animations.retain_mut(|(id, animation)| {
+            let Some(entry) = nodes.get_mut(id.0) else { return false };
+            !animation.advance(&mut entry.node, elapsed)
+        });
+
+        if self.spatial.is_some() {
+            for id in animated {
+                if self.nodes.contains(id.0) {
+                    self.refresh_spatial(id);
                 }
             }
         }
-    } 
+    }
+
+    pub fn ppu(&self) -> u32 {
+        self.ppu
+    }
+
+    //size of the last rendered surface in pixels
+    pub(crate) fn surface_size(&self) -> Option<Vector2<f32>> {
+        let (height, width) = self.viewport;
+        (height > 0.0 && width > 0.0).then_some(Vector2::new(width, height))
+    }
+
+    //the topmost camera whose viewport contains the pixel
+    pub fn camera_at(&self, screen_point: Vector2<f32>) -> Option<usize> {
+        let surface_size = self.surface_size()?;
+
+        self.cameras.iter().rposition(|camera| camera.pixel_viewport(surface_size).contains(screen_point))
+    }
+
+    //converts a pixel position (origin top left) on the last rendered surface into world space,
+    //through whichever camera is drawn at that pixel
+    pub fn screen_to_world(&self, screen_point: Vector2<f32>) -> Option<Vector2<f32>> {
+        let camera = &self.cameras[self.camera_at(screen_point)?];
+        camera.screen_to_world(screen_point, self.ppu as f32, self.surface_size()?)
+    }
 
-    pub fn add_root(&mut self, node: RDNode) {
-        self.root.children.push(node);
-        self.vertex_cache = false;
+    //where the point lands on the surface through the first camera
+    pub fn world_to_screen(&self, world_point: Vector2<f32>) -> Option<Vector2<f32>> {
+        Some(self.cameras.first()?.world_to_screen(world_point, self.ppu as f32, self.surface_size()?))
     }
-    pub fn add(&mut self, parent: &mut RDNode, node: RDNode) {
-        parent.children.push(node);
-        self.vertex_cache = false;
+
+    //the topmost visible mesh node under the point, this is the mesh itself (eg. a path's "fill"),
+    //use parent to get to the node that was added
+    pub fn pick(&self, screen_point: Vector2<f32>) -> Option<RDNodeId> {
+        self.pick_world(self.screen_to_world(screen_point)?)
+    }
+
+    pub fn pick_all(&self, screen_point: Vector2<f32>) -> Vec<RDNodeId> {
+        self.screen_to_world(screen_point).map_or(vec![], |point| self.pick_all_world(point))
+    }
+
+    pub fn pick_world(&self, point: Vector2<f32>) -> Option<RDNodeId> {
+        self.pick_candidates(point).into_iter().rev().find(|(id, matrix)| self.hit_test(*id, matrix, point)).map(|(id, _)| id)
+    }
+
+    //topmost first
+    pub fn pick_all_world(&self, point: Vector2<f32>) -> Vec<RDNodeId> {
+        self.pick_candidates(point).into_iter().rev().filter(|(id, matrix)| self.hit_test(*id, matrix, point)).map(|(id, _)| id).collect()
+    }
+
+    //back to front, narrowed down by the spatial index when there is one
+    fn pick_candidates(&self, point: Vector2<f32>) -> Vec<(RDNodeId, RDAffine)> {
+        let Some(spatial) = &self.spatial else { return self.draw_order() };
+
+        let mut candidates: Vec<_> = spatial.query_point(point).into_iter()
+            .filter(|id| self.is_visible(*id))
+            .map(|id| (self.draw_key(id), id))
+            .collect();
+        candidates.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        candidates.into_iter().map(|(_, id)| (id, self.world_transform(id))).collect()
+    }
+
+    //sorts the same as the draw list, the path of child positions from the root stands in for tree order
+    fn draw_key(&self, id: RDNodeId) -> (RDLayer, i32, Vec<usize>) {
+        let mut layer = None;
+        let mut z_index = 0;
+        let mut path = vec![];
+        let mut current = id;
+
+        loop {
+            let entry = &self.nodes[current.0];
+
+            layer = layer.or(entry.node.layer);
+            z_index += entry.node.z_index;
+
+            let Some(parent) = entry.parent else { break };
+
+            path.push(self.children(parent).iter().position(|child| *child == current).unwrap());
+            current = parent;
+        }
+        path.reverse();
+
+        (layer.unwrap_or_default(), z_index, path)
+    }
+
+    //indexes the world bounds of every mesh node, kept up to date as nodes move from then on
+    pub fn enable_spatial_index(&mut self, cell_size: f32) {
+        let mut spatial = RDSpatialIndex::new(cell_size);
+
+        for id in self.descendants(self.root) {
+            let bounds = self.nodes[id.0].node.mesh.as_ref().and_then(|mesh| mesh.vao.bounds());
+
+            if let Some(bounds) = bounds {
+                spatial.insert(id, bounds.transform(&self.world_transform(id)));
+            }
+        }
+
+        self.spatial = Some(spatial);
+    }
+
+    //puts the subtree's world bounds back into the spatial index after it moved, changed shape or was added,
+    //so picking is right before the next output_gfx
+    fn refresh_spatial(&mut self, id: RDNodeId) {
+        let Some(mut spatial) = self.spatial.take() else { return };
+
+        let parent_matrix = self.nodes[id.0].parent.map_or(RDAffine::identity(), |parent| self.world_transform(parent));
+        self.recurse_refresh_spatial(id, parent_matrix, &mut spatial);
+
+        self.spatial = Some(spatial);
     }
 
-    fn recurse_output_gfx_vao(node: &RDNode, vao: &mut VAO, counter: &mut u32) {
-        if let Some(mesh) = &node.mesh {
+    fn recurse_refresh_spatial(&self, id: RDNodeId, parent_matrix: RDAffine, spatial: &mut RDSpatialIndex) {
+        let entry = &self.nodes[id.0];
+        let matrix = parent_matrix * entry.node.transform.to_matrix();
+
+        match entry.node.mesh.as_ref().and_then(|mesh| mesh.vao.bounds()) {
+            Some(bounds) => spatial.insert(id, bounds.transform(&matrix)),
+            None => spatial.remove(id),
+        }
+
+        for child in entry.children.iter() {
+            self.recurse_refresh_spatial(*child, matrix, spatial);
+        }
+    }
+
+    pub fn disable_spatial_index(&mut self) {
+        self.spatial = None;
+    }
+
+    pub fn spatial_index(&self) -> Option<&RDSpatialIndex> {
+        self.spatial.as_ref()
+    }
+
+    //the part of the world the first camera showed in the last rendered frame
+    pub fn view_bounds(&self) -> Option<RDRect> {
+        self.cameras.first()?.view_bounds(self.ppu as f32, self.surface_size()?)
+    }
+
+    fn hit_test(&self, id: RDNodeId, world_matrix: &RDAffine, point: Vector2<f32>) -> bool {
+        let Some(mesh) = &self.nodes[id.0].node.mesh else { return false };
+        let Some(inverse) = world_matrix.inverse() else { return false };
+        let local = inverse.transform_point(point);
+
+        if !mesh.vao.bounds().is_some_and(|bounds| bounds.contains(local)) {
+            return false;
+        }
+
+        let position = |index: u32| Vector2::from(mesh.vao.vertices[index as usize].position);
+
+        mesh.vao.indicies.chunks_exact(3).any(|triangle| {
+            triangle_contains(position(triangle[0]), position(triangle[1]), position(triangle[2]), local)
+        })
+    }
+
+    //number of nodes in the scene, not counting the root
+    pub fn len(&self) -> usize {
+        self.nodes.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn recurse_output_gfx_vao(&self, id: RDNodeId, (layer, z_index): (RDLayer, i32), meshes: &mut Vec<(RDLayer, i32, VAO)>, counter: &mut u32) {
+        let entry = &self.nodes[id.0];
+
+        if !entry.node.visible {
+            return;
+        }
+
+        let layer = entry.node.layer.unwrap_or(layer);
+        let z_index = z_index + entry.node.z_index;
+
+        if let Some(mesh) = &entry.node.mesh {
             let mut new_vao = mesh.vao.clone();
             for vertex in new_vao.vertices.iter_mut() {
                 vertex.id = *counter;
             }
 
-            vao.merge(new_vao);
+            meshes.push((layer, z_index, new_vao));
             *counter += 1;
         }
 
-        for child in node.children.iter() {
-            RDScene::recurse_output_gfx_vao(child, vao, counter);
+        for child in entry.children.iter() {
+            self.recurse_output_gfx_vao(*child, (layer, z_index), meshes, counter);
         }
     }
+    //ids follow tree order to line up with output_gfx_storage, the indicies are in draw order
     pub fn output_gfx_vao(&self) -> VAO {
-        let mut vao = VAO::new();
+        let mut meshes = vec![];
         let mut counter: u32 = 0;
-        RDScene::recurse_output_gfx_vao(&self.root, &mut vao, &mut counter);
+        self.recurse_output_gfx_vao(self.root, (RDLayer::default(), 0), &mut meshes, &mut counter);
+
+        meshes.sort_by_key(|(layer, z_index, _)| (*layer, *z_index));
+
+        let mut vao = VAO::new();
+        for (_, _, mesh) in meshes {
+            vao.merge(mesh);
+        }
         vao
     }
 
-    fn recurse_output_gfx_storage(parent_matrix: Matrix4<f32>, node: &RDNode, buffer: &mut Vec<RDStorage>) {
-        let matrix = parent_matrix * node.transform.to_matrix();
+    fn recurse_output_gfx_storage(&self, parent_matrix: RDAffine, parent_tint: &RDColor, id: RDNodeId, buffer: &mut Vec<RDStorage>) {
+        let entry = &self.nodes[id.0];
+
+        if !entry.node.visible {
+            return;
+        }
 
-        if let Some(mesh) = &node.mesh {
+        let matrix = parent_matrix * entry.node.transform.to_matrix();
+        let tint = inherit_tint(parent_tint, &entry.node);
+
+        if let Some(mesh) = &entry.node.mesh {
             let gfx_storage = RDStorage {
                 texture: mesh.texture,
                 transform: matrix.into(),
+                tint: tint.to_u32(),
             };
 
             buffer.push(gfx_storage);
         }
 
-        for child in node.children.iter() {
-            RDScene::recurse_output_gfx_storage(matrix, child, buffer);
+        for child in entry.children.iter() {
+            self.recurse_output_gfx_storage(matrix, &tint, *child, buffer);
         }
     }
-    pub fn output_gfx_storage(&self, height: f32, width: f32) -> Vec<RDStorage> {
-        let aspect = height / width;
-        let onscreen_units = self.ppu as f32 / height;
+    //world space transforms, a camera's view matrix maps them onto the screen
+    pub fn output_gfx_storage(&self) -> Vec<RDStorage> {
         let mut output = vec![];
-        RDScene::recurse_output_gfx_storage(Matrix4::from_nonuniform_scale(aspect * onscreen_units, onscreen_units, 1.0), &self.root, &mut output);
+        self.recurse_output_gfx_storage(RDAffine::identity(), &RDColor::WHITE, self.root, &mut output);
         output
     }
+
+    fn recurse_output_gfx(
+        &mut self,
+        id: RDNodeId,
+        (parent_matrix, parent_tint): (RDAffine, &RDColor),
+        parent_dirty: bool,
+        (layer, z_index): (RDLayer, i32),
+        update: &mut RDGfxUpdate,
+        draws: &mut Vec<RDDrawCall>,
+    ) {
+        let SceneEntry { node, children, .. } = &mut self.nodes[id.0];
+
+        //the subtree is skipped entirely, remember that its world transform went stale
+        if !node.visible {
+            node.transform_dirty |= parent_dirty;
+            return;
+        }
+
+        let layer = node.layer.unwrap_or(layer);
+        let z_index = z_index + node.z_index;
+
+        let matrix = parent_matrix * node.transform.to_matrix();
+        let tint = inherit_tint(parent_tint, node);
+        let mut transform_dirty = parent_dirty || node.transform_dirty;
+
+        if node.geometry_dirty {
+            if let Some(allocation) = node.allocation.take() {
+                self.allocator.free(allocation);
+
+                if let Some(spatial) = &mut self.spatial {
+                    spatial.remove(id);
+                }
+            }
+
+            if let Some(mesh) = &node.mesh {
+                let allocation = self.allocator.allocate(&mesh.vao);
+
+                let mut vertices = mesh.vao.vertices.clone();
+                for vertex in vertices.iter_mut() {
+                    vertex.id = allocation.object;
+                }
+
+                if !vertices.is_empty() {
+                    update.vertices.push((allocation.vertices.start, vertices));
+                    update.indicies.push((allocation.indicies.start, mesh.vao.indicies.clone()));
+                }
+
+                node.allocation = Some(allocation);
+                //a new object slot has no transform in it yet
+                transform_dirty = true;
+            }
+        }
+
+        if let (Some(mesh), Some(allocation)) = (&node.mesh, &node.allocation) {
+            if transform_dirty {
+                update.push_storage(allocation.object, RDStorage {
+                    texture: mesh.texture,
+                    transform: matrix.into(),
+                    tint: tint.to_u32(),
+                });
+
+                if let (Some(spatial), Some(bounds)) = (&mut self.spatial, allocation.bounds) {
+                    spatial.insert(id, bounds.transform(&matrix));
+                }
+            }
+
+            if !allocation.indicies.is_empty() {
+                draws.push(RDDrawCall {
+                    node: id,
+                    indicies: allocation.indicies.clone(),
+                    base_vertex: allocation.vertices.start as i32,
+                    layer,
+                    z_index,
+                });
+            }
+        }
+
+        node.geometry_dirty = false;
+        node.transform_dirty = false;
+
+        for i in 0..children.len() {
+            let child = self.nodes[id.0].children[i];
+            self.recurse_output_gfx(child, (matrix, &tint), transform_dirty, (layer, z_index), update, draws);
+        }
+    }
+    //walks the tree uploading only nodes whose geometry or transform changed since the last call
+    pub fn output_gfx(&mut self, height: f32, width: f32) -> RDGfxUpdate {
+        let mut update = RDGfxUpdate::default();
+        let mut draws = vec![];
+
+        self.viewport = (height, width);
+        self.recurse_output_gfx(self.root, (RDAffine::identity(), &RDColor::WHITE), false, (RDLayer::default(), 0), &mut update, &mut draws);
+
+        //stable, so tree order still breaks ties
+        draws.sort_by_key(|draw| (draw.layer, draw.z_index));
+
+        let surface_size = Vector2::new(width, height);
+
+        for camera in self.cameras.iter() {
+            //a camera entirely off the surface still gets its view, just without anything to draw
+            let Some((transform, viewport)) = camera.surface_view(self.ppu as f32, surface_size) else {
+                update.views.push(RDView {
+                    transform: camera.view_matrix(self.ppu as f32, surface_size).into(),
+                    viewport: camera.pixel_viewport(surface_size),
+                    draws: vec![],
+                });
+                continue;
+            };
+
+            let mut view = RDView {
+                transform: transform.into(),
+                viewport,
+                draws: draws.clone(),
+            };
+
+            if let (Some(spatial), Some(bounds)) = (&self.spatial, camera.view_bounds(self.ppu as f32, surface_size)) {
+                let on_screen: HashSet<RDNodeId> = spatial.query_rect(&bounds).into_iter().collect();
+
+                view.draws.retain(|draw| on_screen.contains(&draw.node));
+                update.culled_draws += (draws.len() - view.draws.len()) as u32;
+            }
+
+            update.views.push(view);
+        }
+
+        update.atlas = self.glyph_atlas.take_update();
+        update.vertex_capacity = self.allocator.vertices.end();
+        update.index_capacity = self.allocator.indicies.end();
+        update.object_capacity = self.allocator.objects.end();
+
+        update
+    }
+
+    //for a backend that dropped an update, every node and the atlas are sent again with the next one
+    pub(crate) fn invalidate_uploads(&mut self) {
+        for entry in self.nodes.values_mut() {
+            entry.node.geometry_dirty = true;
+        }
+
+        self.glyph_atlas.invalidate();
+    }
+
+    pub(crate) fn allocated_counts(&self) -> (u32, u32, u32) {
+        (self.allocator.vertices.used(), self.allocator.indicies.used(), self.allocator.objects.used())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{InnerSpace, Vector2};
+
+    use crate::{animation::{RDEasing, RDProperty}, path_builder::{RDPath, RDStroke}};
+
+    use super::*;
+
+    fn square() -> RDNode {
+        RDPath::new()
+            .to(0.0, 0.0)
+            .line(1.0, 0.0)
+            .line(1.0, 1.0)
+            .line(0.0, 1.0)
+            .close()
+            .fill(RDColor::RED)
+            .to_node()
+    }
+
+    #[test]
+    fn test_scene_handles() {
+        let mut scene = RDScene::new(10);
+
+        let a = scene.add_root(square());
+        let b = scene.add_root(square());
+        let child = scene.add(a, RDNode::new());
+
+        assert_eq!(scene.len(), 7);
+        assert_eq!(scene.parent(child), Some(a));
+        assert_eq!(scene.children(a).len(), 3);
+
+        assert!(!scene.reparent(a, child));
+        assert!(scene.reparent(child, b));
+        assert_eq!(scene.children(b).last(), Some(&child));
+
+        let removed = scene.remove(b).unwrap();
+        assert_eq!(removed.children.len(), 3);
+        assert!(!scene.contains(child));
+        assert_eq!(scene.get(b).map(|_| ()), None);
+        assert_eq!(scene.len(), 3);
+
+        let b = scene.add_root(removed);
+        assert_eq!(scene.children(b).len(), 3);
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("fill", "fill"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("health*", "healthbar"));
+        assert!(glob_match("*bar", "healthbar"));
+        assert!(glob_match("h?alth*r", "healthbar"));
+        assert!(glob_match("*a*a*", "banana"));
+        assert!(!glob_match("health", "healthbar"));
+        assert!(!glob_match("*x*", "healthbar"));
+        assert!(!glob_match("?", ""));
+    }
+
+    #[test]
+    fn test_find() {
+        let mut scene = RDScene::new(10);
+
+        let hud = scene.add_root(RDNode::new().named("hud"));
+        let healthbar = scene.add(hud, square().named("healthbar"));
+        let manabar = scene.add(hud, square().named("manabar"));
+
+        assert_eq!(scene.find("hud"), Some(hud));
+        assert_eq!(scene.find("/hud/healthbar"), Some(healthbar));
+        assert_eq!(scene.find_from(hud, "manabar"), Some(manabar));
+        assert_eq!(scene.find("hud/healthbar/fill"), Some(scene.children(healthbar)[0]));
+        assert_eq!(scene.find("hud/missing"), None);
+
+        assert_eq!(scene.query_name("*bar"), vec![healthbar, manabar]);
+        assert_eq!(scene.query_name("fill").len(), 2);
+        assert_eq!(scene.query(|node| node.mesh.is_some()).len(), 4);
+        assert_eq!(scene.descendants(hud).count(), 6);
+        assert_eq!(scene.descendants(healthbar).next(), scene.find("hud/healthbar/fill"));
+    }
+
+    #[test]
+    fn test_draw_order() {
+        let mut scene = RDScene::new(10);
+
+        let overlay = scene.add_root(square().on_layer(RDLayer::Overlay));
+        let raised = scene.add_root(square().with_z_index(2));
+        let plain = scene.add_root(square());
+        let background = scene.add_root(square().on_layer(RDLayer::Background).with_z_index(10));
+
+        let update = scene.output_gfx(800.0, 800.0);
+
+        let base_vertex = |id: RDNodeId| scene.get(scene.children(id)[0]).unwrap().allocation.as_ref().unwrap().vertices.start as i32;
+        let order: Vec<_> = update.views[0].draws.iter().step_by(2).map(|draw| draw.base_vertex).collect();
+
+        assert_eq!(order, vec![base_vertex(background), base_vertex(plain), base_vertex(raised), base_vertex(overlay)]);
+        assert_eq!(update.views[0].draws[2].z_index, 0);
+        assert_eq!(update.views[0].draws[4].z_index, 2);
+
+        //strokes go after their fill
+        assert_eq!(update.views[0].draws[0].indicies.len(), 6);
+        assert_eq!(update.views[0].draws[1].indicies.len(), 24);
+    }
+
+    #[test]
+    fn test_visibility() {
+        let mut scene = RDScene::new(10);
+
+        let group = scene.add_root(RDNode::new());
+        let tooltip = scene.add(group, square().hidden());
+        scene.add(group, square());
+
+        let update = scene.output_gfx(800.0, 800.0);
+        assert_eq!(update.views[0].draws.len(), 2);
+        assert_eq!(update.vertices.len(), 2);
+        assert!(!scene.is_visible(scene.children(tooltip)[0]));
+
+        scene.set_transform(group, RDTransform {
+            rotation: 1.0,
+            ..Default::default()
+        });
+        let update = scene.output_gfx(800.0, 800.0);
+        assert_eq!(update.storage.len(), 1);
+        assert_eq!(update.storage[0].1.len(), 2);
+
+        scene.set_visible(tooltip, true);
+        let update = scene.output_gfx(800.0, 800.0);
+        assert_eq!(update.views[0].draws.len(), 4);
+        assert_eq!(update.vertices.len(), 2);
+        assert_eq!(update.storage.len(), 1);
+
+        scene.set_visible(group, false);
+        let update = scene.output_gfx(800.0, 800.0);
+        assert!(update.views[0].draws.is_empty());
+        assert!(update.vertices.is_empty());
+        assert_eq!(scene.output_gfx_vao().vertices.len(), 0);
+    }
+
+    #[test]
+    fn test_bounds() {
+        let mut scene = RDScene::new(10);
+
+        let mut group = RDNode::new();
+        group.transform.position = Vector2::new(10.0, 0.0);
+        group.transform.scale = Vector2::new(2.0, 2.0);
+        let group = scene.add_root(group);
+
+        let mut node = square();
+        node.transform.position = Vector2::new(1.0, 1.0);
+        let node = scene.add(group, node);
+
+        //the default stroke is 10 wide so it dominates the unit square
+        let local = RDRect::new(Vector2::new(-5.0, -5.0), Vector2::new(6.0, 6.0));
+        assert_eq!(scene.local_bounds(node), Some(local));
+        assert_eq!(scene.local_bounds(group), Some(RDRect::new(Vector2::new(-4.0, -4.0), Vector2::new(7.0, 7.0))));
+        assert_eq!(scene.world_bounds(node), Some(RDRect::new(Vector2::new(2.0, -8.0), Vector2::new(24.0, 14.0))));
+        assert_eq!(scene.world_bounds(group), scene.world_bounds(node));
+        let empty = scene.add_root(RDNode::new());
+        assert_eq!(scene.world_bounds(empty), None);
+
+        //scaling around the far corner mirrors the square back over the pivot
+        scene.set_transform(node, RDTransform {
+            scale: Vector2::new(-1.0, 1.0),
+            pivot: Vector2::new(6.0, 0.0),
+            ..Default::default()
+        });
+        assert_eq!(scene.world_bounds(node), Some(RDRect::new(Vector2::new(10.0, -10.0), Vector2::new(32.0, 12.0))));
+
+        let matrix = scene.world_transform(node);
+        let point = Vector2::new(3.0, 4.0);
+        assert_eq!(matrix.inverse().unwrap().transform_point(matrix.transform_point(point)), point);
+    }
+
+    #[test]
+    fn test_pick() {
+        let mut scene = RDScene::new(10);
+
+        let square = || RDPath::new()
+            .to(0.0, 0.0)
+            .line(1.0, 0.0)
+            .line(1.0, 1.0)
+            .line(0.0, 1.0)
+            .close()
+            .stroke(RDStroke { weight: 0.2, color: RDColor::BLACK })
+            .to_node();
+
+        let mut below = square();
+        below.transform.scale = Vector2::new(4.0, 4.0);
+        let below = scene.add_root(below);
+
+        let mut above = square().with_z_index(1);
+        above.transform.position = Vector2::new(2.0, 2.0);
+        let above = scene.add_root(above);
+
+        let hidden = scene.add_root(square().on_layer(RDLayer::Overlay).hidden());
+
+        assert_eq!(scene.pick(Vector2::new(400.0, 400.0)), None);
+        scene.output_gfx(800.0, 800.0);
+
+        let fill = |id: RDNodeId| scene.children(id)[0];
+        let stroke = |id: RDNodeId| scene.children(id)[1];
+
+        assert_eq!(scene.pick_world(Vector2::new(2.5, 2.5)), Some(fill(above)));
+        assert_eq!(scene.pick_world(Vector2::new(1.0, 1.0)), Some(fill(below)));
+        assert_eq!(scene.pick_world(Vector2::new(1.0, 0.2)), Some(stroke(below)));
+        assert_eq!(scene.pick_world(Vector2::new(100.0, 0.0)), None);
+
+        assert_eq!(scene.pick_all_world(Vector2::new(2.5, 2.5)), vec![fill(above), fill(below)]);
+        assert_eq!(scene.pick_all_world(Vector2::new(1.0, 0.2)), vec![stroke(below), fill(below)]);
+        assert!(!scene.pick_all_world(Vector2::new(0.5, 0.5)).contains(&fill(hidden)));
+
+        //the center pixel is the origin and at 10 ppu an 800 pixel viewport spans 160 units
+        assert_eq!(scene.screen_to_world(Vector2::new(400.0, 400.0)), Some(Vector2::new(0.0, 0.0)));
+        assert_eq!(scene.pick(Vector2::new(400.0 + 2.5 * 5.0, 400.0 - 2.5 * 5.0)), Some(fill(above)));
+    }
+
+    #[test]
+    fn test_spatial_index() {
+        let mut scene = RDScene::new(10);
+
+        let mut nodes = vec![];
+        for i in 0..50 {
+            let mut node = square();
+            node.transform.position = Vector2::new(i as f32 * 30.0, 0.0);
+            nodes.push(scene.add_root(node));
+        }
+
+        scene.enable_spatial_index(16.0);
+        assert_eq!(scene.spatial_index().unwrap().len(), 100);
+
+        //at 10 ppu an 800 pixel viewport spans -80..80
+        let update = scene.output_gfx(800.0, 800.0);
+        assert_eq!(update.views[0].draws.len(), 6);
+        assert_eq!(update.culled_draws, 94);
+
+        let fill = |id: RDNodeId| scene.children(id)[0];
+        assert_eq!(scene.pick_world(Vector2::new(300.5, 0.5)), Some(scene.children(nodes[10])[1]));
+        assert_eq!(scene.pick_all_world(Vector2::new(0.5, 0.5)).last(), Some(&fill(nodes[0])));
+
+        scene.set_transform(nodes[40], RDTransform::default());
+        let update = scene.output_gfx(800.0, 800.0);
+        assert_eq!(update.views[0].draws.len(), 8);
+        assert_eq!(scene.pick_all_world(Vector2::new(0.5, 0.5)).len(), 4);
+
+        scene.remove(nodes[40]);
+        assert_eq!(scene.spatial_index().unwrap().len(), 98);
+    }
+
+    #[test]
+    fn test_spatial_pick_without_render() {
+        let mut scene = RDScene::new(10);
+        scene.enable_spatial_index(16.0);
+
+        //nothing is rendered in between, the index follows every change right away
+        let node = scene.add_root(square());
+        //the outline is drawn over the fill, so it is what gets hit
+        let top = scene.children(node)[1];
+        assert_eq!(scene.pick_world(Vector2::new(0.5, 0.5)), Some(top));
+
+        scene.set_transform(node, RDTransform { position: Vector2::new(100.0, 0.0), ..Default::default() });
+        assert_eq!(scene.pick_world(Vector2::new(0.5, 0.5)), None);
+        assert_eq!(scene.pick_world(Vector2::new(100.5, 0.5)), Some(top));
+
+        let mut group = RDNode::new();
+        group.transform.position = Vector2::new(-50.0, 0.0);
+        let parent = scene.add_root(group);
+        scene.reparent(node, parent);
+        assert_eq!(scene.pick_world(Vector2::new(50.5, 0.5)), Some(top));
+
+        scene.get_mut(node).unwrap().transform_mut().position.x = 0.0;
+        assert_eq!(scene.pick_world(Vector2::new(-49.5, 0.5)), Some(top));
+
+        scene.animate(parent, RDTween::new(RDProperty::Position(Vector2::new(0.0, 0.0)), Duration::from_secs(1)));
+        scene.advance_animations(Duration::from_secs(1));
+        assert_eq!(scene.pick_world(Vector2::new(0.5, 0.5)), Some(top));
+    }
+
+    #[test]
+    fn test_cameras() {
+        let mut scene = RDScene::new(10);
+        let node = scene.add_root(square());
+
+        scene.camera_mut().viewport = RDRect::new(Vector2::new(0.0, 0.0), Vector2::new(0.5, 1.0));
+        let mut minimap = RDCamera::with_viewport(RDRect::new(Vector2::new(0.5, 0.0), Vector2::new(1.0, 1.0)));
+        minimap.position = Vector2::new(100.0, 0.0);
+        scene.add_camera(minimap);
+
+        let update = scene.output_gfx(400.0, 800.0);
+        assert_eq!(update.views.len(), 2);
+        assert_eq!(update.views[1].viewport, RDRect::new(Vector2::new(400.0, 0.0), Vector2::new(800.0, 400.0)));
+
+        assert_eq!(scene.camera_at(Vector2::new(100.0, 100.0)), Some(0));
+        assert_eq!(scene.camera_at(Vector2::new(500.0, 100.0)), Some(1));
+        assert!((scene.screen_to_world(Vector2::new(600.0, 200.0)).unwrap() - Vector2::new(100.0, 0.0)).magnitude() < 1e-3);
+        assert!((scene.world_to_screen(Vector2::new(0.0, 0.0)).unwrap() - Vector2::new(200.0, 200.0)).magnitude() < 1e-3);
+        assert_eq!(scene.pick(Vector2::new(200.0, 200.0)), Some(scene.children(node)[1]));
+
+        //moving the camera doesn't touch the per object data
+        scene.camera_mut().position = Vector2::new(5.0, 5.0);
+        let update = scene.output_gfx(400.0, 800.0);
+        assert!(update.storage.is_empty());
+    }
+
+    #[test]
+    fn test_incremental_upload() {
+        let mut scene = RDScene::new(10);
+
+        let nodes: Vec<_> = (0..10).map(|_| scene.add_root(square())).collect();
+
+        let update = scene.output_gfx(800.0, 800.0);
+        assert_eq!(update.vertices.len(), 20);
+        assert_eq!(update.storage.iter().map(|(_, run)| run.len()).sum::<usize>(), 20);
+        assert_eq!(update.views[0].draws.len(), 20);
+
+        let update = scene.output_gfx(800.0, 800.0);
+        assert!(update.vertices.is_empty());
+        assert!(update.storage.is_empty());
+        assert_eq!(update.views[0].draws.len(), 20);
+
+        scene.set_transform(nodes[3], RDTransform {
+            position: Vector2::new(1.0, 0.0),
+            ..Default::default()
+        });
+
+        let update = scene.output_gfx(800.0, 800.0);
+        assert!(update.vertices.is_empty());
+        assert_eq!(update.storage.len(), 1);
+        assert_eq!(update.storage[0].1.len(), 2);
+
+        scene.remove(nodes[0]);
+        let update = scene.output_gfx(800.0, 800.0);
+        assert!(update.vertices.is_empty());
+        assert_eq!(update.views[0].draws.len(), 18);
+
+        scene.add_root(square());
+        let update = scene.output_gfx(800.0, 800.0);
+        assert_eq!(update.vertices.len(), 2);
+        assert_eq!(update.vertex_capacity, 120);
+    }
+
+    #[test]
+    fn test_animations() {
+        let mut scene = RDScene::new(10);
+        let node = scene.add_root(square());
+        scene.output_gfx(800.0, 800.0);
+
+        let slide = scene.animate(node, RDTween::new(RDProperty::Position(Vector2::new(4.0, 0.0)), Duration::from_secs(2)).easing(RDEasing::Linear));
+        let fade = scene.animate(node, RDTween::new(RDProperty::Opacity(0.0), Duration::from_secs(1)).repeat_forever());
+
+        scene.advance_animations(Duration::from_secs(1));
+        assert_eq!(scene.get(node).unwrap().transform.position, Vector2::new(2.0, 0.0));
+
+        //moving and fading only re-upload the object
+        let update = scene.output_gfx(800.0, 800.0);
+        assert!(update.vertices.is_empty());
+        assert_eq!(update.storage.len(), 1);
+
+        //a finished tween leaves its end value behind
+        scene.advance_animations(Duration::from_secs(5));
+        assert_eq!(scene.get(node).unwrap().transform.position, Vector2::new(4.0, 0.0));
+        assert!(!scene.is_animating(slide) && scene.is_animating(fade));
+
+        assert!(scene.stop_animation(fade));
+        assert!(!scene.stop_animation(fade));
+
+        let fade = scene.animate(node, RDTween::new(RDProperty::Opacity(0.0), Duration::from_secs(1)));
+        scene.remove(node);
+        scene.advance_animations(Duration::from_millis(10));
+        assert!(!scene.is_animating(fade));
+    }
+
+    #[test]
+    fn test_get_mut() {
+        let mut scene = RDScene::new(10);
+        let square_id = scene.add_root(square());
+        let node = scene.children(square_id)[0];
+        scene.output_gfx(800.0, 800.0);
+
+        //looking at a node doesn't upload it again
+        assert_eq!(scene.get_mut(node).unwrap().z_index, 0);
+        let update = scene.output_gfx(800.0, 800.0);
+        assert!(update.vertices.is_empty() && update.storage.is_empty());
+
+        scene.get_mut(node).unwrap().transform_mut().position.x = 1.0;
+        let update = scene.output_gfx(800.0, 800.0);
+        assert!(update.vertices.is_empty());
+        assert_eq!(update.storage.len(), 1);
+
+        scene.get_mut(node).unwrap().set_mesh(square().children.remove(0).mesh);
+        let update = scene.output_gfx(800.0, 800.0);
+        assert_eq!(update.vertices.len(), 1);
+    }
 }
@@ -6,15 +6,33 @@ struct VertexInput {
 };
 
 struct Object {
-    transform: mat4x4<f32>,
+    transform: mat3x2<f32>,
     texture: u32,
+    tint: u32,
+};
+
+//same layout as a mat3x2, spelled out since gl pads matrix columns in uniforms to 16 bytes
+struct Camera {
+    x: vec2<f32>,
+    y: vec2<f32>,
+    w: vec2<f32>,
 };
 
 @group(0) @binding(0) var<storage, read> storage_data: array<Object>;
+@group(1) @binding(0) var<uniform> camera: Camera;
+@group(2) @binding(0) var atlas: texture_2d<f32>;
+@group(2) @binding(1) var atlas_sampler: sampler;
+
+//the same as GLYPH_ATLAS_TEXTURE and SDF_SPREAD in atlas.rs
+const GLYPH_ATLAS_TEXTURE: u32 = 1u;
+const SDF_SPREAD: f32 = 4.0;
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) color: vec4<f32>,
+    //in atlas pixels
+    @location(1) texture_position: vec2<f32>,
+    @location(2) @interpolate(flat) texture: u32,
 };
 
 fn extract_u8_from_u32(value: u32) -> vec4<f32> {
@@ -33,8 +51,12 @@ fn vs_main(
 
     var out: VertexOutput;
 
-    out.color = extract_u8_from_u32(model.color);
-    out.clip_position = object.transform * vec4<f32>(model.position, 1.0, 1.0);
+    out.color = extract_u8_from_u32(model.color) * extract_u8_from_u32(object.tint);
+    out.texture_position = model.texture_position;
+    out.texture = object.texture;
+    let world = object.transform * vec3<f32>(model.position, 1.0);
+    let camera_transform = mat3x2<f32>(camera.x, camera.y, camera.w);
+    out.clip_position = vec4<f32>(camera_transform * vec3<f32>(world, 1.0), 0.0, 1.0);
 
     return out;
 }
@@ -43,6 +65,18 @@ fn vs_main(
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return in.color;
+    //derivatives and filtered sampling need uniform control flow, so the atlas is read for every fragment
+    let texels_per_pixel = 0.5 * (length(dpdx(in.texture_position)) + length(dpdy(in.texture_position)));
+    let value = textureSample(atlas, atlas_sampler, in.texture_position / vec2<f32>(textureDimensions(atlas))).r;
+
+    if in.texture != GLYPH_ATLAS_TEXTURE {
+        return in.color;
+    }
+
+    //the distance to the outline in screen pixels, the edge stays a pixel wide however far the text is zoomed
+    let pixels = (value - 0.5) * 2.0 * SDF_SPREAD / max(texels_per_pixel, 1e-6);
+    let coverage = clamp(pixels + 0.5, 0.0, 1.0);
+
+    return vec4<f32>(in.color.rgb, in.color.a * coverage);
 }
  
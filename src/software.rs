@@ -0,0 +1,457 @@
+use std::{convert::Infallible, ops::Range};
+
+use cgmath::{InnerSpace, Vector2};
+
+use crate::{atlas::{sdf_coverage, GLYPH_ATLAS_TEXTURE}, backend::{render_frame, RDBackend}, color::linear_to_srgb, image::RDImage, math::{RDAffine, RDRect}, scene::{RDGfxUpdate, RDScene, RDView}, RDMultisample, RDOptions, RDRenderStats, RDStorage, RDVertex, CLEAR_COLOR};
+
+//sample positions inside a pixel, the standard d3d and vulkan patterns so edges match the gpu
+fn sample_pattern(multisample: RDMultisample) -> &'static [(f32, f32)] {
+    match multisample {
+        RDMultisample::Off => &[(0.5, 0.5)],
+        RDMultisample::X4 => &[(0.375, 0.125), (0.875, 0.375), (0.125, 0.625), (0.625, 0.875)],
+        RDMultisample::X8 => &[
+            (0.5625, 0.3125), (0.4375, 0.6875), (0.8125, 0.5625), (0.3125, 0.1875),
+            (0.1875, 0.8125), (0.0625, 0.4375), (0.6875, 0.9375), (0.9375, 0.0625),
+        ],
+    }
+}
+
+fn unpack_color(color: u32) -> [f32; 4] {
+    [0, 8, 16, 24].map(|shift| ((color >> shift) & 0xFF) as f32 / 255.0)
+}
+
+fn edge(a: Vector2<f32>, b: Vector2<f32>, p: Vector2<f32>) -> f32 {
+    (b - a).perp_dot(p - a)
+}
+
+//samples exactly on an edge go to one side only, so triangles sharing it don't blend twice
+fn owns_edge(a: Vector2<f32>, b: Vector2<f32>) -> bool {
+    a.y < b.y || (a.y == b.y && a.x < b.x)
+}
+
+//the glyph atlas as uploaded, read with the bilinear filtering the gpu sampler does
+#[derive(Default)]
+struct AtlasMirror {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl AtlasMirror {
+    //texel centers sit on half pixels, positions outside clamp to the edge
+    fn sample(&self, position: Vector2<f32>) -> f32 {
+        if self.pixels.is_empty() {
+            return 0.0;
+        }
+
+        let texel = |x: f32, y: f32| {
+            let x = x.clamp(0.0, self.width as f32 - 1.0) as usize;
+            let y = y.clamp(0.0, self.height as f32 - 1.0) as usize;
+            self.pixels[y * self.width as usize + x] as f32 / 255.0
+        };
+
+        let (x, y) = (position.x - 0.5, position.y - 0.5);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+
+        let top = texel(x0, y0) * (1.0 - fx) + texel(x0 + 1.0, y0) * fx;
+        let bottom = texel(x0, y0 + 1.0) * (1.0 - fx) + texel(x0 + 1.0, y0 + 1.0) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+}
+
+//a triangle corner in pixels with its color and atlas position
+type Corner = (Vector2<f32>, [f32; 4], Vector2<f32>);
+
+//one linear color per sample, resolved into srgb like the gpu's multisampled srgb target
+struct SampleBuffer {
+    width: u32,
+    height: u32,
+    pattern: &'static [(f32, f32)],
+    samples: Vec<[f32; 4]>,
+}
+
+impl SampleBuffer {
+    fn new(width: u32, height: u32, pattern: &'static [(f32, f32)]) -> Self {
+        Self {
+            width,
+            height,
+            pattern,
+            samples: vec![CLEAR_COLOR; width as usize * height as usize * pattern.len()],
+        }
+    }
+
+    //alpha blending, the same as wgpu::BlendState::ALPHA_BLENDING
+    fn blend(&mut self, x: u32, y: u32, sample: usize, color: [f32; 4]) {
+        let index = (y as usize * self.width as usize + x as usize) * self.pattern.len() + sample;
+        let destination = &mut self.samples[index];
+        let alpha = color[3];
+
+        for channel in 0..3 {
+            destination[channel] = color[channel] * alpha + destination[channel] * (1.0 - alpha);
+        }
+        destination[3] = alpha + destination[3] * (1.0 - alpha);
+    }
+
+    //colors are interpolated per sample, with an atlas their alpha is scaled by the distance field's coverage
+    fn triangle(&mut self, mut corners: [Corner; 3], atlas: Option<&AtlasMirror>, columns: Range<u32>, rows: Range<u32>) {
+        let area = edge(corners[0].0, corners[1].0, corners[2].0);
+
+        if area == 0.0 || !area.is_finite() {
+            return;
+        }
+
+        //no culling, mirrored triangles are turned around instead
+        if area < 0.0 {
+            corners.swap(1, 2);
+        }
+
+        let area = area.abs();
+        let [(p0, c0, t0), (p1, c1, t1), (p2, c2, t2)] = corners;
+        let owned = [owns_edge(p1, p2), owns_edge(p2, p0), owns_edge(p0, p1)];
+
+        //the atlas position moves by the same amount for every pixel step across the triangle, the derivatives the gpu takes
+        let step = |d0: f32, d1: f32, d2: f32| (t0 * d0 + t1 * d1 + t2 * d2) / area;
+        let per_column = step(p1.y - p2.y, p2.y - p0.y, p0.y - p1.y);
+        let per_row = step(p2.x - p1.x, p0.x - p2.x, p1.x - p0.x);
+        let texels_per_pixel = 0.5 * (per_column.magnitude() + per_row.magnitude());
+
+        let bounds = RDRect::from_points([p0, p1, p2]).unwrap();
+        let columns = (bounds.min.x.floor().max(columns.start as f32) as u32)..(bounds.max.x.ceil().min(columns.end as f32) as u32);
+        let rows = (bounds.min.y.floor().max(rows.start as f32) as u32)..(bounds.max.y.ceil().min(rows.end as f32) as u32);
+
+        for y in rows {
+            for x in columns.clone() {
+                for (sample, (dx, dy)) in self.pattern.iter().enumerate() {
+                    let p = Vector2::new(x as f32 + dx, y as f32 + dy);
+                    let weights = [edge(p1, p2, p), edge(p2, p0, p), edge(p0, p1, p)];
+
+                    let inside = weights.iter().zip(owned).all(|(weight, owned)| *weight > 0.0 || (*weight == 0.0 && owned));
+                    if !inside {
+                        continue;
+                    }
+
+                    let [w0, w1, w2] = weights.map(|weight| weight / area);
+                    let mut color = [0, 1, 2, 3].map(|channel| c0[channel] * w0 + c1[channel] * w1 + c2[channel] * w2);
+
+                    if let Some(atlas) = atlas {
+                        color[3] *= sdf_coverage(atlas.sample(t0 * w0 + t1 * w1 + t2 * w2), texels_per_pixel);
+                    }
+
+                    self.blend(x, y, sample, color);
+                }
+            }
+        }
+    }
+
+    fn resolve(&self) -> RDImage {
+        let mut image = RDImage::new(self.width, self.height);
+
+        for (pixel, samples) in image.pixels.chunks_exact_mut(4).zip(self.samples.chunks_exact(self.pattern.len())) {
+            for channel in 0..4 {
+                let average = samples.iter().map(|sample| sample[channel]).sum::<f32>() / samples.len() as f32;
+                let value = if channel == 3 { average } else { linear_to_srgb(average) };
+
+                pixel[channel] = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+            }
+        }
+
+        image
+    }
+}
+
+//rasterizes on the cpu from the same buffers the gpu gets, for machines without an adapter and as a reference in tests
+pub struct RDSoftwareBackend {
+    pattern: &'static [(f32, f32)],
+    width: u32,
+    height: u32,
+    //mirrors of the gpu buffers, kept in sync by the same incremental updates
+    vertices: Vec<RDVertex>,
+    indicies: Vec<u32>,
+    storage: Vec<RDStorage>,
+    atlas: AtlasMirror,
+    frame: Option<SampleBuffer>,
+}
+
+impl RDSoftwareBackend {
+    pub fn new(options: RDOptions) -> Self {
+        Self {
+            pattern: sample_pattern(options.multisample),
+            width: 1,
+            height: 1,
+            vertices: vec![],
+            indicies: vec![],
+            storage: vec![],
+            atlas: AtlasMirror::default(),
+            frame: None,
+        }
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+    }
+}
+
+impl RDBackend for RDSoftwareBackend {
+    type Error = Infallible;
+
+    fn begin_frame(&mut self) -> Result<(), Infallible> {
+        self.frame = Some(SampleBuffer::new(self.width, self.height, self.pattern));
+        Ok(())
+    }
+
+    fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn upload_geometry(&mut self, update: &RDGfxUpdate, stats: &mut RDRenderStats) -> Result<(), Infallible> {
+        reserve(&mut self.vertices, update.vertex_capacity, stats);
+        reserve(&mut self.indicies, update.index_capacity, stats);
+
+        stats.uploaded_bytes += write(&mut self.vertices, &update.vertices) + write(&mut self.indicies, &update.indicies);
+        stats.vertex_buffer_size = std::mem::size_of_val(self.vertices.as_slice()) as u64;
+        stats.index_buffer_size = std::mem::size_of_val(self.indicies.as_slice()) as u64;
+        Ok(())
+    }
+
+    fn upload_objects(&mut self, update: &RDGfxUpdate, stats: &mut RDRenderStats) -> Result<(), Infallible> {
+        reserve(&mut self.storage, update.object_capacity, stats);
+
+        stats.uploaded_bytes += write(&mut self.storage, &update.storage);
+        stats.storage_buffer_size = std::mem::size_of_val(self.storage.as_slice()) as u64;
+        Ok(())
+    }
+
+    fn upload_textures(&mut self, update: &RDGfxUpdate, stats: &mut RDRenderStats) -> Result<(), Infallible> {
+        let Some(update) = &update.atlas else { return Ok(()) };
+
+        if (self.atlas.width, self.atlas.height) != (update.width, update.height) {
+            self.atlas = AtlasMirror { width: update.width, height: update.height, pixels: vec![0; (update.width * update.height) as usize] };
+        }
+
+        let start = (update.rows.start * update.width) as usize;
+        self.atlas.pixels[start..start + update.pixels.len()].copy_from_slice(&update.pixels);
+        stats.uploaded_bytes += update.pixels.len() as u64;
+        Ok(())
+    }
+
+    fn draw(&mut self, views: &[RDView]) -> Result<(), Infallible> {
+        let Some(target) = &mut self.frame else { panic!("draw outside of a frame"); };
+        let surface = RDRect::new(Vector2::new(0.0, 0.0), Vector2::new(self.width as f32, self.height as f32));
+
+        for view in views.iter() {
+            //the same clamping the gpu path does for its scissor rect
+            let Some(scissor) = view.viewport.intersection(&surface) else { continue };
+            if scissor.width() < 1.0 || scissor.height() < 1.0 {
+                continue;
+            }
+
+            let columns = scissor.min.x as u32..scissor.min.x as u32 + scissor.width() as u32;
+            let rows = scissor.min.y as u32..scissor.min.y as u32 + scissor.height() as u32;
+
+            let camera = RDAffine::from(view.transform);
+            let viewport = view.viewport;
+
+            for draw in view.draws.iter() {
+                for triangle in self.indicies[draw.indicies.start as usize..draw.indicies.end as usize].chunks_exact(3) {
+                    let vertex = |index: u32| &self.vertices[(index as i64 + draw.base_vertex as i64) as usize];
+                    let textured = self.storage[vertex(triangle[0]).id as usize].texture == GLYPH_ATLAS_TEXTURE;
+
+                    let corners = [triangle[0], triangle[1], triangle[2]].map(|index| {
+                        let vertex = vertex(index);
+                        let storage = &self.storage[vertex.id as usize];
+                        let object = RDAffine::from(storage.transform);
+                        let clip = camera.transform_point(object.transform_point(vertex.position.into()));
+
+                        let pixel = Vector2::new(
+                            viewport.min.x + (clip.x + 1.0) * 0.5 * viewport.width(),
+                            viewport.min.y + (1.0 - clip.y) * 0.5 * viewport.height(),
+                        );
+
+                        let tint = unpack_color(storage.tint);
+                        let color = unpack_color(vertex.color);
+
+                        (pixel, [0, 1, 2, 3].map(|channel| color[channel] * tint[channel]), Vector2::from(vertex.texture_position))
+                    });
+
+                    target.triangle(corners, textured.then_some(&self.atlas), columns.clone(), rows.clone());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn end_frame(&mut self) -> Option<RDImage> {
+        Some(self.frame.take()?.resolve())
+    }
+}
+
+fn reserve<T: bytemuck::Zeroable + Clone>(buffer: &mut Vec<T>, capacity: u32, stats: &mut RDRenderStats) {
+    if buffer.len() < capacity as usize {
+        buffer.resize(capacity as usize, T::zeroed());
+        stats.buffer_reallocations += 1;
+    }
+}
+
+fn write<T: Copy>(buffer: &mut [T], ranges: &[(u32, Vec<T>)]) -> u64 {
+    ranges.iter().map(|(offset, values)| {
+        buffer[*offset as usize..*offset as usize + values.len()].copy_from_slice(values);
+        std::mem::size_of_val(values.as_slice()) as u64
+    }).sum()
+}
+
+//a scene drawn by the software backend, the cpu counterpart of HeadlessRenderer
+pub struct SoftwareRenderer {
+    pub scene: RDScene,
+    stats: RDRenderStats,
+    backend: RDSoftwareBackend,
+}
+
+impl Default for SoftwareRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SoftwareRenderer {
+    pub fn new() -> Self {
+        SoftwareRenderer::with_options(RDOptions::default())
+    }
+
+    pub fn with_options(options: RDOptions) -> Self {
+        Self {
+            scene: RDScene::new(10),
+            stats: RDRenderStats::default(),
+            backend: RDSoftwareBackend::new(options),
+        }
+    }
+
+    pub fn stats(&self) -> RDRenderStats {
+        self.stats
+    }
+
+    pub fn render_to_image(&mut self, width: u32, height: u32) -> RDImage {
+        if width == 0 || height == 0 {
+            return RDImage::new(width, height);
+        }
+
+        self.backend.resize(width, height);
+
+        let Ok(image) = render_frame(&mut self.scene, &mut self.backend, &mut self.stats);
+        image.expect("software frames are always read back")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Vector2;
+
+    use crate::{color::RDColor, font::tests::truetype_font, path_builder::{RDPath, RDStroke}, scene::RDNode, text::RDText, RDMultisample, RDOptions};
+
+    use super::SoftwareRenderer;
+
+    fn square(color: RDColor) -> RDNode {
+        RDPath::new()
+            .to(-1.0, -1.0)
+            .line(1.0, -1.0)
+            .line(1.0, 1.0)
+            .line(-1.0, 1.0)
+            .close()
+            .stroke(RDStroke { weight: 0.0, color: RDColor::BLACK })
+            .fill(color)
+            .to_node()
+    }
+
+    #[test]
+    fn test_software_render() {
+        let mut renderer = SoftwareRenderer::new();
+
+        let mut red = square(RDColor::RED);
+        red.transform.position = Vector2::new(5.0, 0.0);
+        renderer.scene.add_root(red);
+
+        let mut blue = square(RDColor::new(0, 0, 255, 128));
+        blue.transform.position = Vector2::new(5.5, 0.5);
+        renderer.scene.add_root(blue);
+
+        //ppu 10 over 100 pixels, the red square covers pixels 70..80 by 45..55
+        let image = renderer.render_to_image(100, 100);
+
+        assert_eq!(image.pixel(0, 0), RDColor::new(89, 124, 149, 255));
+        assert_eq!(image.pixel(72, 53), RDColor::RED);
+
+        //half transparent blue over red, blended in linear space then stored as srgb
+        assert_eq!(image.pixel(78, 45), RDColor::new(187, 0, 188, 255));
+
+        //the edge lies exactly on the pixel grid, nothing bleeds into the next column
+        assert_eq!(image.pixel(69, 50), image.pixel(0, 0));
+
+        //half a pixel off the grid the edge pixels are half covered
+        renderer.scene.set_transform(renderer.scene.children(renderer.scene.root())[0], crate::scene::RDTransform {
+            position: Vector2::new(5.05, 0.0),
+            ..Default::default()
+        });
+        let image = renderer.render_to_image(100, 100);
+        let edge = image.pixel(70, 50);
+        //three of the four samples land on the square
+        assert_eq!(edge, RDColor::new(228, 63, 77, 255));
+        assert_eq!(image.pixel(71, 50), RDColor::RED);
+
+        //the shared diagonal inside the square is neither skipped nor drawn twice
+        let mut renderer = SoftwareRenderer::with_options(RDOptions { multisample: RDMultisample::X8, ..Default::default() });
+        renderer.scene.add_root(square(RDColor::new(255, 255, 255, 128)));
+        let image = renderer.render_to_image(100, 100);
+        assert!((45..55).all(|i| image.pixel(i, i) == image.pixel(46, 53)));
+    }
+
+    #[test]
+    fn test_tint() {
+        let mut renderer = SoftwareRenderer::new();
+
+        //a white square under a red tinted, half faded group draws like a half transparent red one
+        let mut group = RDNode::new().with_tint(RDColor::RED).with_opacity(0.5);
+        group.children.push(square(RDColor::WHITE));
+        renderer.scene.add_root(group);
+        let tinted = renderer.render_to_image(100, 100);
+
+        let mut renderer = SoftwareRenderer::new();
+        renderer.scene.add_root(square(RDColor::new(255, 0, 0, 128)));
+        assert_eq!(tinted.pixel(50, 50), renderer.render_to_image(100, 100).pixel(50, 50));
+
+        let mut renderer = SoftwareRenderer::new();
+        let group = renderer.scene.add_root(RDNode::new().with_tint(RDColor::RED));
+        renderer.scene.add(group, square(RDColor::WHITE));
+        renderer.render_to_image(100, 100);
+
+        //tints can change after the first upload
+        renderer.scene.set_tint(group, RDColor::BLUE);
+        assert_eq!(renderer.render_to_image(100, 100).pixel(50, 50), RDColor::BLUE);
+    }
+
+    #[test]
+    fn test_sdf_text() {
+        let font = truetype_font();
+        let mut renderer = SoftwareRenderer::new();
+
+        let text = RDText::new(&font, "o").size(10.0).fill(RDColor::RED).to_sdf_node(renderer.scene.glyph_atlas_mut());
+        renderer.scene.add_root(text);
+
+        //the square's ring is 2 units wide around a 2 unit hole, 10 and 10 pixels at ppu 10
+        let image = renderer.render_to_image(100, 100);
+        let background = RDColor::new(89, 124, 149, 255);
+        assert_eq!(image.pixel(55, 35), RDColor::RED);
+        assert_eq!(image.pixel(65, 35), background);
+        assert_eq!(image.pixel(85, 35), background);
+
+        //zoomed in until an atlas pixel covers some 30 screen pixels the hole's edge still falls within a pixel
+        renderer.scene.camera_mut().zoom = 20.0;
+        renderer.scene.camera_mut().position = Vector2::new(2.0, 3.0);
+        let image = renderer.render_to_image(100, 100);
+        assert_eq!(image.pixel(48, 50), RDColor::RED);
+        assert_eq!(image.pixel(52, 50), background);
+        let edge = [49, 50].map(|x| image.pixel(x, 50));
+        assert!(edge.iter().all(|pixel| *pixel != RDColor::RED && *pixel != background), "{edge:?}");
+    }
+}
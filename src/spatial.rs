@@ -0,0 +1,183 @@
+use std::collections::{HashMap, HashSet};
+
+use cgmath::Vector2;
+
+use crate::{math::RDRect, scene::RDNodeId};
+
+//nodes covering more cells than this are kept in a separate list instead of being smeared over the grid
+const MAX_CELLS_PER_ENTRY: i64 = 64;
+
+type Cell = (i32, i32);
+
+#[derive(Clone, Copy)]
+struct CellRange {
+    min: Cell,
+    max: Cell,
+}
+
+impl CellRange {
+    fn len(&self) -> i64 {
+        (self.max.0 as i64 - self.min.0 as i64 + 1) * (self.max.1 as i64 - self.min.1 as i64 + 1)
+    }
+
+    fn cells(&self) -> impl Iterator<Item = Cell> {
+        let CellRange { min, max } = *self;
+        (min.0..=max.0).flat_map(move |x| (min.1..=max.1).map(move |y| (x, y)))
+    }
+}
+
+//uniform grid over world space bounds, cheap to update when a single node moves
+pub struct RDSpatialIndex {
+    cell_size: f32,
+    cells: HashMap<Cell, Vec<RDNodeId>>,
+    entries: HashMap<RDNodeId, (RDRect, Option<CellRange>)>,
+    large: HashSet<RDNodeId>,
+}
+
+impl RDSpatialIndex {
+    pub fn new(cell_size: f32) -> Self {
+        debug_assert!(cell_size > 0.0);
+
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            entries: HashMap::new(),
+            large: HashSet::new(),
+        }
+    }
+
+    fn cell_range(&self, rect: &RDRect) -> CellRange {
+        let cell = |point: Vector2<f32>| ((point.x / self.cell_size).floor() as i32, (point.y / self.cell_size).floor() as i32);
+
+        CellRange {
+            min: cell(rect.min),
+            max: cell(rect.max),
+        }
+    }
+
+    pub fn insert(&mut self, id: RDNodeId, bounds: RDRect) {
+        self.remove(id);
+
+        let range = self.cell_range(&bounds);
+
+        if range.len() > MAX_CELLS_PER_ENTRY {
+            self.large.insert(id);
+            self.entries.insert(id, (bounds, None));
+            return;
+        }
+
+        for cell in range.cells() {
+            self.cells.entry(cell).or_default().push(id);
+        }
+        self.entries.insert(id, (bounds, Some(range)));
+    }
+
+    pub fn remove(&mut self, id: RDNodeId) {
+        let Some((_, range)) = self.entries.remove(&id) else { return };
+
+        let Some(range) = range else {
+            self.large.remove(&id);
+            return;
+        };
+
+        for cell in range.cells() {
+            if let Some(ids) = self.cells.get_mut(&cell) {
+                ids.retain(|other| *other != id);
+
+                if ids.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+    }
+
+    pub fn bounds(&self, id: RDNodeId) -> Option<RDRect> {
+        self.entries.get(&id).map(|(bounds, _)| *bounds)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.entries.clear();
+        self.large.clear();
+    }
+
+    //every node whose bounds overlap the rect, in no particular order
+    pub fn query_rect(&self, rect: &RDRect) -> Vec<RDNodeId> {
+        let range = self.cell_range(rect);
+        let overlaps = |id: &RDNodeId| self.entries[id].0.intersects(rect);
+
+        //a query bigger than the whole index is faster as a plain scan
+        if range.len() > self.entries.len() as i64 {
+            return self.entries.keys().copied().filter(overlaps).collect();
+        }
+
+        let mut seen = HashSet::new();
+        let mut found = vec![];
+
+        for cell in range.cells() {
+            for id in self.cells.get(&cell).into_iter().flatten() {
+                if seen.insert(*id) && overlaps(id) {
+                    found.push(*id);
+                }
+            }
+        }
+
+        found.extend(self.large.iter().copied().filter(overlaps));
+        found
+    }
+
+    pub fn query_point(&self, point: Vector2<f32>) -> Vec<RDNodeId> {
+        self.query_rect(&RDRect::new(point, point))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Vector2;
+
+    use crate::{arena::Arena, math::RDRect, scene::RDNodeId};
+
+    use super::RDSpatialIndex;
+
+    #[test]
+    fn test_spatial_index() {
+        let mut arena = Arena::default();
+        let mut id = || RDNodeId(arena.insert(()));
+
+        let rect = |x: f32, y: f32, size: f32| RDRect::new(Vector2::new(x, y), Vector2::new(x + size, y + size));
+
+        let mut index = RDSpatialIndex::new(10.0);
+
+        let small = id();
+        let other = id();
+        let huge = id();
+
+        index.insert(small, rect(1.0, 1.0, 2.0));
+        index.insert(other, rect(-25.0, 5.0, 10.0));
+        index.insert(huge, rect(-1000.0, -1000.0, 2000.0));
+
+        let mut found = index.query_point(Vector2::new(2.0, 2.0));
+        found.sort();
+        assert_eq!(found, vec![small, huge]);
+
+        assert_eq!(index.query_rect(&rect(-20.0, 0.0, 5.0)).len(), 2);
+        assert_eq!(index.query_point(Vector2::new(5000.0, 0.0)), vec![]);
+
+        index.insert(small, rect(100.0, 100.0, 2.0));
+        assert_eq!(index.query_point(Vector2::new(2.0, 2.0)), vec![huge]);
+        assert_eq!(index.bounds(small), Some(rect(100.0, 100.0, 2.0)));
+
+        index.remove(huge);
+        index.remove(other);
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.query_rect(&rect(-1e6, -1e6, 2e6)), vec![small]);
+    }
+}
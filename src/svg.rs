@@ -0,0 +1,1114 @@
+use std::{collections::HashMap, fmt::{self, Write}};
+
+use cgmath::{InnerSpace, Vector2};
+
+use crate::{color::RDColor, export::{page_frame, write_scene, VectorWriter}, math::{RDAffine, RDRect}, path_builder::{ContinousPath, PathSegment, RDFillRule, RDGradient, RDGradientShape, RDPath, RDStroke}, scene::{RDNode, RDScene, RDTransform}, xml::{self, XmlElement}};
+
+fn matrix_attribute(matrix: &RDAffine) -> String {
+    format!("matrix({} {} {} {} {} {})", matrix.x.x, matrix.x.y, matrix.y.x, matrix.y.y, matrix.w.x, matrix.w.y)
+}
+
+//svg has no alpha in its colors, the opacity goes in its own attribute
+fn paint_attributes(kind: &str, color: &RDColor) -> String {
+    let color = color.to_srgb();
+    let mut attributes = format!("{kind}=\"#{:02x}{:02x}{:02x}\"", color.r, color.g, color.b);
+
+    if color.a < 255 {
+        write!(attributes, " {kind}-opacity=\"{}\"", color.a as f32 / 255.0).unwrap();
+    }
+
+    attributes
+}
+
+fn path_data(subpaths: &[&ContinousPath]) -> String {
+    let mut data = String::new();
+
+    for subpath in subpaths {
+        write!(data, "M{} {} ", subpath.start.x, subpath.start.y).unwrap();
+
+        for segment in subpath.segments.iter() {
+            match segment {
+                PathSegment::Line(point) => write!(data, "L{} {} ", point.x, point.y).unwrap(),
+                PathSegment::Cubic(a, b, point) => write!(data, "C{} {} {} {} {} {} ", a.x, a.y, b.x, b.y, point.x, point.y).unwrap(),
+            }
+        }
+
+        if subpath.closed {
+            data.push_str("Z ");
+        }
+    }
+
+    data.trim_end().to_string()
+}
+
+struct SvgWriter {
+    svg: String,
+    depth: usize,
+}
+
+impl SvgWriter {
+    fn indent(&self) -> String {
+        "  ".repeat(self.depth)
+    }
+}
+
+impl VectorWriter for SvgWriter {
+    fn begin_group(&mut self, transform: &RDAffine) {
+        let indent = self.indent();
+
+        if *transform == RDAffine::identity() {
+            writeln!(self.svg, "{indent}<g>").unwrap();
+        } else {
+            writeln!(self.svg, "{indent}<g transform=\"{}\">", matrix_attribute(transform)).unwrap();
+        }
+
+        self.depth += 1;
+    }
+
+    fn end_group(&mut self) {
+        self.depth -= 1;
+        writeln!(self.svg, "{}</g>", self.indent()).unwrap();
+    }
+
+    fn fill(&mut self, subpaths: &[&ContinousPath], color: &RDColor, rule: RDFillRule) {
+        let rule = match rule {
+            RDFillRule::Union => "",
+            RDFillRule::EvenOdd => " fill-rule=\"evenodd\"",
+        };
+
+        writeln!(self.svg, "{}<path d=\"{}\" {}{rule}/>", self.indent(), path_data(subpaths), paint_attributes("fill", color)).unwrap();
+    }
+
+    //the tessellated joins are always mitered, however sharp the corner
+    fn stroke(&mut self, subpaths: &[&ContinousPath], stroke: &RDStroke) {
+        writeln!(
+            self.svg,
+            "{}<path d=\"{}\" fill=\"none\" {} stroke-width=\"{}\" stroke-linejoin=\"miter\" stroke-miterlimit=\"100\"/>",
+            self.indent(),
+            path_data(subpaths),
+            paint_attributes("stroke", &stroke.color),
+            stroke.weight,
+        ).unwrap();
+    }
+}
+
+impl RDScene {
+    //vector version of what the first camera shows, paths keep their own outlines, curves and strokes
+    //the size comes from the last rendered surface, before the first render the scene's bounds are used instead
+    pub fn to_svg(&self) -> String {
+        let (size, view) = page_frame(self);
+
+        let mut writer = SvgWriter { svg: String::new(), depth: 1 };
+        writeln!(writer.svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">", size.x, size.y).unwrap();
+
+        writer.begin_group(&view);
+        write_scene(self, &mut writer);
+        writer.end_group();
+
+        writer.svg.push_str("</svg>\n");
+        writer.svg
+    }
+}
+
+//bezier handle length that makes a quarter circle
+const KAPPA: f32 = 0.552_284_8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SvgError {
+    //the document isn't well formed, offset is in bytes
+    Xml { offset: usize, message: &'static str },
+    //the root element isn't <svg>
+    NotSvg,
+    InvalidAttribute { element: String, attribute: String, value: String },
+}
+
+impl fmt::Display for SvgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SvgError::Xml { offset, message } => write!(f, "malformed svg at byte {offset}: {message}"),
+            SvgError::NotSvg => write!(f, "the root element isn't <svg>"),
+            SvgError::InvalidAttribute { element, attribute, value } => write!(f, "invalid {attribute} \"{value}\" on <{element}>"),
+        }
+    }
+}
+
+impl std::error::Error for SvgError {}
+
+fn invalid(element: &XmlElement, attribute: &str, value: &str) -> SvgError {
+    SvgError::InvalidAttribute {
+        element: element.local_name().to_string(),
+        attribute: attribute.to_string(),
+        value: value.to_string(),
+    }
+}
+
+//reads numbers, flags and commands the way path data packs them, "10-5.5.5" is three numbers
+struct Scanner<'a> {
+    text: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { text: text.as_bytes(), position: 0 }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.position).copied()
+    }
+
+    fn skip_separators(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_whitespace() || c == b',') {
+            self.position += 1;
+        }
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.skip_separators();
+        self.peek().is_none()
+    }
+
+    fn at_number(&mut self) -> bool {
+        self.skip_separators();
+        self.peek().is_some_and(|c| c.is_ascii_digit() || matches!(c, b'.' | b'-' | b'+'))
+    }
+
+    fn number(&mut self) -> Option<f32> {
+        self.skip_separators();
+        let start = self.position;
+        let digits = |scanner: &mut Self| {
+            let start = scanner.position;
+            while scanner.peek().is_some_and(|c| c.is_ascii_digit()) {
+                scanner.position += 1;
+            }
+            scanner.position > start
+        };
+
+        if matches!(self.peek(), Some(b'-' | b'+')) {
+            self.position += 1;
+        }
+
+        let whole = digits(self);
+        let fraction = self.peek() == Some(b'.') && {
+            self.position += 1;
+            digits(self)
+        };
+
+        if !whole && !fraction {
+            self.position = start;
+            return None;
+        }
+
+        //an exponent only counts if digits follow, "1em" is a number and a unit
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            let mantissa_end = self.position;
+            self.position += 1;
+            if matches!(self.peek(), Some(b'-' | b'+')) {
+                self.position += 1;
+            }
+            if !digits(self) {
+                self.position = mantissa_end;
+            }
+        }
+
+        std::str::from_utf8(&self.text[start..self.position]).ok()?.parse().ok()
+    }
+
+    fn flag(&mut self) -> Option<bool> {
+        self.skip_separators();
+        let flag = match self.peek()? {
+            b'0' => false,
+            b'1' => true,
+            _ => return None,
+        };
+        self.position += 1;
+        Some(flag)
+    }
+
+    fn command(&mut self) -> Option<u8> {
+        self.skip_separators();
+        let command = self.peek().filter(|c| c.is_ascii_alphabetic())?;
+        self.position += 1;
+        Some(command)
+    }
+
+    fn rest(&self) -> &'a str {
+        std::str::from_utf8(&self.text[self.position..]).unwrap_or_default()
+    }
+}
+
+fn parse_numbers(text: &str) -> Option<Vec<f32>> {
+    let mut scanner = Scanner::new(text);
+    let mut numbers = vec![];
+
+    while !scanner.at_end() {
+        numbers.push(scanner.number()?);
+    }
+
+    Some(numbers)
+}
+
+//user units, percentages are of the reference length
+fn parse_length(text: &str, reference: f32) -> Option<f32> {
+    let mut scanner = Scanner::new(text.trim());
+    let value = scanner.number()?;
+
+    let factor = match scanner.rest().trim() {
+        "" | "px" => 1.0,
+        "%" => reference / 100.0,
+        "pt" => 4.0 / 3.0,
+        "pc" => 16.0,
+        "mm" => 96.0 / 25.4,
+        "cm" => 96.0 / 2.54,
+        "in" => 96.0,
+        //against the default font size
+        "em" => 16.0,
+        "ex" => 8.0,
+        _ => return None,
+    };
+
+    Some(value * factor)
+}
+
+//plain numbers or percentages, clamped to 0..1
+fn parse_fraction(text: &str) -> Option<f32> {
+    let text = text.trim();
+    let value = match text.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f32>().ok()? / 100.0,
+        None => text.parse().ok()?,
+    };
+
+    Some(value.clamp(0.0, 1.0))
+}
+
+//as written, in srgb
+fn parse_color(text: &str) -> Option<RDColor> {
+    let text = text.trim();
+
+    if let Some(hex) = text.strip_prefix('#') {
+        let digits: Vec<u8> = hex.chars().map(|c| c.to_digit(16).map(|digit| digit as u8)).collect::<Option<_>>()?;
+
+        return match digits.len() {
+            3 => Some(RDColor::new(digits[0] * 17, digits[1] * 17, digits[2] * 17, 255)),
+            6 => Some(RDColor::new(digits[0] * 16 + digits[1], digits[2] * 16 + digits[3], digits[4] * 16 + digits[5], 255)),
+            _ => None,
+        };
+    }
+
+    if let Some(arguments) = text.strip_prefix("rgb(").or(text.strip_prefix("rgba(")).and_then(|rest| rest.strip_suffix(')')) {
+        let channels: Vec<_> = arguments.split([',', ' ', '/']).filter(|part| !part.is_empty()).collect();
+        if !(3..=4).contains(&channels.len()) {
+            return None;
+        }
+
+        let channel = |text: &str| match text.strip_suffix('%') {
+            Some(percent) => percent.parse::<f32>().ok().map(|value| value * 2.55),
+            None => text.parse::<f32>().ok(),
+        }.map(|value| value.round().clamp(0.0, 255.0) as u8);
+
+        let alpha = channels.get(3).map_or(Some(1.0), |alpha| parse_fraction(alpha))?;
+        return Some(RDColor::new(channel(channels[0])?, channel(channels[1])?, channel(channels[2])?, (alpha * 255.0).round() as u8));
+    }
+
+    let (r, g, b) = match text.to_ascii_lowercase().as_str() {
+        "black" => (0, 0, 0),
+        "white" => (255, 255, 255),
+        "red" => (255, 0, 0),
+        "green" => (0, 128, 0),
+        "lime" => (0, 255, 0),
+        "blue" => (0, 0, 255),
+        "yellow" => (255, 255, 0),
+        "cyan" | "aqua" => (0, 255, 255),
+        "magenta" | "fuchsia" => (255, 0, 255),
+        "gray" | "grey" => (128, 128, 128),
+        "silver" => (192, 192, 192),
+        "maroon" => (128, 0, 0),
+        "olive" => (128, 128, 0),
+        "teal" => (0, 128, 128),
+        "navy" => (0, 0, 128),
+        "purple" => (128, 0, 128),
+        "orange" => (255, 165, 0),
+        "transparent" => return Some(RDColor::TRANSPARENT),
+        _ => return None,
+    };
+
+    Some(RDColor::new(r, g, b, 255))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Paint {
+    None,
+    Color(RDColor),
+    CurrentColor,
+    //the fallback is used when nothing has the id
+    Gradient { id: String, fallback: Option<RDColor> },
+}
+
+fn parse_paint(text: &str) -> Option<Paint> {
+    let text = text.trim();
+
+    match text {
+        "none" => Some(Paint::None),
+        "currentColor" => Some(Paint::CurrentColor),
+        _ => match text.strip_prefix("url(") {
+            Some(rest) => {
+                let (reference, fallback) = rest.split_once(')')?;
+                let id = reference.trim().trim_matches(['"', '\'']).strip_prefix('#')?;
+                let fallback = fallback.trim();
+
+                Some(Paint::Gradient {
+                    id: id.to_string(),
+                    fallback: if fallback.is_empty() || fallback == "none" { None } else { Some(parse_color(fallback)?) },
+                })
+            }
+            None => parse_color(text).map(Paint::Color),
+        },
+    }
+}
+
+//a transform list applies right to left, like the matrices multiplied in the order they are written
+fn parse_transform(text: &str) -> Option<RDAffine> {
+    let mut matrix = RDAffine::identity();
+    let mut rest = text.trim();
+
+    while !rest.is_empty() {
+        let (name, arguments) = rest.split_once('(')?;
+        let (arguments, remaining) = arguments.split_once(')')?;
+        let values = parse_numbers(arguments)?;
+
+        let transform = match (name.trim(), values.as_slice()) {
+            ("matrix", [a, b, c, d, e, f]) => RDAffine::new(Vector2::new(*a, *b), Vector2::new(*c, *d), Vector2::new(*e, *f)),
+            ("translate", [x]) => RDAffine::from_translation(Vector2::new(*x, 0.0)),
+            ("translate", [x, y]) => RDAffine::from_translation(Vector2::new(*x, *y)),
+            ("scale", [s]) => RDAffine::from_scale(Vector2::new(*s, *s)),
+            ("scale", [x, y]) => RDAffine::from_scale(Vector2::new(*x, *y)),
+            ("rotate", [angle]) => RDAffine::from_rotation(angle.to_radians()),
+            ("rotate", [angle, x, y]) => {
+                let center = Vector2::new(*x, *y);
+                RDAffine::from_translation(center) * RDAffine::from_rotation(angle.to_radians()) * RDAffine::from_translation(-center)
+            }
+            ("skewX", [angle]) => RDAffine::from_skew(Vector2::new(angle.to_radians(), 0.0)),
+            ("skewY", [angle]) => RDAffine::from_skew(Vector2::new(0.0, angle.to_radians())),
+            _ => return None,
+        };
+
+        matrix = matrix * transform;
+        rest = remaining.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+    }
+
+    Some(matrix)
+}
+
+//endpoint arc to cubics, following the svg spec's conversion to a center parameterization
+fn arc_to_cubics(from: Vector2<f32>, radii: Vector2<f32>, angle: f32, large_arc: bool, sweep: bool, to: Vector2<f32>) -> Vec<[Vector2<f32>; 3]> {
+    let (mut rx, mut ry) = (radii.x.abs(), radii.y.abs());
+    let rotation = RDAffine::from_rotation(angle.to_radians());
+    let unrotated = rotation.inverse().unwrap().transform_vector((from - to) * 0.5);
+
+    //radii too small to reach the end point are scaled up until they just do
+    let lambda = (unrotated.x / rx).powi(2) + (unrotated.y / ry).powi(2);
+    if lambda > 1.0 {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+
+    let (x2, y2) = (unrotated.x * unrotated.x, unrotated.y * unrotated.y);
+    let numerator = rx * rx * ry * ry - rx * rx * y2 - ry * ry * x2;
+    let denominator = rx * rx * y2 + ry * ry * x2;
+    let sign = if large_arc == sweep { -1.0 } else { 1.0 };
+    let coefficient = sign * (numerator / denominator).max(0.0).sqrt();
+
+    let center_unrotated = Vector2::new(coefficient * rx * unrotated.y / ry, -coefficient * ry * unrotated.x / rx);
+    let center = rotation.transform_vector(center_unrotated) + (from + to) * 0.5;
+
+    let angle_between = |u: Vector2<f32>, v: Vector2<f32>| u.perp_dot(v).atan2(u.dot(v));
+    let start_vector = Vector2::new((unrotated.x - center_unrotated.x) / rx, (unrotated.y - center_unrotated.y) / ry);
+    let end_vector = Vector2::new((-unrotated.x - center_unrotated.x) / rx, (-unrotated.y - center_unrotated.y) / ry);
+
+    let start_angle = angle_between(Vector2::new(1.0, 0.0), start_vector);
+    let mut sweep_angle = angle_between(start_vector, end_vector);
+    if !sweep && sweep_angle > 0.0 {
+        sweep_angle -= std::f32::consts::TAU;
+    } else if sweep && sweep_angle < 0.0 {
+        sweep_angle += std::f32::consts::TAU;
+    }
+
+    //quarter turns at most, the cubic drifts from the ellipse beyond that
+    let segments = (sweep_angle.abs() / std::f32::consts::FRAC_PI_2).ceil().max(1.0) as usize;
+    let step = sweep_angle / segments as f32;
+    let handle = 4.0 / 3.0 * (step / 4.0).tan();
+
+    let ellipse = RDAffine::from_translation(center) * rotation * RDAffine::from_scale(Vector2::new(rx, ry));
+    let unit = |angle: f32| Vector2::new(angle.cos(), angle.sin());
+    let tangent = |angle: f32| Vector2::new(-angle.sin(), angle.cos());
+
+    let mut cubics: Vec<_> = (0..segments).map(|i| {
+        let (a, b) = (start_angle + step * i as f32, start_angle + step * (i + 1) as f32);
+
+        [
+            ellipse.transform_point(unit(a) + tangent(a) * handle),
+            ellipse.transform_point(unit(b) - tangent(b) * handle),
+            ellipse.transform_point(unit(b)),
+        ]
+    }).collect();
+
+    if let Some(last) = cubics.last_mut() {
+        last[2] = to;
+    }
+
+    cubics
+}
+
+//svg path data onto the builder, everything up to the first error is kept like browsers do
+fn parse_path_data(data: &str) -> RDPath {
+    let mut scanner = Scanner::new(data);
+    let mut path = RDPath::new();
+
+    let mut current = Vector2::new(0.0, 0.0);
+    let mut start = current;
+    //whether the builder has a subpath that can still be drawn onto
+    let mut open = false;
+    //the last control point, for the smooth curve commands to mirror
+    let mut last_cubic: Option<Vector2<f32>> = None;
+    let mut last_quad: Option<Vector2<f32>> = None;
+    let mut command = None;
+
+    loop {
+        if scanner.at_end() {
+            break;
+        }
+
+        //numbers without a command repeat the last one, after a move they are lines
+        if !scanner.at_number() {
+            command = scanner.command();
+        } else if let Some(previous) = command {
+            command = match previous {
+                b'M' => Some(b'L'),
+                b'm' => Some(b'l'),
+                b'Z' | b'z' => None,
+                other => Some(other),
+            };
+        }
+
+        let Some(letter) = command else { break };
+        let relative = letter.is_ascii_lowercase();
+        let origin = if relative { current } else { Vector2::new(0.0, 0.0) };
+
+        let point = |scanner: &mut Scanner| Some(origin + Vector2::new(scanner.number()?, scanner.number()?));
+
+        //a path has to start with a move
+        if !matches!(letter, b'M' | b'm') && !open && path.subpaths().is_empty() {
+            break;
+        }
+        if !matches!(letter, b'M' | b'm' | b'Z' | b'z') && !open {
+            path = path.to(current.x, current.y);
+            open = true;
+        }
+
+        let (mut cubic, mut quad) = (None, None);
+
+        match letter.to_ascii_uppercase() {
+            b'M' => {
+                let Some(to) = point(&mut scanner) else { break };
+                path = path.to(to.x, to.y);
+                (current, start, open) = (to, to, true);
+            }
+            b'L' => {
+                let Some(to) = point(&mut scanner) else { break };
+                path = path.line(to.x, to.y);
+                current = to;
+            }
+            b'H' | b'V' => {
+                let Some(value) = scanner.number() else { break };
+                let horizontal = letter.eq_ignore_ascii_case(&b'H');
+
+                current = match (horizontal, relative) {
+                    (true, true) => Vector2::new(current.x + value, current.y),
+                    (true, false) => Vector2::new(value, current.y),
+                    (false, true) => Vector2::new(current.x, current.y + value),
+                    (false, false) => Vector2::new(current.x, value),
+                };
+                path = path.line(current.x, current.y);
+            }
+            b'C' | b'S' => {
+                let control_a = if letter.eq_ignore_ascii_case(&b'S') {
+                    current * 2.0 - last_cubic.unwrap_or(current)
+                } else {
+                    let Some(control) = point(&mut scanner) else { break };
+                    control
+                };
+                let (Some(control_b), Some(to)) = (point(&mut scanner), point(&mut scanner)) else { break };
+
+                path = path.cubic(control_a.x, control_a.y, control_b.x, control_b.y, to.x, to.y);
+                (current, cubic) = (to, Some(control_b));
+            }
+            b'Q' | b'T' => {
+                let control = if letter.eq_ignore_ascii_case(&b'T') {
+                    current * 2.0 - last_quad.unwrap_or(current)
+                } else {
+                    let Some(control) = point(&mut scanner) else { break };
+                    control
+                };
+                let Some(to) = point(&mut scanner) else { break };
+
+                path = path.quad(control.x, control.y, to.x, to.y);
+                (current, quad) = (to, Some(control));
+            }
+            b'A' => {
+                let (Some(rx), Some(ry), Some(angle)) = (scanner.number(), scanner.number(), scanner.number()) else { break };
+                let (Some(large_arc), Some(sweep)) = (scanner.flag(), scanner.flag()) else { break };
+                let Some(to) = point(&mut scanner) else { break };
+
+                if rx == 0.0 || ry == 0.0 {
+                    path = path.line(to.x, to.y);
+                } else if to != current {
+                    for [control_a, control_b, end] in arc_to_cubics(current, Vector2::new(rx, ry), angle, large_arc, sweep, to) {
+                        path = path.cubic(control_a.x, control_a.y, control_b.x, control_b.y, end.x, end.y);
+                    }
+                }
+                current = to;
+            }
+            b'Z' => {
+                if open {
+                    path = path.close();
+                }
+                (current, open) = (start, false);
+            }
+            _ => break,
+        }
+
+        (last_cubic, last_quad) = (cubic, quad);
+    }
+
+    path
+}
+
+//presentation attributes first so the style attribute overrides them
+fn properties(element: &XmlElement) -> Vec<(&str, &str)> {
+    let mut properties: Vec<_> = element.attributes.iter().map(|(name, value)| (name.as_str(), value.as_str())).collect();
+
+    if let Some(style) = element.attribute("style") {
+        properties.extend(style.split(';').filter_map(|declaration| {
+            let (name, value) = declaration.split_once(':')?;
+            Some((name.trim(), value.trim().trim_end_matches("!important").trim_end()))
+        }));
+    }
+
+    properties
+}
+
+//the inherited properties, opacity is multiplied down the tree instead of making group layers
+#[derive(Clone)]
+struct Style {
+    fill: Paint,
+    stroke: Paint,
+    stroke_width: f32,
+    fill_rule: RDFillRule,
+    fill_opacity: f32,
+    stroke_opacity: f32,
+    opacity: f32,
+    color: RDColor,
+    visible: bool,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            fill: Paint::Color(RDColor::BLACK),
+            stroke: Paint::None,
+            stroke_width: 1.0,
+            //nonzero in svg, the same for the usual shapes that don't overlap themselves
+            fill_rule: RDFillRule::Union,
+            fill_opacity: 1.0,
+            stroke_opacity: 1.0,
+            opacity: 1.0,
+            color: RDColor::BLACK,
+            visible: true,
+        }
+    }
+}
+
+fn with_opacity(color: &RDColor, opacity: f32) -> RDColor {
+    RDColor { a: (color.a as f32 * opacity).round() as u8, ..color.to_linear() }
+}
+
+struct SvgLoader<'a> {
+    gradients: HashMap<&'a str, &'a XmlElement>,
+    //the size percentages are taken of
+    viewport: Vector2<f32>,
+}
+
+impl<'a> SvgLoader<'a> {
+    fn style(&self, element: &XmlElement, parent: &Style) -> Result<Style, SvgError> {
+        let mut style = parent.clone();
+        let diagonal = (self.viewport.x.powi(2) + self.viewport.y.powi(2)).sqrt() / std::f32::consts::SQRT_2;
+
+        for (name, value) in properties(element) {
+            if value == "inherit" {
+                continue;
+            }
+
+            let error = || invalid(element, name, value);
+
+            match name {
+                "fill" => style.fill = parse_paint(value).ok_or_else(error)?,
+                "stroke" => style.stroke = parse_paint(value).ok_or_else(error)?,
+                "stroke-width" => style.stroke_width = parse_length(value, diagonal).ok_or_else(error)?,
+                "fill-rule" => style.fill_rule = match value {
+                    "nonzero" => RDFillRule::Union,
+                    "evenodd" => RDFillRule::EvenOdd,
+                    _ => return Err(error()),
+                },
+                "fill-opacity" => style.fill_opacity = parse_fraction(value).ok_or_else(error)?,
+                "stroke-opacity" => style.stroke_opacity = parse_fraction(value).ok_or_else(error)?,
+                "opacity" => style.opacity = parent.opacity * parse_fraction(value).ok_or_else(error)?,
+                "color" => style.color = parse_color(value).ok_or_else(error)?,
+                "visibility" => style.visible = value == "visible",
+                _ => (),
+            }
+        }
+
+        Ok(style)
+    }
+
+    fn length(&self, element: &XmlElement, name: &str, reference: f32) -> Result<f32, SvgError> {
+        match element.attribute(name) {
+            Some(value) => parse_length(value, reference).ok_or_else(|| invalid(element, name, value)),
+            None => Ok(0.0),
+        }
+    }
+
+    //None when the shape has nothing to draw, like a rect without a size
+    fn shape(&self, element: &XmlElement) -> Result<Option<RDPath>, SvgError> {
+        let (width, height) = (self.viewport.x, self.viewport.y);
+        let diagonal = (width * width + height * height).sqrt() / std::f32::consts::SQRT_2;
+
+        let path = match element.local_name() {
+            "path" => parse_path_data(element.attribute("d").unwrap_or_default()),
+            "rect" => {
+                let (x, y) = (self.length(element, "x", width)?, self.length(element, "y", height)?);
+                let (w, h) = (self.length(element, "width", width)?, self.length(element, "height", height)?);
+
+                if w <= 0.0 || h <= 0.0 {
+                    return Ok(None);
+                }
+
+                //a missing corner radius takes the other one
+                let rx = element.attribute("rx").map(|_| self.length(element, "rx", width)).transpose()?;
+                let ry = element.attribute("ry").map(|_| self.length(element, "ry", height)).transpose()?;
+                let rx = rx.or(ry).unwrap_or(0.0).clamp(0.0, w / 2.0);
+                let ry = ry.or(Some(rx)).unwrap_or(0.0).clamp(0.0, h / 2.0);
+
+                if rx == 0.0 || ry == 0.0 {
+                    RDPath::new().to(x, y).line(x + w, y).line(x + w, y + h).line(x, y + h).close()
+                } else {
+                    let (kx, ky) = (rx * KAPPA, ry * KAPPA);
+
+                    RDPath::new()
+                        .to(x + rx, y)
+                        .line(x + w - rx, y)
+                        .cubic(x + w - rx + kx, y, x + w, y + ry - ky, x + w, y + ry)
+                        .line(x + w, y + h - ry)
+                        .cubic(x + w, y + h - ry + ky, x + w - rx + kx, y + h, x + w - rx, y + h)
+                        .line(x + rx, y + h)
+                        .cubic(x + rx - kx, y + h, x, y + h - ry + ky, x, y + h - ry)
+                        .line(x, y + ry)
+                        .cubic(x, y + ry - ky, x + rx - kx, y, x + rx, y)
+                        .close()
+                }
+            }
+            "circle" | "ellipse" => {
+                let (cx, cy) = (self.length(element, "cx", width)?, self.length(element, "cy", height)?);
+                let (rx, ry) = match element.local_name() {
+                    "circle" => {
+                        let r = self.length(element, "r", diagonal)?;
+                        (r, r)
+                    }
+                    _ => (self.length(element, "rx", width)?, self.length(element, "ry", height)?),
+                };
+
+                if rx <= 0.0 || ry <= 0.0 {
+                    return Ok(None);
+                }
+
+                let (kx, ky) = (rx * KAPPA, ry * KAPPA);
+
+                RDPath::new()
+                    .to(cx + rx, cy)
+                    .cubic(cx + rx, cy + ky, cx + kx, cy + ry, cx, cy + ry)
+                    .cubic(cx - kx, cy + ry, cx - rx, cy + ky, cx - rx, cy)
+                    .cubic(cx - rx, cy - ky, cx - kx, cy - ry, cx, cy - ry)
+                    .cubic(cx + kx, cy - ry, cx + rx, cy - ky, cx + rx, cy)
+                    .close()
+            }
+            "line" => {
+                let (x1, y1) = (self.length(element, "x1", width)?, self.length(element, "y1", height)?);
+                let (x2, y2) = (self.length(element, "x2", width)?, self.length(element, "y2", height)?);
+
+                RDPath::new().to(x1, y1).line(x2, y2)
+            }
+            "polygon" | "polyline" => {
+                let value = element.attribute("points").unwrap_or_default();
+                let numbers = parse_numbers(value).ok_or_else(|| invalid(element, "points", value))?;
+
+                //an odd number out is dropped
+                let mut points = numbers.chunks_exact(2);
+                let Some([x, y]) = points.next() else { return Ok(None) };
+
+                let mut path = points.fold(RDPath::new().to(*x, *y), |path, point| path.line(point[0], point[1]));
+                if element.local_name() == "polygon" {
+                    path = path.close();
+                }
+                path
+            }
+            _ => return Ok(None),
+        };
+
+        Ok((!path.subpaths().is_empty()).then_some(path))
+    }
+
+    //stops and attributes can come from gradients further down an href chain
+    fn gradient(&self, id: &str, bounds: Option<RDRect>, opacity: f32) -> Option<RDGradient> {
+        let element = *self.gradients.get(id)?;
+
+        let chain: Vec<&XmlElement> = std::iter::successors(Some(element), |element| {
+            let reference = element.attribute("href").or(element.attribute("xlink:href"))?;
+            self.gradients.get(reference.strip_prefix('#')?).copied()
+        }).take(16).collect();
+
+        let attribute = |name: &str| chain.iter().find_map(|element| element.attribute(name));
+        let bounding_box = attribute("gradientUnits") != Some("userSpaceOnUse");
+
+        //bounding box units are fractions of the box, percentages included
+        let coordinate = |name: &str, default: &str, reference: f32| {
+            let value = attribute(name).unwrap_or(default);
+            if bounding_box { parse_fraction(value).or(value.parse().ok()) } else { parse_length(value, reference) }.unwrap_or(0.0)
+        };
+
+        let (width, height) = (self.viewport.x, self.viewport.y);
+        let diagonal = (width * width + height * height).sqrt() / std::f32::consts::SQRT_2;
+
+        let shape = match element.local_name() {
+            "linearGradient" => RDGradientShape::Linear {
+                start: Vector2::new(coordinate("x1", "0%", width), coordinate("y1", "0%", height)),
+                end: Vector2::new(coordinate("x2", "100%", width), coordinate("y2", "0%", height)),
+            },
+            _ => {
+                let center = Vector2::new(coordinate("cx", "50%", width), coordinate("cy", "50%", height));
+                let focus = Vector2::new(
+                    attribute("fx").map_or(center.x, |_| coordinate("fx", "50%", width)),
+                    attribute("fy").map_or(center.y, |_| coordinate("fy", "50%", height)),
+                );
+
+                RDGradientShape::Radial { center, radius: coordinate("r", "50%", diagonal), focus }
+            }
+        };
+
+        let stops_element = chain.iter().find(|element| element.children.iter().any(|child| child.local_name() == "stop"))?;
+        let mut stops: Vec<(f32, RDColor)> = vec![];
+
+        for stop in stops_element.children.iter().filter(|child| child.local_name() == "stop") {
+            let properties = properties(stop);
+            let property = |name: &str| properties.iter().rev().find(|(key, _)| *key == name).map(|(_, value)| *value);
+
+            //offsets never go backwards
+            let offset = stop.attribute("offset").and_then(parse_fraction).unwrap_or(0.0);
+            let offset = stops.last().map_or(offset, |(previous, _)| offset.max(*previous));
+
+            let color = property("stop-color").and_then(parse_color).unwrap_or(RDColor::BLACK);
+            let stop_opacity = property("stop-opacity").and_then(parse_fraction).unwrap_or(1.0);
+
+            stops.push((offset, with_opacity(&color, stop_opacity * opacity)));
+        }
+
+        let gradient_transform = attribute("gradientTransform").and_then(parse_transform).unwrap_or_default();
+        let transform = match (bounding_box, bounds) {
+            (true, Some(bounds)) => RDAffine::from_translation(bounds.min) * RDAffine::from_scale(Vector2::new(bounds.width(), bounds.height())) * gradient_transform,
+            _ => gradient_transform,
+        };
+
+        Some(RDGradient { shape, stops, transform })
+    }
+
+    //the color a paint stands for, gradients on strokes fall back to their first stop
+    fn apply_paint(&self, mut path: RDPath, style: &Style) -> RDPath {
+        let bounds = RDRect::from_points(path.subpaths().iter().flat_map(|subpath| subpath.points.iter().copied()));
+
+        let resolve = |paint: &Paint, opacity: f32| -> (Option<RDColor>, Option<RDGradient>) {
+            match paint {
+                Paint::None => (None, None),
+                Paint::Color(color) => (Some(with_opacity(color, opacity)), None),
+                Paint::CurrentColor => (Some(with_opacity(&style.color, opacity)), None),
+                Paint::Gradient { id, fallback } => match self.gradient(id, bounds, opacity) {
+                    Some(gradient) => (gradient.stops.first().map(|(_, color)| color.clone()), Some(gradient)),
+                    None => (fallback.as_ref().map(|color| with_opacity(color, opacity)), None),
+                },
+            }
+        };
+
+        let (fill, gradient) = resolve(&style.fill, style.fill_opacity * style.opacity);
+        path.color = fill.unwrap_or(RDColor::TRANSPARENT);
+        path.gradient = gradient;
+        path.fill_rule = style.fill_rule;
+        path.stroke_open = true;
+
+        let (stroke, _) = resolve(&style.stroke, style.stroke_opacity * style.opacity);
+        path.stroke = RDStroke {
+            weight: if stroke.is_some() { style.stroke_width } else { 0.0 },
+            color: stroke.unwrap_or(RDColor::TRANSPARENT),
+        };
+
+        path
+    }
+
+    fn load_children(&self, element: &XmlElement, style: &Style, node: &mut RDNode) -> Result<(), SvgError> {
+        for child in element.children.iter() {
+            if let Some(child) = self.load_element(child, style)? {
+                node.children.push(child);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn load_element(&self, element: &XmlElement, parent: &Style) -> Result<Option<RDNode>, SvgError> {
+        if properties(element).iter().any(|(name, value)| *name == "display" && *value == "none") {
+            return Ok(None);
+        }
+
+        let style = self.style(element, parent)?;
+
+        let mut node = match element.local_name() {
+            //a nested svg is treated as a plain group, its own viewport isn't applied
+            "g" | "a" | "svg" | "switch" => {
+                let mut node = RDNode::new();
+                self.load_children(element, &style, &mut node)?;
+                node
+            }
+            "path" | "rect" | "circle" | "ellipse" | "line" | "polygon" | "polyline" => {
+                let Some(path) = self.shape(element)? else { return Ok(None) };
+                self.apply_paint(path, &style).to_node()
+            }
+            //definitions, text and everything else outside the supported subset
+            _ => return Ok(None),
+        };
+
+        if let Some(value) = element.attribute("transform") {
+            let matrix = parse_transform(value).ok_or_else(|| invalid(element, "transform", value))?;
+            node.transform = RDTransform::from_matrix(&matrix);
+        }
+
+        if let Some(id) = element.attribute("id") {
+            node = node.named(id);
+        }
+
+        node.visible = style.visible;
+        Ok(Some(node))
+    }
+}
+
+impl RDScene {
+    //builds a node tree from an svg document, one node per group and shape with each shape's path below it
+    //the drawing is flipped to have y pointing up and sits with its bottom left corner at the node's origin,
+    //one user unit per world unit
+    pub fn load_svg(svg: &str) -> Result<RDNode, SvgError> {
+        let root = xml::parse(svg).map_err(|error| SvgError::Xml { offset: error.offset, message: error.message })?;
+
+        if root.local_name() != "svg" {
+            return Err(SvgError::NotSvg);
+        }
+
+        let view_box = match root.attribute("viewBox") {
+            Some(value) => {
+                let numbers = parse_numbers(value).filter(|numbers| numbers.len() == 4 && numbers[2] > 0.0 && numbers[3] > 0.0);
+                Some(numbers.ok_or_else(|| invalid(&root, "viewBox", value))?)
+            }
+            None => None,
+        };
+
+        //width and height default to the view box, percentages are of it too
+        let view_size = view_box.as_ref().map_or(Vector2::new(100.0, 100.0), |view_box| Vector2::new(view_box[2], view_box[3]));
+        let size = |name: &str, reference: f32| match root.attribute(name) {
+            Some(value) => parse_length(value, reference).filter(|length| *length > 0.0).ok_or_else(|| invalid(&root, name, value)),
+            None => Ok(reference),
+        };
+        let (width, height) = (size("width", view_size.x)?, size("height", view_size.y)?);
+
+        let loader = SvgLoader {
+            gradients: root.descendants().into_iter()
+                .filter(|element| matches!(element.local_name(), "linearGradient" | "radialGradient"))
+                .filter_map(|element| Some((element.attribute("id")?, element)))
+                .collect(),
+            viewport: view_size,
+        };
+
+        let mut node = RDNode::new();
+        let style = loader.style(&root, &Style::default())?;
+        loader.load_children(&root, &style, &mut node)?;
+
+        //the view box is fitted into the size keeping its aspect ratio, centered like the default xMidYMid meet
+        let view = match view_box {
+            Some(view_box) => {
+                let scale = (width / view_box[2]).min(height / view_box[3]);
+                let offset = (Vector2::new(width, height) - view_size * scale) * 0.5;
+
+                RDAffine::from_translation(offset) * RDAffine::from_scale(Vector2::new(scale, scale)) * RDAffine::from_translation(-Vector2::new(view_box[0], view_box[1]))
+            }
+            None => RDAffine::identity(),
+        };
+        let flip = RDAffine::from_translation(Vector2::new(0.0, height)) * RDAffine::from_scale(Vector2::new(1.0, -1.0));
+        node.transform = RDTransform::from_matrix(&(flip * view));
+
+        if let Some(id) = root.attribute("id") {
+            node = node.named(id);
+        }
+
+        Ok(node)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{InnerSpace, Vector2};
+
+    use crate::{color::RDColor, math::RDAffine, path_builder::{RDPath, RDStroke}, scene::{RDMeshSource, RDNode, RDScene, RDTransform}};
+
+    use super::SvgError;
+
+    //the path a node made by RDPath::to_node was built from
+    fn source(node: &RDNode) -> &RDPath {
+        match node.children[0].mesh.as_ref().and_then(|mesh| mesh.source.as_ref()) {
+            Some(RDMeshSource::Fill(path)) => path,
+            _ => panic!("not a path node"),
+        }
+    }
+
+    fn assert_near(a: Vector2<f32>, b: Vector2<f32>) {
+        assert!((a.x - b.x).abs() < 1e-3 && (a.y - b.y).abs() < 1e-3, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn test_to_svg() {
+        let mut scene = RDScene::new(10);
+
+        let mut node = RDPath::new()
+            .to(0.0, 0.0)
+            .line(2.0, 0.0)
+            .line(2.0, 1.0)
+            .close()
+            .to(5.0, 5.0)
+            .line(6.0, 5.0)
+            .quad(6.0, 6.0, 5.0, 6.0)
+            .stroke(RDStroke { weight: 0.5, color: RDColor::new(0, 0, 255, 128) })
+            .fill(RDColor::RED)
+            .to_node();
+        node.transform.position = Vector2::new(3.0, 4.0);
+        scene.add_root(node);
+
+        //before any render the bounds decide the size and the y axis is flipped
+        let svg = scene.to_svg();
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.contains("<g transform=\"matrix(5 0 0 -5"));
+        assert!(svg.contains("<g transform=\"matrix(1 0 0 1 3 4)\">"));
+        assert!(svg.contains("<path d=\"M0 0 L2 0 L2 1 Z M5 5 L6 5 C6 5.6666665 5.6666665 6 5 6\" fill=\"#ff0000\"/>"));
+
+        //the open subpath isn't stroked, just like on screen
+        assert!(svg.contains("<path d=\"M0 0 L2 0 L2 1 Z\" fill=\"none\" stroke=\"#0000ff\" stroke-opacity=\"0.5019608\" stroke-width=\"0.5\""));
+        assert_eq!(svg.matches("<g").count(), svg.matches("</g>").count());
+
+        //once rendered the first camera maps the world onto the surface
+        scene.output_gfx(200.0, 400.0);
+        let svg = scene.to_svg();
+        assert!(svg.contains("width=\"400\" height=\"200\""));
+        assert!(svg.contains("<g transform=\"matrix(5 0 0 -5 200 100)\">"));
+    }
+
+    #[test]
+    fn test_load_svg() {
+        let node = RDScene::load_svg(r##"<?xml version="1.0"?>
+            <svg xmlns="http://www.w3.org/2000/svg" width="20" height="10" viewBox="0 0 40 20">
+                <defs>
+                    <linearGradient id="fade"><stop offset="0" stop-color="#000"/><stop offset="1" stop-color="#fff"/></linearGradient>
+                </defs>
+                <g id="group" transform="translate(10 2) rotate(90)" opacity="0.5">
+                    <rect width="4" height="2" fill="url(#fade)"/>
+                    <circle cx="1" cy="1" r="1" style="fill: red; stroke: blue; stroke-width: 2"/>
+                </g>
+                <path d="m1 1 h2 v2 h-2 z m5 0 A5 5 0 0 1 16 1"/>
+                <polygon points="0,0 4,0 4,4" fill="none"/>
+                <line x1="0" y1="0" x2="3" y2="4" stroke="#00ff00"/>
+                <rect width="5" height="5" display="none"/>
+            </svg>
+        "##).unwrap();
+
+        //the view box is scaled into the size and the y axis flipped
+        assert_near(node.transform.to_matrix().transform_point(Vector2::new(40.0, 20.0)), Vector2::new(20.0, 0.0));
+        assert_near(node.transform.to_matrix().transform_point(Vector2::new(0.0, 0.0)), Vector2::new(0.0, 10.0));
+        assert_eq!(node.children.len(), 4);
+
+        let group = &node.children[0];
+        assert_eq!(group.name.as_deref(), Some("group"));
+        assert_near(group.transform.to_matrix().transform_point(Vector2::new(1.0, 0.0)), Vector2::new(10.0, 3.0));
+
+        //the gradient spans the rect's bounds and the group's opacity carries down
+        let rect = source(&group.children[0]);
+        let gradient = rect.gradient.as_ref().unwrap();
+        assert_eq!(gradient.color_at(Vector2::new(0.0, 1.0)), RDColor::new(0, 0, 0, 128));
+        assert_eq!(gradient.color_at(Vector2::new(4.0, 1.0)), RDColor::new(255, 255, 255, 128));
+        assert_eq!(rect.color, RDColor::new(0, 0, 0, 128));
+
+        let circle = source(&group.children[1]);
+        assert_eq!(circle.color, RDColor::new(255, 0, 0, 128));
+        assert_eq!((circle.stroke.weight, circle.stroke.color.clone()), (2.0, RDColor::new(0, 0, 255, 128)));
+
+        //relative commands, a move after a close and a half circle arc
+        let path = source(&node.children[1]);
+        let subpaths = path.subpaths();
+        assert_eq!(subpaths.len(), 2);
+        assert!(subpaths[0].closed && !subpaths[1].closed);
+        assert_eq!(subpaths[0].points, [Vector2::new(1.0, 1.0), Vector2::new(3.0, 1.0), Vector2::new(3.0, 3.0), Vector2::new(1.0, 3.0)]);
+        assert_eq!(subpaths[1].start, Vector2::new(6.0, 1.0));
+        assert!(subpaths[1].points.iter().all(|point| ((point - Vector2::new(11.0, 1.0)).magnitude() - 5.0).abs() < 0.05 && point.y <= 1.001));
+        assert_near(*subpaths[1].points.last().unwrap(), Vector2::new(16.0, 1.0));
+
+        let polygon = source(&node.children[2]);
+        assert!(polygon.subpaths()[0].closed);
+        assert_eq!((polygon.color.clone(), polygon.stroke.weight), (RDColor::TRANSPARENT, 0.0));
+
+        let line = source(&node.children[3]);
+        assert!(!line.subpaths()[0].closed);
+        assert_eq!((line.stroke.weight, line.stroke.color.clone()), (1.0, RDColor::new(0, 255, 0, 255)));
+
+        //svg strokes open subpaths, a path built by hand leaves them unstroked
+        assert!(!node.children[3].children[1].mesh.as_ref().unwrap().vao.indicies.is_empty());
+        assert!(line.clone().stroke_open(false).to_node().children[1].mesh.as_ref().unwrap().vao.indicies.is_empty());
+
+        assert_eq!(RDScene::load_svg("<svg><rect fill=\"nope\"/></svg>").err(), Some(SvgError::InvalidAttribute {
+            element: "rect".to_string(),
+            attribute: "fill".to_string(),
+            value: "nope".to_string(),
+        }));
+        assert_eq!(RDScene::load_svg("<html/>").err(), Some(SvgError::NotSvg));
+        assert!(matches!(RDScene::load_svg("<svg>"), Err(SvgError::Xml { .. })));
+    }
+
+    #[test]
+    fn test_transform_from_matrix() {
+        let transform = RDTransform {
+            position: Vector2::new(3.0, -2.0),
+            rotation: 0.7,
+            scale: Vector2::new(2.0, 0.5),
+            skew: Vector2::new(0.3, 0.0),
+            ..Default::default()
+        };
+        let matrix = transform.to_matrix();
+        let decomposed = RDTransform::from_matrix(&matrix).to_matrix();
+
+        for point in [Vector2::new(1.0, 0.0), Vector2::new(0.0, 1.0), Vector2::new(-2.0, 5.0)] {
+            assert_near(decomposed.transform_point(point), matrix.transform_point(point));
+        }
+
+        let mirrored = RDAffine::from_scale(Vector2::new(1.0, -1.0));
+        assert_near(RDTransform::from_matrix(&mirrored).to_matrix().transform_point(Vector2::new(2.0, 3.0)), Vector2::new(2.0, -3.0));
+    }
+}
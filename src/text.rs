@@ -0,0 +1,201 @@
+use std::sync::Arc;
+
+use cgmath::Vector2;
+
+use crate::{atlas::{RDGlyphAtlas, GLYPH_ATLAS_TEXTURE}, color::RDColor, font::{RDFont, RDGlyph}, layout::{RDAlign, RDLayoutOptions, RDTextLayout, RDWrap}, path_builder::{RDAntiAliasing, RDFillRule, RDPath, RDStroke}, scene::{RDMesh, RDMeshSource, RDNode, VAO}, RDVertex};
+
+//text as filled glyph outlines, laid out by RDTextLayout with the first baseline at y 0
+pub struct RDText<'a> {
+    font: &'a RDFont,
+    text: String,
+    //of an em, in path units
+    size: f32,
+    color: RDColor,
+    layout: RDLayoutOptions,
+    anti_aliasing: RDAntiAliasing,
+}
+
+impl<'a> RDText<'a> {
+    pub fn new(font: &'a RDFont, text: &str) -> Self {
+        Self {
+            font,
+            text: text.to_string(),
+            size: 1.0,
+            color: RDColor::WHITE,
+            layout: RDLayoutOptions::default(),
+            anti_aliasing: RDAntiAliasing::None,
+        }
+    }
+
+    pub fn size(mut self, size: f32) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn fill(mut self, color: RDColor) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn line_height(mut self, line_height: f32) -> Self {
+        self.layout.line_height = line_height;
+        self
+    }
+
+    pub fn max_width(mut self, width: f32) -> Self {
+        self.layout.max_width = Some(width);
+        self
+    }
+
+    pub fn max_height(mut self, height: f32) -> Self {
+        self.layout.max_height = Some(height);
+        self
+    }
+
+    pub fn wrap(mut self, wrap: RDWrap) -> Self {
+        self.layout.wrap = wrap;
+        self
+    }
+
+    pub fn align(mut self, align: RDAlign) -> Self {
+        self.layout.align = align;
+        self
+    }
+
+    pub fn ellipsis(mut self, ellipsis: bool) -> Self {
+        self.layout.ellipsis = ellipsis;
+        self
+    }
+
+    pub fn anti_alias(mut self, mode: RDAntiAliasing) -> Self {
+        self.anti_aliasing = mode;
+        self
+    }
+
+    //lines, carets and hit testing for the text as it is drawn
+    pub fn layout(&self) -> RDTextLayout {
+        RDTextLayout::new(self.font, &self.text, self.size, &self.layout)
+    }
+
+    fn glyphs(&self) -> Vec<RDGlyph> {
+        self.layout().glyphs().copied().collect()
+    }
+
+    //every glyph of every line in one path, counters are cut out by the even odd rule
+    pub fn to_path(&self) -> RDPath {
+        let mut path = RDPath::new()
+            .fill(self.color.clone())
+            .stroke(RDStroke { weight: 0.0, color: RDColor::TRANSPARENT })
+            .fill_rule(RDFillRule::EvenOdd)
+            .anti_alias(self.anti_aliasing);
+
+        for glyph in self.glyphs() {
+            path = self.font.append_glyph(path, glyph.id, self.size, glyph.position);
+        }
+
+        path
+    }
+
+    pub fn to_node(&self) -> RDNode {
+        self.to_path().to_node()
+    }
+
+    //a quad per glyph shaded from the distance fields in the atlas, far cheaper than tessellating outlines
+    //the atlas has to be the glyph atlas of the scene the node goes into, exporters still get the outlines
+    pub fn to_sdf_node(&self, atlas: &mut RDGlyphAtlas) -> RDNode {
+        let mut vao = VAO::new();
+
+        for glyph in self.glyphs() {
+            let Some(entry) = atlas.glyph(self.font, glyph.id) else { continue };
+
+            let min = glyph.position + entry.bounds.min * self.size;
+            let max = glyph.position + entry.bounds.max * self.size;
+            //the atlas has its rows top down, so the top of the quad takes the smaller texture y
+            let corners = [
+                (Vector2::new(min.x, min.y), Vector2::new(entry.texels.min.x, entry.texels.max.y)),
+                (Vector2::new(max.x, min.y), entry.texels.max),
+                (Vector2::new(max.x, max.y), Vector2::new(entry.texels.max.x, entry.texels.min.y)),
+                (Vector2::new(min.x, max.y), entry.texels.min),
+            ];
+
+            let first = vao.vertices.len() as u32;
+            vao.indicies.extend([0, 1, 2, 0, 2, 3].map(|i| first + i));
+            vao.vertices.extend(corners.map(|(position, texture_position)| RDVertex {
+                id: 0,
+                position: position.into(),
+                texture_position: texture_position.into(),
+                color: self.color.to_u32(),
+            }));
+        }
+
+        RDNode::with_mesh(RDMesh {
+            texture: GLYPH_ATLAS_TEXTURE,
+            vao,
+            source: Some(RDMeshSource::Fill(Arc::new(self.to_path()))),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Vector2;
+
+    use crate::{atlas::{RDGlyphAtlas, GLYPH_ATLAS_TEXTURE}, color::RDColor, font::tests::truetype_font, scene::RDMeshSource};
+
+    use super::RDText;
+
+    #[test]
+    fn test_text() {
+        let font = truetype_font();
+        let text = RDText::new(&font, "o\nq").size(10.0).fill(RDColor::RED);
+
+        //the second line is a line height further down and the composite brings its own two contours
+        let path = text.to_path();
+        let starts: Vec<_> = path.subpaths().iter().map(|subpath| subpath.start).collect();
+        assert_eq!(starts, [
+            Vector2::new(0.0, 0.0),
+            Vector2::new(2.0, 2.0),
+            Vector2::new(7.0, -11.0),
+            Vector2::new(8.0, -10.0),
+        ]);
+        assert!(path.subpaths().iter().all(|subpath| subpath.closed));
+
+        //the counters are left out, 36 - 4 square units for the 'o' and a quarter of that for the 'q'
+        let node = text.to_node();
+        let vao = &node.children[0].mesh.as_ref().unwrap().vao;
+        let area: f32 = vao.indicies.chunks_exact(3).map(|triangle| {
+            let [a, b, c] = [0, 1, 2].map(|i| Vector2::from(vao.vertices[triangle[i] as usize].position));
+            (b - a).perp_dot(c - a) * 0.5
+        }).sum();
+        assert!((area - 40.0).abs() < 1e-3, "{area}");
+        assert!(vao.vertices.iter().all(|vertex| vertex.color == RDColor::RED.to_u32()));
+
+        //wrapped onto a second line the same way a newline would
+        let wrapped = RDText::new(&font, "o o").size(10.0).max_width(10.0).to_path();
+        assert_eq!(wrapped.subpaths()[2].start, Vector2::new(0.0, -11.0));
+    }
+
+    #[test]
+    fn test_sdf_text() {
+        let font = truetype_font();
+        let mut atlas = RDGlyphAtlas::new();
+        let node = RDText::new(&font, "o o").size(10.0).fill(RDColor::RED).to_sdf_node(&mut atlas);
+
+        //the space has no outline and no quad, both squares share one spot in the atlas
+        let mesh = node.mesh.as_ref().unwrap();
+        assert_eq!(mesh.texture, GLYPH_ATLAS_TEXTURE);
+        assert_eq!((mesh.vao.vertices.len(), mesh.vao.indicies.len()), (8, 12));
+        assert_eq!(mesh.vao.indicies[6..], [4, 5, 6, 4, 6, 7]);
+
+        //the glyph's padded area, 0.6 em rounded out to whole atlas pixels plus the spread, the bottom left corner
+        //takes the bottom row of the atlas area
+        let corners: Vec<_> = mesh.vao.vertices.iter().map(|vertex| (vertex.position, vertex.texture_position)).collect();
+        assert_eq!(corners[0], ([-1.25, -1.25], [1.0, 29.0]));
+        assert_eq!(corners[2], ([7.5, 7.5], [29.0, 1.0]));
+        assert_eq!(corners[4], ([10.75, -1.25], [1.0, 29.0]));
+        assert!(mesh.vao.vertices.iter().all(|vertex| vertex.color == RDColor::RED.to_u32()));
+
+        //exporters write the outlines instead of the quads
+        assert!(matches!(&mesh.source, Some(RDMeshSource::Fill(path)) if path.subpaths().len() == 4));
+    }
+}
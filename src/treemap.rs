@@ -1,4 +1,4 @@
-use std::{collections::HashMap, sync::Arc, usize};
+use std::{collections::HashMap, sync::Arc};
 
 type MapElement<T> = (Arc<str>, T, Vec<usize>, usize);
 
@@ -29,7 +29,7 @@ impl<T> TreeMap<T> {
 
     //cant assume last isn't also being removed
     fn fill_empty(&mut self, replaced_idx: usize) {
-        debug_assert!(self.elements.len() != 0);
+        debug_assert!(!self.elements.is_empty());
 
         self.elements[replaced_idx] = self.elements.pop().unwrap();
         
@@ -70,8 +70,7 @@ impl<T> TreeMap<T> {
         //collect all to remove
         let mut removal_indicies = vec![];
         let mut stack = vec![root_index];
-        while stack.len() > 0 {
-            let index = stack.pop().unwrap();
+        while let Some(index) = stack.pop() {
             stack.append(&mut self.elements[index].2.clone());
             removal_indicies.push(index);
         }
@@ -90,7 +89,7 @@ impl<T> TreeMap<T> {
     }
 
     pub fn get(&self, element_id: &str) -> Option<&T> {
-        let Some(idx) = self.map.get(element_id) else { return None };
+        let idx = self.map.get(element_id)?;
         
         debug_assert!(*idx < self.elements.len());
 
@@ -98,7 +97,7 @@ impl<T> TreeMap<T> {
     }
 
     pub fn traverse<'a, U, F: FnMut(TreeTraverser<'a, T>) -> U>(&'a self, starting_id: &str, mut traverser: F) -> Option<U> {
-        let Some(idx) = self.map.get(starting_id) else { return None };
+        let idx = self.map.get(starting_id)?;
         
         debug_assert!(*idx < self.elements.len());
 
@@ -106,8 +105,11 @@ impl<T> TreeMap<T> {
     }
 }
 
+//only walked by the tests so far
+#[allow(dead_code)]
 pub struct TreeTraverser<'a, T>(&'a TreeMap<T>, usize);
 
+#[allow(dead_code)]
 impl<'a, T> TreeTraverser<'a, T> {
     fn parent(&self) -> TreeTraverser<'a, T> {
         debug_assert_ne!(self.0.elements[self.1].3, usize::MAX);
@@ -121,7 +123,7 @@ impl<'a, T> TreeTraverser<'a, T> {
         Some(self.0.elements[self.1].0.clone())
     }
     
-    fn children(&self) -> impl Iterator<Item = TreeTraverser<T>> {
+    fn children(&self) -> impl Iterator<Item = TreeTraverser<'_, T>> {
         self.0.elements[self.1].2.iter().map(|idx| TreeTraverser(self.0, *idx))
     }
 }
@@ -131,6 +133,8 @@ mod tests {
     use super::TreeMap;
 
     #[test]
+    //the nested loops each run over a single child and return from the innermost one
+    #[allow(clippy::never_loop, unused_variables)]
     fn test_tree() {
         let mut tree_map = TreeMap::new("root", 32);        
 
@@ -1,6 +1,6 @@
 use cgmath::{InnerSpace, Vector2};
 
-use crate::path_builder::RDStroke;
+use crate::{math::RDRect, path_builder::RDStroke};
 
 fn intersect_lines(a: Vector2<f32>, b: Vector2<f32>, c: Vector2<f32>, d: Vector2<f32>) -> Option<Vector2<f32>> {
     let slope_ab = (b.y - a.y) / (b.x - a.x);
@@ -13,12 +13,12 @@ fn intersect_lines(a: Vector2<f32>, b: Vector2<f32>, c: Vector2<f32>, d: Vector2
     let intercept_ab = a.y - slope_ab * a.x;
     let intercept_cd = c.y - slope_cd * c.x;    
     
-    if slope_ab == f32::INFINITY || slope_ab == f32::NEG_INFINITY {
+    if slope_ab.is_infinite() {
         let y = slope_cd * a.x + intercept_cd;
         return Some(Vector2::new(a.x, y));
     }
     
-    if slope_cd == f32::INFINITY || slope_cd == f32::NEG_INFINITY {
+    if slope_cd.is_infinite() {
         let y = slope_ab * c.x + intercept_ab;
         return Some(Vector2::new(c.x, y));
     }
@@ -37,6 +37,31 @@ pub(crate) fn perp_right(v: Vector2<f32>) -> Vector2<f32> {
     Vector2::new(v.y, -v.x)
 }
 
+//the two mitered corners of the stroke around b, on the right of the direction of travel first
+fn stroke_corner(a: Vector2<f32>, b: Vector2<f32>, c: Vector2<f32>, stroke: &RDStroke) -> [Vector2<f32>; 2] {
+    let offset_a = perp_left(a-b).normalize() * stroke.weight * 0.5;
+    let offset_c = perp_right(c-b).normalize() * stroke.weight * 0.5;
+
+    let intersect1 = intersect_lines(a + offset_a, b + offset_a, c + offset_c, b + offset_c);
+    let intersect2 = intersect_lines(a - offset_a, b - offset_a, c - offset_c, b - offset_c);
+
+    //parallel edges, which flattened curves produce often, meet right at the offset corner
+    [intersect1.unwrap_or(b + offset_a), intersect2.unwrap_or(b - offset_a)]
+}
+
+//quads between each pair of consecutive corners
+fn stroke_indicies(segments: u32, indicies: &mut Vec<u32>) {
+    for i in 0..segments {
+        indicies.push(1 + i * 2);
+        indicies.push(i * 2);
+        indicies.push(3 + i * 2);
+        
+        indicies.push(3 + i * 2);
+        indicies.push(i * 2);
+        indicies.push(2 + i * 2);
+    }
+}
+
 pub(crate) fn triangulate_stroke(points: &[Vector2<f32>], stroke: &RDStroke) -> (Vec<Vector2<f32>>, Vec<u32>) {
     let mut vertices = vec![];
     let mut indicies = vec![];
@@ -45,34 +70,10 @@ pub(crate) fn triangulate_stroke(points: &[Vector2<f32>], stroke: &RDStroke) ->
         let last_idx = if i != 0 { i - 1 } else { points.len() - 1 };
         let next_idx = if i + 1 == points.len() { 0 } else { i + 1 }; 
 
-        let a: Vector2<f32> = points[last_idx];
-        let b: Vector2<f32> = points[i];
-        let c: Vector2<f32> = points[next_idx];
-
-        let offset_a = perp_left(a-b).normalize() * stroke.weight * 0.5;
-        let offset_c = perp_right(c-b).normalize() * stroke.weight * 0.5;
-
-        //println!("{:?}", offset_c);
-        //println!("{:?}", offset_a);
-
-        let intersect1 = intersect_lines(a + offset_a, b + offset_a, c + offset_c, b + offset_c);
-        let intersect2 = intersect_lines(a - offset_a, b - offset_a, c - offset_c, b - offset_c);
-
-        vertices.push(intersect1.unwrap());
-        vertices.push(intersect2.unwrap());
+        vertices.extend(stroke_corner(points[last_idx], points[i], points[next_idx], stroke));
     }
 
-    for i in 0..vertices.len()/2-1 {
-        let i: u32 = i as u32;
-
-        indicies.push(1 + i * 2);
-        indicies.push(0 + i * 2);
-        indicies.push(3 + i * 2);
-        
-        indicies.push(3 + i * 2);
-        indicies.push(0 + i * 2);
-        indicies.push(2 + i * 2);
-    }
+    stroke_indicies(vertices.len() as u32 / 2 - 1, &mut indicies);
 
     let len_u32: u32 = vertices.len() as u32;
 
@@ -87,19 +88,136 @@ pub(crate) fn triangulate_stroke(points: &[Vector2<f32>], stroke: &RDStroke) ->
     (vertices, indicies)
 }
 
-//1. no colinear edges
-//2. no clockwise verticies
+//same corners as a closed stroke but the ends are cut off square at the first and last point
+pub(crate) fn triangulate_open_stroke(points: &[Vector2<f32>], stroke: &RDStroke) -> (Vec<Vector2<f32>>, Vec<u32>) {
+    let mut vertices = vec![];
+    let mut indicies = vec![];
+
+    if points.len() < 2 {
+        return (vertices, indicies);
+    }
+
+    for i in 0..points.len() {
+        if i == 0 || i + 1 == points.len() {
+            let direction = if i == 0 { points[1] - points[0] } else { points[i] - points[i - 1] };
+            let offset = perp_right(direction).normalize() * stroke.weight * 0.5;
+
+            vertices.extend([points[i] + offset, points[i] - offset]);
+        } else {
+            vertices.extend(stroke_corner(points[i - 1], points[i], points[i + 1], stroke));
+        }
+    }
+
+    stroke_indicies(points.len() as u32 - 1, &mut indicies);
+
+    (vertices, indicies)
+}
+
+//splits every triangle into four until no edge is longer than max_edge, for colors that change across a triangle
+//the depth is capped so a tiny max_edge can't explode the vertex count
+pub(crate) fn subdivide(points: &[Vector2<f32>], indicies: &[u32], max_edge: f32) -> (Vec<Vector2<f32>>, Vec<u32>) {
+    const MAX_DEPTH: u32 = 5;
+
+    fn split(corners: [Vector2<f32>; 3], max_edge: f32, depth: u32, points: &mut Vec<Vector2<f32>>, indicies: &mut Vec<u32>) {
+        let [a, b, c] = corners;
+        let longest = (b - a).magnitude().max((c - b).magnitude()).max((a - c).magnitude());
+
+        if depth == MAX_DEPTH || longest <= max_edge {
+            indicies.extend((0..3).map(|i| points.len() as u32 + i));
+            points.extend(corners);
+            return;
+        }
+
+        let (ab, bc, ca) = ((a + b) * 0.5, (b + c) * 0.5, (c + a) * 0.5);
+        for corners in [[a, ab, ca], [ab, b, bc], [ca, bc, c], [ab, bc, ca]] {
+            split(corners, max_edge, depth + 1, points, indicies);
+        }
+    }
+
+    let mut output = (vec![], vec![]);
+    for triangle in indicies.chunks_exact(3) {
+        let corners = [0, 1, 2].map(|i| points[triangle[i] as usize]);
+        split(corners, max_edge, 0, &mut output.0, &mut output.1);
+    }
+
+    output
+}
+
+pub(crate) fn signed_area(points: &[Vector2<f32>]) -> f32 {
+    let mut area = 0.0;
+
+    for i in 0..points.len() {
+        let next_idx = if i + 1 == points.len() { 0 } else { i + 1 };
+        area += points[i].perp_dot(points[next_idx]);
+    }
+
+    area * 0.5
+}
+
+//extrudes a closed ring by width, every even vertex lies on the ring and every odd one is pushed
+//out (or in towards the interior) so the caller can fade its alpha to zero across the strip
+pub(crate) fn triangulate_fringe(points: &[Vector2<f32>], width: f32, outward: bool) -> (Vec<Vector2<f32>>, Vec<u32>) {
+    const MITER_LIMIT: f32 = 4.0;
+
+    let mut vertices = vec![];
+    let mut indicies = vec![];
+
+    //a counter clockwise ring has its interior on the left of every edge
+    let direction = if (signed_area(points) > 0.0) == outward { 1.0 } else { -1.0 };
+
+    for i in 0..points.len() {
+        let last_idx = if i != 0 { i - 1 } else { points.len() - 1 };
+        let next_idx = if i + 1 == points.len() { 0 } else { i + 1 };
+
+        let a = points[last_idx];
+        let b = points[i];
+        let c = points[next_idx];
+
+        let normal_ab = perp_right(b - a).normalize();
+        let normal_bc = perp_right(c - b).normalize();
+
+        let miter = normal_ab + normal_bc;
+        let miter = if miter.magnitude2() < 1e-6 { normal_ab } else { miter.normalize() };
+        let scale = (1.0 / miter.dot(normal_ab)).min(MITER_LIMIT);
+
+        vertices.push(b);
+        vertices.push(b + miter * width * scale * direction);
+    }
+
+    for i in 0..points.len() as u32 {
+        let j = if i + 1 == points.len() as u32 { 0 } else { i + 1 };
+
+        for triangle in [[i * 2, i * 2 + 1, j * 2 + 1], [i * 2, j * 2 + 1, j * 2]] {
+            let [a, b, c] = triangle.map(|idx| vertices[idx as usize]);
+
+            if (b - a).perp_dot(c - a) < 0.0 {
+                indicies.extend([triangle[0], triangle[2], triangle[1]]);
+            } else {
+                indicies.extend(triangle);
+            }
+        }
+    }
+
+    (vertices, indicies)
+}
+
+//ear clipping, either winding works but the outline mustn't cross itself
 pub(crate) fn triangulate(vertices: &[Vector2<f32>]) -> Vec<u32> {
-    let mut vertex_ids = vec![];
+    let mut vertex_ids: Vec<usize> = (0..vertices.len()).collect();
     let mut indicies = vec![];
 
-    for i in 0..vertices.len() {
-        vertex_ids.push(i);
+    //the clipper expects counter clockwise outlines, clockwise ones are walked backwards
+    if signed_area(vertices) < 0.0 {
+        vertex_ids.reverse();
     }
 
     let mut id: usize = 0;
+    //vertices tried since the last ear, a self intersecting outline can run out of them
+    let mut misses = 0;
+
+    'ear_clipper: while vertex_ids.len() > 2 && misses < vertex_ids.len() {
+        misses += 1;
 
-    'ear_clipper: while vertex_ids.len() > 2 {
         let last_id = if id == 0 { vertex_ids.len() - 1 } else { id - 1 };
         let next_id = if id + 1 == vertex_ids.len() { 0 } else { id + 1 };
 
@@ -113,8 +231,12 @@ pub(crate) fn triangulate(vertices: &[Vector2<f32>]) -> Vec<u32> {
             continue 'ear_clipper;
         }
 
+        //points on the ear's edges block it too, collinear runs like the serifs of glyphs would get cut across otherwise
+        //copies of a corner come from hole bridges and don't count
         for i in 0..vertex_ids.len() {
-            if within_triangle(last, now, next, vertices[vertex_ids[i]]) {
+            let point = vertices[vertex_ids[i]];
+
+            if point != last && point != now && point != next && touches_triangle(last, now, next, point) {
                 id += 1;
                 id %= vertex_ids.len();
                 continue 'ear_clipper;
@@ -125,11 +247,131 @@ pub(crate) fn triangulate(vertices: &[Vector2<f32>]) -> Vec<u32> {
         indicies.push(vertex_ids[id] as u32);
         indicies.push(vertex_ids[next_id] as u32);
         vertex_ids.remove(id);
+        id %= vertex_ids.len();
+        misses = 0;
     }
 
     indicies
 }
 
+//crossing test, a point is inside when a ray from it crosses the ring an odd number of times
+pub(crate) fn ring_contains(ring: &[Vector2<f32>], point: Vector2<f32>) -> bool {
+    let mut inside = false;
+
+    for i in 0..ring.len() {
+        let a = ring[i];
+        let b = ring[if i + 1 == ring.len() { 0 } else { i + 1 }];
+
+        if (a.y > point.y) != (b.y > point.y) && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+            inside = !inside;
+        }
+    }
+
+    inside
+}
+
+//for the even odd rule, rings inside an odd number of others are holes
+//each hole gets the ring directly around it as its parent, everything else is filled and has none
+//only rings wholly inside another count as nested, ones that cross like overlapping accents are both filled
+pub(crate) fn even_odd_parents(rings: &[&[Vector2<f32>]]) -> Vec<Option<usize>> {
+    let bounds: Vec<_> = rings.iter().map(|ring| RDRect::from_points(ring.iter().copied())).collect();
+
+    let contains = |outer: usize, inner: usize| match (bounds[outer], bounds[inner]) {
+        (Some(outer_bounds), Some(inner_bounds)) => outer != inner
+            && rings[outer].len() > 2
+            && outer_bounds.contains(inner_bounds.min) && outer_bounds.contains(inner_bounds.max)
+            && rings[inner].iter().all(|point| ring_contains(rings[outer], *point)),
+        _ => false,
+    };
+
+    let containers: Vec<Vec<usize>> = (0..rings.len()).map(|i| (0..rings.len()).filter(|j| contains(*j, i)).collect()).collect();
+
+    (0..rings.len()).map(|i| {
+        let depth = containers[i].len();
+
+        if depth.is_multiple_of(2) {
+            return None;
+        }
+
+        containers[i].iter().copied()
+            .filter(|j| containers[*j].len() == depth - 1)
+            .min_by(|a, b| signed_area(rings[*a]).abs().total_cmp(&signed_area(rings[*b]).abs()))
+    }).collect()
+}
+
+//whether segment ab crosses any edge of the ring, edges that share an end with it don't count
+fn crosses_ring(a: Vector2<f32>, b: Vector2<f32>, ring: &[Vector2<f32>]) -> bool {
+    let side = |p: Vector2<f32>, q: Vector2<f32>, r: Vector2<f32>| (q - p).perp_dot(r - p);
+
+    (0..ring.len()).any(|i| {
+        let c = ring[i];
+        let d = ring[if i + 1 == ring.len() { 0 } else { i + 1 }];
+
+        if [c, d].iter().any(|point| *point == a || *point == b) {
+            return false;
+        }
+
+        side(a, b, c) * side(a, b, d) < 0.0 && side(c, d, a) * side(c, d, b) < 0.0
+    })
+}
+
+//cuts each hole into the outline along a bridge from its rightmost point to the closest outline point it can see,
+//the result walks out along the bridge, around the hole and back, so the ear clipper sees a single ring
+pub(crate) fn bridge_holes(outline: &[Vector2<f32>], holes: &[&[Vector2<f32>]]) -> Vec<Vector2<f32>> {
+    let rightmost = |ring: &[Vector2<f32>]| (0..ring.len()).max_by(|a, b| ring[*a].x.total_cmp(&ring[*b].x)).unwrap_or(0);
+
+    //the outline counter clockwise and the holes the other way around
+    let mut polygon = outline.to_vec();
+    if signed_area(&polygon) < 0.0 {
+        polygon.reverse();
+    }
+
+    let mut holes: Vec<Vec<Vector2<f32>>> = holes.iter().filter(|hole| hole.len() > 2).map(|hole| {
+        let mut hole = hole.to_vec();
+        if signed_area(&hole) > 0.0 {
+            hole.reverse();
+        }
+        hole
+    }).collect();
+
+    //right to left, so a bridge never has to pass a hole that isn't part of the outline yet
+    holes.sort_by(|a, b| b[rightmost(b)].x.total_cmp(&a[rightmost(a)].x));
+
+    for (i, hole) in holes.iter().enumerate() {
+        let start = rightmost(hole);
+        let point = hole[start];
+
+        //closest first, points to the right of the hole are preferred like in earcut
+        let mut candidates: Vec<usize> = (0..polygon.len()).collect();
+        candidates.sort_by(|a, b| {
+            let key = |index: usize| (polygon[index].x < point.x, (polygon[index] - point).magnitude2());
+            let (a, b) = (key(*a), key(*b));
+            a.0.cmp(&b.0).then(a.1.total_cmp(&b.1))
+        });
+
+        let visible = candidates.into_iter().find(|candidate| {
+            let target = polygon[*candidate];
+            !crosses_ring(point, target, &polygon) && holes[i..].iter().all(|hole| !crosses_ring(point, target, hole))
+        });
+
+        //a hole that can't be reached is left filled
+        let Some(bridge) = visible else { continue };
+
+        let around = hole[start..].iter().chain(hole[..=start].iter()).copied();
+        let merged: Vec<_> = polygon[..=bridge].iter().copied().chain(around).chain(polygon[bridge..].iter().copied()).collect();
+        polygon = merged;
+    }
+
+    polygon
+}
+
+//within_triangle with the edges included
+fn touches_triangle(a: Vector2<f32>, b: Vector2<f32>, c: Vector2<f32>, p: Vector2<f32>) -> bool {
+    let on_edge = |start: Vector2<f32>, end: Vector2<f32>| (p - start).perp_dot(end - start) == 0.0 && (p - start).dot(p - end) <= 0.0;
+
+    within_triangle(a, b, c, p) || on_edge(a, b) || on_edge(b, c) || on_edge(c, a)
+}
+
 pub(crate) fn within_triangle(a: Vector2<f32>, b: Vector2<f32>, c: Vector2<f32>, p: Vector2<f32>) -> bool {
     (p - a).perp_dot(b - a) < 0.0 && (p - b).perp_dot(c - b) < 0.0 && (p - c).perp_dot(a - c) < 0.0
 }
@@ -153,6 +395,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_within_triangle() {
         assert_eq!( within_triangle( Vector2::new(0.0, 0.0), Vector2::new(0.0, 10.0), Vector2::new(-10.0, 10.0), Vector2::new(-5.0, 2.5)), false);
         assert_eq!( within_triangle( Vector2::new(0.0, 0.0), Vector2::new(0.0, 10.0), Vector2::new(-10.0, 10.0), Vector2::new(-2.5, 5.0)), true);
@@ -164,6 +407,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::useless_vec)]
     fn test_triangulate_stroke() {
         assert_eq!(triangulate_stroke(&vec![
             Vector2::new(0.0, 0.0),
@@ -223,6 +467,71 @@ mod tests {
     }
     
     #[test]
+    fn test_triangulate_fringe() {
+        let square = [
+            Vector2::new(0.0, 0.0),
+            Vector2::new(10.0, 0.0),
+            Vector2::new(10.0, 10.0),
+            Vector2::new(0.0, 10.0),
+        ];
+
+        let (vertices, indicies) = triangulate_fringe(&square, 1.0, true);
+
+        let expected = [
+            Vector2::new(0.0, 0.0),
+            Vector2::new(-1.0, -1.0),
+            Vector2::new(10.0, 0.0),
+            Vector2::new(11.0, -1.0),
+            Vector2::new(10.0, 10.0),
+            Vector2::new(11.0, 11.0),
+            Vector2::new(0.0, 10.0),
+            Vector2::new(-1.0, 11.0),
+        ];
+
+        assert_eq!(vertices.len(), expected.len());
+        for (vertex, expected) in vertices.iter().zip(expected.iter()) {
+            assert!((vertex - expected).magnitude() < 1e-4);
+        }
+
+        assert_eq!(indicies.len(), 24);
+        for triangle in indicies.chunks(3) {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|idx| vertices[idx as usize]);
+            assert!((b - a).perp_dot(c - a) > 0.0);
+        }
+
+        let (vertices, _) = triangulate_fringe(&square, 1.0, false);
+        assert!((vertices[1] - Vector2::new(1.0, 1.0)).magnitude() < 1e-4);
+    }
+
+    #[test]
+    fn test_bridge_holes() {
+        let outline = [Vector2::new(0.0, 0.0), Vector2::new(10.0, 0.0), Vector2::new(10.0, 10.0), Vector2::new(0.0, 10.0)];
+        let hole = [Vector2::new(3.0, 3.0), Vector2::new(7.0, 3.0), Vector2::new(7.0, 7.0), Vector2::new(3.0, 7.0)];
+        let island = [Vector2::new(4.0, 4.0), Vector2::new(6.0, 4.0), Vector2::new(6.0, 6.0), Vector2::new(4.0, 6.0)];
+
+        assert_eq!(even_odd_parents(&[&outline, &hole, &island]), [None, Some(0), None]);
+
+        let polygon = bridge_holes(&outline, &[&hole]);
+        assert_eq!(polygon.len(), outline.len() + hole.len() + 2);
+
+        //the ring with its hole covers the area between the squares, nothing inside the hole
+        let indicies = triangulate(&polygon);
+        let area: f32 = indicies.chunks_exact(3)
+            .map(|triangle| {
+                let [a, b, c] = [0, 1, 2].map(|i| polygon[triangle[i] as usize]);
+                (b - a).perp_dot(c - a) * 0.5
+            })
+            .sum();
+        assert!((area - 84.0).abs() < 1e-3, "{area}");
+
+        for triangle in indicies.chunks_exact(3) {
+            let center = [0, 1, 2].map(|i| polygon[triangle[i] as usize]).iter().sum::<Vector2<f32>>() / 3.0;
+            assert!(!ring_contains(&hole, center));
+        }
+    }
+
+    #[test]
+    #[allow(clippy::useless_vec)]
     fn test_triangulate() {
         assert_eq!(triangulate(&vec![
             Vector2::new(0.0, 0.0),
@@ -238,5 +547,13 @@ mod tests {
             Vector2::new(5.01, 5.01),
             Vector2::new(0.0, 10.0),
         ]), vec![4, 0, 1,  1, 2, 3,  1, 3, 4]);
+
+        //the same square wound clockwise
+        assert_eq!(triangulate(&vec![
+            Vector2::new(0.0, 10.0),
+            Vector2::new(10.0, 10.0),
+            Vector2::new(10.0, 0.0),
+            Vector2::new(0.0, 0.0),
+        ]), vec![0, 3, 2, 0, 2, 1]);
     }
 }
@@ -0,0 +1,259 @@
+//just enough xml for svg files, text content, namespaces and dtds are skipped over
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct XmlElement {
+    pub(crate) name: String,
+    pub(crate) attributes: Vec<(String, String)>,
+    pub(crate) children: Vec<XmlElement>,
+}
+
+impl XmlElement {
+    pub(crate) fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+
+    //the name without its namespace prefix, "svg:path" and "path" are the same element
+    pub(crate) fn local_name(&self) -> &str {
+        self.name.rsplit(':').next().unwrap_or(&self.name)
+    }
+
+    pub(crate) fn descendants(&self) -> Vec<&XmlElement> {
+        let mut elements = vec![];
+        let mut stack = vec![self];
+
+        while let Some(element) = stack.pop() {
+            elements.push(element);
+            stack.extend(element.children.iter().rev());
+        }
+
+        elements
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct XmlError {
+    //in bytes from the start of the document
+    pub(crate) offset: usize,
+    pub(crate) message: &'static str,
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.position..]
+    }
+
+    fn error(&self, message: &'static str) -> XmlError {
+        XmlError { offset: self.position, message }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    //moves past the next occurence of the terminator
+    fn skip_past(&mut self, terminator: &str) -> Result<(), XmlError> {
+        let Some(index) = self.rest().find(terminator) else { return Err(self.error("unterminated markup")) };
+        self.position += index + terminator.len();
+        Ok(())
+    }
+
+    fn name(&mut self) -> Result<&'a str, XmlError> {
+        let rest = self.rest();
+        let length = rest.find(|c: char| c.is_whitespace() || matches!(c, '/' | '>' | '=')).unwrap_or(rest.len());
+
+        if length == 0 {
+            return Err(self.error("expected a name"));
+        }
+
+        self.position += length;
+        Ok(&rest[..length])
+    }
+
+    //comments, processing instructions, doctypes and text between elements
+    fn skip_misc(&mut self) -> Result<(), XmlError> {
+        loop {
+            let rest = self.rest();
+
+            if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with("<![CDATA[") {
+                self.skip_past("]]>")?;
+            } else if rest.starts_with("<!") {
+                //a doctype can carry an internal subset in brackets
+                let end = match (rest.find('['), rest.find('>')) {
+                    (Some(bracket), Some(close)) if bracket < close => "]>",
+                    _ => ">",
+                };
+                self.skip_past(end)?;
+            } else if let Some(index) = rest.find('<').filter(|index| *index > 0) {
+                self.position += index;
+            } else if !rest.starts_with('<') {
+                self.position = self.text.len();
+                return Ok(());
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn element(&mut self, depth: usize) -> Result<XmlElement, XmlError> {
+        const MAX_DEPTH: usize = 256;
+
+        if depth > MAX_DEPTH {
+            return Err(self.error("elements nested too deeply"));
+        }
+
+        self.position += 1;
+        let name = self.name()?.to_string();
+        let mut attributes = vec![];
+
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+
+            if rest.starts_with("/>") {
+                self.position += 2;
+                return Ok(XmlElement { name, attributes, children: vec![] });
+            }
+            if rest.starts_with('>') {
+                self.position += 1;
+                break;
+            }
+
+            let key = self.name()?.to_string();
+            self.skip_whitespace();
+            if !self.rest().starts_with('=') {
+                return Err(self.error("expected '=' after an attribute name"));
+            }
+            self.position += 1;
+            self.skip_whitespace();
+
+            let quote = self.rest().chars().next().filter(|c| matches!(c, '"' | '\''));
+            let Some(quote) = quote else { return Err(self.error("expected a quoted attribute value")) };
+            self.position += 1;
+
+            let Some(length) = self.rest().find(quote) else { return Err(self.error("unterminated attribute value")) };
+            let value = decode_entities(&self.rest()[..length]);
+            self.position += length + 1;
+
+            attributes.push((key, value));
+        }
+
+        let mut children = vec![];
+
+        loop {
+            self.skip_misc()?;
+            let rest = self.rest();
+
+            if rest.is_empty() {
+                return Err(self.error("unclosed element"));
+            }
+
+            if let Some(rest) = rest.strip_prefix("</") {
+                if !rest.starts_with(name.as_str()) {
+                    return Err(self.error("mismatched closing tag"));
+                }
+                self.position += 2 + name.len();
+                self.skip_whitespace();
+
+                if !self.rest().starts_with('>') {
+                    return Err(self.error("expected '>'"));
+                }
+                self.position += 1;
+
+                return Ok(XmlElement { name, attributes, children });
+            }
+
+            children.push(self.element(depth + 1)?);
+        }
+    }
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let Some(end) = rest.find(';') else { break };
+        let entity = &rest[1..end];
+
+        let character = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity.strip_prefix("#x").or(entity.strip_prefix("#X"))
+                .map(|hex| u32::from_str_radix(hex, 16).ok())
+                .unwrap_or_else(|| entity.strip_prefix('#').and_then(|decimal| decimal.parse().ok()))
+                .and_then(char::from_u32),
+        };
+
+        //unknown entities are kept as they were written
+        match character {
+            Some(character) => decoded.push(character),
+            None => decoded.push_str(&rest[..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+
+    decoded.push_str(rest);
+    decoded
+}
+
+//the document's root element
+pub(crate) fn parse(text: &str) -> Result<XmlElement, XmlError> {
+    let mut parser = Parser { text, position: 0 };
+
+    parser.skip_misc()?;
+    if parser.rest().is_empty() {
+        return Err(parser.error("no root element"));
+    }
+
+    let root = parser.element(0)?;
+
+    parser.skip_misc()?;
+    if !parser.rest().is_empty() {
+        return Err(parser.error("content after the root element"));
+    }
+
+    Ok(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let root = parse(r#"<?xml version="1.0"?>
+            <!DOCTYPE svg PUBLIC "-//W3C//DTD SVG 1.1//EN" "http://www.w3.org/Graphics/SVG/1.1/DTD/svg11.dtd" [ <!ENTITY ns "x"> ]>
+            <!-- a comment -->
+            <svg:svg a = 'one' b="&lt;&#65;&#x42;&amp;&unknown;">
+                text <![CDATA[ <not-an-element/> ]]>
+                <g><path d="M0 0"/></g>
+                <rect/>
+            </svg:svg>
+        "#).unwrap();
+
+        assert_eq!(root.local_name(), "svg");
+        assert_eq!(root.attribute("a"), Some("one"));
+        assert_eq!(root.attribute("b"), Some("<AB&&unknown;"));
+        assert_eq!(root.descendants().iter().map(|element| element.name.as_str()).collect::<Vec<_>>(), ["svg:svg", "g", "path", "rect"]);
+
+        assert_eq!(parse("<svg><g></svg>").unwrap_err().message, "mismatched closing tag");
+        assert_eq!(parse("<svg a=b/>").unwrap_err(), XmlError { offset: 7, message: "expected a quoted attribute value" });
+        assert!(parse("<svg>").is_err() && parse("").is_err() && parse("<a/><b/>").is_err());
+    }
+}